@@ -1,27 +1,63 @@
 use crate::{
+    model::command,
     panic_info,
     usecase::{
+        clear_cache,
         fzf_make::{self, FzfMake},
-        help, history, invalid_arg, repeat, usecase_main, version,
+        global_history, help, history, invalid_arg, repeat, usecase_main, version,
     },
 };
 use colored::Colorize;
-use std::{collections::HashMap, env, sync::Arc};
+use std::{collections::HashMap, env, path::PathBuf, process, sync::Arc};
+
+const HISTORY_FILE_OPTION: &str = "--history-file";
+const NO_CACHE_OPTION: &str = "--no-cache";
 
 pub async fn run() {
     let command_line_args = env::args().collect();
-    let usecase = args_to_usecase(command_line_args);
+    let (usecase, options) = match parse_options(command_line_args) {
+        Some((args, options)) => (args_to_usecase(args), options),
+        None => (
+            Arc::new(invalid_arg::InvalidArg::new()) as Arc<dyn usecase_main::Usecase>,
+            usecase_main::Options::default(),
+        ),
+    };
 
-    if let Err(e) = usecase.run().await {
-        // If there was panic info of tui recorded, the error is printed
-        // by the panic hook defined in main.rs.
-        if panic_info::get_panic_info().is_none() {
-            print_error(&e);
+    match usecase.run(options).await {
+        // Exit with the same code as the executed command so that the failure can be detected by callers.
+        Ok(Some(status)) if !status.success() => process::exit(command::exit_code(status)),
+        Ok(_) => {}
+        Err(e) => {
+            // If there was panic info of tui recorded, the error is printed
+            // by the panic hook defined in main.rs.
+            if panic_info::get_panic_info().is_none() {
+                print_error(&e);
+            }
+            process::exit(1);
         }
-        std::process::exit(1);
     }
 }
 
+// parse_options takes the options out of `args` and returns the rest of them.
+// It returns None if the value of an option is missing.
+fn parse_options(args: Vec<String>) -> Option<(Vec<String>, usecase_main::Options)> {
+    let mut rest = vec![];
+    let mut options = usecase_main::Options::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == HISTORY_FILE_OPTION {
+            options.history_file = Some(PathBuf::from(args.next()?));
+        } else if let Some(value) = arg.strip_prefix(&format!("{}=", HISTORY_FILE_OPTION)) {
+            options.history_file = Some(PathBuf::from(value));
+        } else if arg == NO_CACHE_OPTION {
+            options.no_cache = true;
+        } else {
+            rest.push(arg);
+        }
+    }
+    Some((rest, options))
+}
+
 fn args_to_usecase(args: Vec<String>) -> Arc<dyn usecase_main::Usecase> {
     // Currently, only fzf-make or fzf-make ${command} is accepted.
     if 2 < args.len() {
@@ -47,6 +83,8 @@ fn usecases() -> HashMap<&'static str, Arc<dyn usecase_main::Usecase>> {
         Arc::new(fzf_make::FzfMake::new()),
         Arc::new(repeat::Repeat::new()),
         Arc::new(history::History::new()),
+        Arc::new(global_history::GlobalHistory::new()),
+        Arc::new(clear_cache::ClearCache::new()),
     ];
 
     let mut usecases_hash_map = HashMap::new();
@@ -60,5 +98,63 @@ fn usecases() -> HashMap<&'static str, Arc<dyn usecase_main::Usecase>> {
 }
 
 fn print_error(e: &anyhow::Error) {
-    println!("{}", e.to_string().red());
+    // The causes are printed as well(e.g. the line where the config file is invalid).
+    println!("{}", format!("{:#}", e).red());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_options_test() {
+        struct Case {
+            title: &'static str,
+            args: Vec<&'static str>,
+            expected: Option<(Vec<&'static str>, Option<&'static str>, bool)>,
+        }
+        let cases = vec![
+            Case {
+                title: "no option",
+                args: vec!["fzf-make", "repeat"],
+                expected: Some((vec!["fzf-make", "repeat"], None, false)),
+            },
+            Case {
+                title: "history file separated by whitespace",
+                args: vec!["fzf-make", "--history-file", "/tmp/history.toml", "repeat"],
+                expected: Some((vec!["fzf-make", "repeat"], Some("/tmp/history.toml"), false)),
+            },
+            Case {
+                title: "history file joined with `=`",
+                args: vec!["fzf-make", "history", "--history-file=/tmp/history.toml"],
+                expected: Some((vec!["fzf-make", "history"], Some("/tmp/history.toml"), false)),
+            },
+            Case {
+                title: "no cache",
+                args: vec!["fzf-make", "--no-cache", "history"],
+                expected: Some((vec!["fzf-make", "history"], None, true)),
+            },
+            Case {
+                title: "missing value",
+                args: vec!["fzf-make", "--history-file"],
+                expected: None,
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(
+                case.expected.map(|(args, history_file, no_cache)| (
+                    args.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                    usecase_main::Options {
+                        history_file: history_file.map(PathBuf::from),
+                        no_cache,
+                    }
+                )),
+                parse_options(case.args.iter().map(|a| a.to_string()).collect()),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
 }
@@ -0,0 +1,142 @@
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+const APP_NAME: &str = "fzf-make";
+
+/// AppDir is the set of the directories where fzf-make stores its files.
+/// They follow the XDG Base Directory Specification.
+#[derive(Debug, PartialEq)]
+pub struct AppDir {
+    /// `$XDG_CONFIG_HOME/fzf-make`(defaults to `~/.config/fzf-make`)
+    pub config: PathBuf,
+    /// `$XDG_STATE_HOME/fzf-make`(defaults to `~/.local/state/fzf-make`)
+    pub state: PathBuf,
+    /// `$XDG_CACHE_HOME/fzf-make`(defaults to `~/.cache/fzf-make`)
+    pub cache: PathBuf,
+    /// `~/.config/fzf-make`. fzf-make had stored all of its files here regardless of `XDG_CONFIG_HOME`.
+    pub legacy: Option<PathBuf>,
+}
+
+impl AppDir {
+    pub fn from_env() -> Option<Self> {
+        Self::new(|key| env::var_os(key), env::home_dir().as_deref())
+    }
+
+    // new returns None if the directories can not be decided because the home directory is unknown.
+    fn new(var: impl Fn(&str) -> Option<OsString>, home_dir: Option<&Path>) -> Option<Self> {
+        Some(Self {
+            config: base_dir(&var, "XDG_CONFIG_HOME", home_dir, ".config")?.join(APP_NAME),
+            state: base_dir(&var, "XDG_STATE_HOME", home_dir, ".local/state")?.join(APP_NAME),
+            cache: base_dir(&var, "XDG_CACHE_HOME", home_dir, ".cache")?.join(APP_NAME),
+            legacy: home_dir.map(|home_dir| home_dir.join(".config").join(APP_NAME)),
+        })
+    }
+}
+
+// base_dir returns the value of the environment variable `key`. `default` under the home directory is used
+// if it is not set, empty or relative path as the specification says.
+fn base_dir(
+    var: &impl Fn(&str) -> Option<OsString>,
+    key: &str,
+    home_dir: Option<&Path>,
+    default: &str,
+) -> Option<PathBuf> {
+    match var(key).map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => Some(dir),
+        _ => home_dir.map(|home_dir| home_dir.join(default)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn new_test() {
+        struct Case {
+            title: &'static str,
+            vars: Vec<(&'static str, &'static str)>,
+            home_dir: Option<&'static str>,
+            expected: Option<AppDir>,
+        }
+        let cases = vec![
+            Case {
+                title: "defaults to the directories under the home directory",
+                vars: vec![],
+                home_dir: Some("/home/user"),
+                expected: Some(AppDir {
+                    config: PathBuf::from("/home/user/.config/fzf-make"),
+                    state: PathBuf::from("/home/user/.local/state/fzf-make"),
+                    cache: PathBuf::from("/home/user/.cache/fzf-make"),
+                    legacy: Some(PathBuf::from("/home/user/.config/fzf-make")),
+                }),
+            },
+            Case {
+                title: "XDG_CONFIG_HOME, XDG_STATE_HOME and XDG_CACHE_HOME are respected",
+                vars: vec![
+                    ("XDG_CONFIG_HOME", "/xdg/config"),
+                    ("XDG_STATE_HOME", "/xdg/state"),
+                    ("XDG_CACHE_HOME", "/xdg/cache"),
+                ],
+                home_dir: Some("/home/user"),
+                expected: Some(AppDir {
+                    config: PathBuf::from("/xdg/config/fzf-make"),
+                    state: PathBuf::from("/xdg/state/fzf-make"),
+                    cache: PathBuf::from("/xdg/cache/fzf-make"),
+                    legacy: Some(PathBuf::from("/home/user/.config/fzf-make")),
+                }),
+            },
+            Case {
+                title: "empty or relative paths are ignored",
+                vars: vec![("XDG_CONFIG_HOME", ""), ("XDG_STATE_HOME", "state")],
+                home_dir: Some("/home/user"),
+                expected: Some(AppDir {
+                    config: PathBuf::from("/home/user/.config/fzf-make"),
+                    state: PathBuf::from("/home/user/.local/state/fzf-make"),
+                    cache: PathBuf::from("/home/user/.cache/fzf-make"),
+                    legacy: Some(PathBuf::from("/home/user/.config/fzf-make")),
+                }),
+            },
+            Case {
+                title: "the home directory is not needed if all of the variables are set",
+                vars: vec![
+                    ("XDG_CONFIG_HOME", "/xdg/config"),
+                    ("XDG_STATE_HOME", "/xdg/state"),
+                    ("XDG_CACHE_HOME", "/xdg/cache"),
+                ],
+                home_dir: None,
+                expected: Some(AppDir {
+                    config: PathBuf::from("/xdg/config/fzf-make"),
+                    state: PathBuf::from("/xdg/state/fzf-make"),
+                    cache: PathBuf::from("/xdg/cache/fzf-make"),
+                    legacy: None,
+                }),
+            },
+            Case {
+                title: "None if the home directory is unknown",
+                vars: vec![("XDG_CONFIG_HOME", "/xdg/config")],
+                home_dir: None,
+                expected: None,
+            },
+        ];
+
+        for case in cases {
+            let var = |key: &str| {
+                case.vars
+                    .iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, v)| OsString::from(v))
+            };
+            assert_eq!(
+                case.expected,
+                AppDir::new(var, case.home_dir.map(Path::new)),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+}
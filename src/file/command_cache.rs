@@ -0,0 +1,284 @@
+use super::toml;
+use crate::model::{
+    command,
+    runner::{self, Runner},
+    runner_type,
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    time::SystemTime,
+};
+
+const COMMANDS_DIR_NAME: &str = "commands";
+// The cache written by another version of fzf-make is not used since the format or the parsers may differ.
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// CommandCache is the cache of the commands listed by the runners in a project directory.
+/// Listing the commands takes a while in big projects(e.g. monorepos with many workspaces), so the cached commands
+/// are shown until the runners are detected again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandCache {
+    cache_dir: PathBuf,
+    // `<cache dir>/commands/<hash of project_dir>.json`
+    path: PathBuf,
+    project_dir: PathBuf,
+}
+
+impl CommandCache {
+    pub fn new(cache_dir: &Path, project_dir: PathBuf) -> Self {
+        let file_name = format!("{:016x}.json", fnv1a(project_dir.as_os_str().as_encoded_bytes()));
+        Self {
+            cache_dir: cache_dir.to_path_buf(),
+            path: cache_dir.join(COMMANDS_DIR_NAME).join(file_name),
+            project_dir,
+        }
+    }
+
+    // for_project returns the cache of another project directory stored in the same cache directory.
+    pub fn for_project(&self, project_dir: PathBuf) -> Self {
+        Self::new(&self.cache_dir, project_dir)
+    }
+
+    // read returns the cached commands of the runners whose source files have not been modified since they were
+    // cached. It returns an empty vec if there is no cache for the project directory.
+    pub fn read(&self) -> Result<Vec<(runner_type::RunnerType, Vec<command::CommandWithPreview>)>> {
+        let content = match fs::read(&self.path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e).with_context(|| format!("failed to read {}", self.path.display())),
+        };
+        let file: CacheFile =
+            serde_json::from_slice(&content).with_context(|| format!("failed to parse {}", self.path.display()))?;
+        if file.version != VERSION || file.project_dir != self.project_dir {
+            return Ok(vec![]);
+        }
+
+        Ok(file
+            .runners
+            .into_iter()
+            .filter(|r| r.sources.iter().all(Source::is_fresh))
+            .map(|r| (r.runner_type, r.commands.into_iter().map(CachedCommand::into).collect()))
+            .collect())
+    }
+
+    // write replaces the cache with the commands of `runners`. The custom commands are not cached since they are
+    // read from `.fzf-make.toml` quickly. The runners whose source files can not be read are not cached either.
+    pub fn write(&self, runners: &[runner::Runner]) -> Result<()> {
+        let file = CacheFile {
+            version: VERSION.to_string(),
+            project_dir: self.project_dir.clone(),
+            runners: runners
+                .iter()
+                .filter(|r| !matches!(r, Runner::Custom(_)))
+                .filter_map(|r| CachedRunner::new(&self.project_dir, r))
+                .collect(),
+        };
+        let content = serde_json::to_vec(&file)?;
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        // The cache is written to a temporary file and renamed not to be read while being written by other processes.
+        let mut temp_file_name = OsString::from(".");
+        temp_file_name.push(self.path.file_name().unwrap_or_default());
+        temp_file_name.push(format!(".{}.tmp", process::id()));
+        let temp_file_path = self.path.with_file_name(temp_file_name);
+        let result = toml::write_and_sync(&temp_file_path, &content)
+            .and_then(|_| fs::rename(&temp_file_path, &self.path))
+            .with_context(|| format!("failed to write {}", self.path.display()));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_file_path);
+        }
+        result
+    }
+}
+
+// clear removes the cached commands of all projects. It returns the removed directory.
+pub fn clear(cache_dir: &Path) -> Result<PathBuf> {
+    let dir = cache_dir.join(COMMANDS_DIR_NAME);
+    match fs::remove_dir_all(&dir) {
+        Ok(()) => Ok(dir),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(dir),
+        Err(e) => Err(e).with_context(|| format!("failed to remove {}", dir.display())),
+    }
+}
+
+// fnv1a returns the FNV-1a hash of `bytes`. It is used instead of std's hasher since its result must be stable
+// across the versions of Rust.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile {
+    version: String,
+    project_dir: PathBuf,
+    runners: Vec<CachedRunner>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedRunner {
+    runner_type: runner_type::RunnerType,
+    // The files which the commands are read from. The commands are stale if any of them is modified.
+    sources: Vec<Source>,
+    commands: Vec<CachedCommand>,
+}
+
+impl CachedRunner {
+    // new returns None if any of the source files of the runner can not be read.
+    fn new(project_dir: &Path, runner: &runner::Runner) -> Option<Self> {
+        let mut sources = vec![];
+        for path in runner.source_files() {
+            let path = project_dir.join(path);
+            let metadata = fs::metadata(&path).ok()?;
+            // Directories(e.g. the directory where task is run) are ignored since their mtimes change
+            // whenever any file is added to them.
+            if metadata.is_file() {
+                sources.push(Source {
+                    modified: metadata.modified().ok()?,
+                    len: metadata.len(),
+                    path,
+                });
+            }
+        }
+        Some(Self {
+            runner_type: runner_type::RunnerType::from(runner.clone()),
+            sources,
+            commands: runner.list_commands().into_iter().map(CachedCommand::from).collect(),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Source {
+    path: PathBuf,
+    modified: SystemTime,
+    len: u64,
+}
+
+impl Source {
+    fn is_fresh(&self) -> bool {
+        fs::metadata(&self.path).is_ok_and(|m| m.modified().ok() == Some(self.modified) && m.len() == self.len)
+    }
+}
+
+/// json representation of command::CommandWithPreview.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct CachedCommand {
+    runner_type: runner_type::RunnerType,
+    args: String,
+    file_path: PathBuf,
+    line_number: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    #[serde(default)]
+    is_up_to_date: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    expanded_body: Vec<String>,
+}
+
+impl CachedCommand {
+    fn into(self) -> command::CommandWithPreview {
+        command::CommandWithPreview::new(self.runner_type, self.args, self.file_path, self.line_number).with_metadata(
+            command::CommandMetadata {
+                description: self.description,
+                summary: self.summary,
+                aliases: self.aliases,
+                is_up_to_date: self.is_up_to_date,
+                body: self.body,
+                expanded_body: self.expanded_body,
+                // They are set by `.fzf-make.toml` after the commands are listed.
+                display_name: None,
+                pinned: false,
+            },
+        )
+    }
+}
+
+impl From<command::CommandWithPreview> for CachedCommand {
+    fn from(command: command::CommandWithPreview) -> CachedCommand {
+        Self {
+            runner_type: command.runner_type,
+            args: command.args,
+            file_path: command.file_path,
+            line_number: command.line_number,
+            description: command.metadata.description,
+            summary: command.metadata.summary,
+            aliases: command.metadata.aliases,
+            is_up_to_date: command.metadata.is_up_to_date,
+            body: command.metadata.body,
+            expanded_body: command.metadata.expanded_body,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{just::just_main::Just, make::make_main::Make};
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn read_and_write_test() {
+        let dir = std::env::temp_dir().join(format!("fzf-make-cache-{}", uuid::Uuid::new_v4()));
+        let (cache_dir, project_dir) = (dir.join("cache"), dir.join("project"));
+        fs::create_dir_all(&project_dir).unwrap();
+        let justfile = project_dir.join("justfile");
+        fs::write(&justfile, "# build the app\nbuild:\n\tcargo build\n").unwrap();
+        let just = Runner::Just(Just::find(project_dir.clone()).unwrap().unwrap());
+        let cache = CommandCache::new(&cache_dir, project_dir.clone());
+
+        // There is no cache yet.
+        assert_eq!(0, cache.read().unwrap().len());
+
+        // The runner whose source files can not be read(Test.mk does not exist) is not cached.
+        cache
+            .write(&[just.clone(), Runner::MakeCommand(Make::new_for_test())])
+            .unwrap();
+        assert_eq!(vec![(runner_type::RunnerType::Just, just.list_commands())], cache.read().unwrap());
+
+        // The cache of another project directory is not used.
+        assert_eq!(0, CommandCache::new(&cache_dir, dir.clone()).read().unwrap().len());
+
+        // The cache is stale once the source file is modified.
+        fs::File::options()
+            .write(true)
+            .open(&justfile)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(0, cache.read().unwrap().len());
+
+        cache.write(&[just]).unwrap();
+        assert_eq!(1, cache.read().unwrap().len());
+        assert_eq!(cache_dir.join(COMMANDS_DIR_NAME), clear(&cache_dir).unwrap());
+        assert_eq!(0, cache.read().unwrap().len());
+        // Clearing the cache which does not exist is not an error.
+        assert!(clear(&cache_dir).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn fnv1a_test() {
+        // The hash must not change across the versions of fzf-make not to leave the stale caches.
+        assert_eq!(0xcbf29ce484222325, fnv1a(b""));
+        assert_eq!(0xaf63dc4c8601ec8c, fnv1a(b"a"));
+        assert_eq!(0x85944171f73967e8, fnv1a(b"foobar"));
+    }
+}
@@ -1,3 +1,5 @@
+pub(crate) mod app_dir;
+pub(crate) mod command_cache;
 pub(crate) mod path_to_content;
 pub(crate) mod toml;
 mod toml_old;
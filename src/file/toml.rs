@@ -1,15 +1,17 @@
-use super::{path_to_content, toml_old};
+use super::{app_dir, path_to_content, toml_old};
 use crate::model::{
     histories::{self},
     runner_type,
 };
-use anyhow::Result;
+use anyhow::{Context, Result, anyhow};
 use serde::{Deserialize, Serialize};
 use std::{
     env,
-    fs::{self, File},
-    io::Write,
-    path::PathBuf,
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
 };
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
@@ -18,24 +20,12 @@ pub struct Histories {
 }
 
 impl Histories {
-    pub fn get_history() -> Histories {
-        match history_file_path() {
-            Some((history_file_dir, history_file_name)) => {
-                match path_to_content::path_to_content(&history_file_dir.join(history_file_name)) {
-                    Ok(c) => Histories::parse_history_in_considering_history_file_format_version(c),
-                    Err(_) => Histories::default(),
-                }
-            }
-            None => Histories::default(),
-        }
-    }
-
-    fn parse_history_in_considering_history_file_format_version(content: String) -> Histories {
+    fn parse_history_in_considering_history_file_format_version(content: String) -> Result<Histories> {
         // NOTE: The history file format has changed after https://github.com/kyu08/fzf-make/pull/324.
         // So at first we try to parse it as the new format, and then try to parse it as the old format.
         match parse_history(content.to_string()) {
-            Ok(h) => h,
-            Err(_) => toml_old::parse_history(content.to_string()).unwrap_or_default(),
+            Ok(h) => Ok(h),
+            Err(e) => toml_old::parse_history(content.to_string()).map_err(|_| e),
         }
     }
 
@@ -103,21 +93,54 @@ impl From<histories::History> for History {
 
 /// toml representation of histories::HistoryCommand.
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case", from = "RawHistoryCommand")]
 pub struct HistoryCommand {
     runner_type: runner_type::RunnerType,
-    args: String,
+    argv: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dir: Option<PathBuf>,
+    // The followings are the result of the last execution. They don't exist in the history written by older versions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    executed_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frecency: Option<f64>,
 }
 
 impl HistoryCommand {
-    pub fn new(runner_type: runner_type::RunnerType, args: String) -> Self {
-        Self { runner_type, args }
+    pub fn new(runner_type: runner_type::RunnerType, argv: Vec<String>) -> Self {
+        Self {
+            runner_type,
+            argv,
+            dir: None,
+            executed_at: None,
+            duration_ms: None,
+            exit_code: None,
+            run_count: None,
+            frecency: None,
+        }
     }
 
     fn into(self) -> histories::HistoryCommand {
         histories::HistoryCommand {
             runner_type: self.runner_type,
-            args: self.args,
+            args: self.argv,
+            dir: self.dir,
+            execution: self.executed_at.map(|executed_at| {
+                let run_count = self.run_count.unwrap_or(1);
+                histories::Execution {
+                    executed_at,
+                    duration_ms: self.duration_ms,
+                    exit_code: self.exit_code,
+                    run_count,
+                    frecency: self.frecency.unwrap_or(run_count as f64),
+                }
+            }),
         }
     }
 }
@@ -126,46 +149,209 @@ impl From<histories::HistoryCommand> for HistoryCommand {
     fn from(command: histories::HistoryCommand) -> HistoryCommand {
         Self {
             runner_type: command.runner_type,
-            args: command.args.clone(),
+            argv: command.args.clone(),
+            dir: command.dir.clone(),
+            executed_at: command.execution.as_ref().map(|e| e.executed_at),
+            duration_ms: command.execution.as_ref().and_then(|e| e.duration_ms),
+            exit_code: command.execution.as_ref().and_then(|e| e.exit_code),
+            run_count: command.execution.as_ref().map(|e| e.run_count),
+            frecency: command.execution.as_ref().map(|e| e.frecency),
         }
     }
 }
 
-// TODO: should return Result not Option(returns when it fails to get the home dir)
-pub fn history_file_path() -> Option<(PathBuf, String)> {
-    const HISTORY_FILE_NAME: &str = "history.toml";
+/// RawHistoryCommand accepts both of `argv`(array of arguments) and `args`(arguments joined with whitespace).
+/// `args` is the format used before `argv` was introduced.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawHistoryCommand {
+    runner_type: runner_type::RunnerType,
+    argv: Option<Vec<String>>,
+    args: Option<String>,
+    dir: Option<PathBuf>,
+    executed_at: Option<u64>,
+    duration_ms: Option<u64>,
+    exit_code: Option<i32>,
+    run_count: Option<u32>,
+    frecency: Option<f64>,
+}
 
-    match env::var("FZF_MAKE_IS_TESTING") {
+impl From<RawHistoryCommand> for HistoryCommand {
+    fn from(raw: RawHistoryCommand) -> HistoryCommand {
+        // The old `args` had been split by whitespace on execution, so it is split in the same way here.
+        let argv = raw.argv.unwrap_or_else(|| {
+            raw.args
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(|a| a.to_string())
+                .collect()
+        });
+        Self {
+            runner_type: raw.runner_type,
+            argv,
+            dir: raw.dir,
+            executed_at: raw.executed_at,
+            duration_ms: raw.duration_ms,
+            exit_code: raw.exit_code,
+            run_count: raw.run_count,
+            frecency: raw.frecency,
+        }
+    }
+}
+
+const HISTORY_FILE_NAME: &str = "history.toml";
+pub const HISTORY_FILE_ENV_NAME: &str = "FZF_MAKE_HISTORY_FILE";
+
+// history_file_path returns the path of the history file. The first one found in the following is used.
+// 1. `path`(specified by `--history-file`)
+// 2. `FZF_MAKE_HISTORY_FILE`
+// 3. `$XDG_STATE_HOME/fzf-make/history.toml`(defaults to `~/.local/state/fzf-make/history.toml`)
+// In the case of 3, the history file in the old location(`~/.config/fzf-make/history.toml`) is moved to it.
+pub fn history_file_path(path: Option<PathBuf>) -> Result<PathBuf> {
+    let path = path.or_else(|| {
+        env::var_os(HISTORY_FILE_ENV_NAME)
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+    });
+    if let Some(path) = path {
+        return Ok(path);
+    }
+
+    let app_dir = app_dir::AppDir::from_env().ok_or_else(|| anyhow!("failed to get the home directory"))?;
+    let old_paths: Vec<PathBuf> = [Some(&app_dir.config), app_dir.legacy.as_ref()]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join(HISTORY_FILE_NAME))
+        .collect();
+    Ok(migrate_history_file(&old_paths, app_dir.state.join(HISTORY_FILE_NAME)))
+}
+
+// migrate_history_file moves the history file in `old_paths` to `path` if `path` does not exist yet.
+// It returns the path to be used. If it fails to move the file, the old one keeps being used.
+fn migrate_history_file(old_paths: &[PathBuf], path: PathBuf) -> PathBuf {
+    if path.exists() {
+        return path;
+    }
+    let Some(old_path) = old_paths.iter().find(|p| p.is_file()) else {
+        return path;
+    };
+
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        // rename fails if they are on the different file systems.
+        .and_then(|_| fs::rename(old_path, &path).or_else(|_| fs::copy(old_path, &path).map(|_| ())));
+    match result {
         Ok(_) => {
-            // When testing
-            let cwd = env::current_dir().unwrap();
-            Some((cwd.join(PathBuf::from("test_data/history")), HISTORY_FILE_NAME.to_string()))
+            let _ = fs::remove_file(old_path);
+            let _ = fs::remove_file(lock_file_path(old_path));
+            path
         }
-        // This will be undeprecated in the next version(1.87.0), so we ignore it for now
-        #[allow(deprecated)]
-        _ => std::env::home_dir()
-            .map(|home_dir| (home_dir.join(PathBuf::from(".config/fzf-make")), HISTORY_FILE_NAME.to_string())),
+        // Another fzf-make process may have moved it at the same time.
+        Err(_) if path.exists() => path,
+        Err(_) => old_path.clone(),
     }
 }
 
+fn lock_file_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".lock")
+}
+
+// error_file_path returns the path of the file where the error of storing the history is saved.
+fn error_file_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".error")
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
 pub fn parse_history(content: String) -> Result<Histories> {
     let histories = toml::from_str(&content)?;
     Ok(histories)
 }
 
-pub fn create_or_update_history_file(
-    history_directory_path: PathBuf,
-    history_file_name: String,
-    new_history: histories::Histories,
+// read_history_file reads the history file. It returns an empty history if the file does not exist yet.
+pub fn read_history_file(path: &Path) -> Result<Histories> {
+    match path_to_content::path_to_content(path) {
+        Ok(c) if c.trim().is_empty() => Ok(Histories::default()),
+        Ok(c) => Histories::parse_history_in_considering_history_file_format_version(c)
+            .with_context(|| format!("failed to parse {}", path.display())),
+        Err(e) if e.downcast_ref::<io::Error>().map(|e| e.kind()) == Some(io::ErrorKind::NotFound) => {
+            Ok(Histories::default())
+        }
+        Err(e) => Err(e).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+// update_history_file updates the history file with `update` safely even if multiple fzf-make processes
+// write it at the same time.
+// 1. An advisory lock is taken on `<file name>.lock` so that only one process updates the file at a time.
+// 2. The file is read again while holding the lock and `update` is applied to it. So the commands stored
+//    by other processes after this process read the file are merged instead of being overwritten.
+// 3. The new content is written to a temporary file and renamed to the history file. So the history file
+//    is never truncated even if the process crashes while writing it.
+pub fn update_history_file(
+    history_file_path: &Path,
+    update: impl FnOnce(histories::Histories) -> histories::Histories,
 ) -> Result<()> {
-    if !history_directory_path.is_dir() {
-        fs::create_dir_all(history_directory_path.clone())?;
+    if let Some(history_directory_path) = history_file_path.parent() {
+        fs::create_dir_all(history_directory_path)
+            .with_context(|| format!("failed to create {}", history_directory_path.display()))?;
+    }
+
+    let lock_file_path = lock_file_path(history_file_path);
+    let lock_file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_file_path)
+        .with_context(|| format!("failed to open {}", lock_file_path.display()))?;
+    lock_file
+        .lock()
+        .with_context(|| format!("failed to lock {}", lock_file_path.display()))?;
+
+    // If the file can not be parsed, it is not overwritten so that the user can fix it by hand.
+    let current = read_history_file(history_file_path)?;
+    let content = toml::to_string(&Histories::from(update(current.into())))?;
+
+    let mut temp_file_name = OsString::from(".");
+    temp_file_name.push(history_file_path.file_name().unwrap_or_default());
+    temp_file_name.push(format!(".{}.tmp", process::id()));
+    let temp_file_path = history_file_path.with_file_name(temp_file_name);
+    let result = write_and_sync(&temp_file_path, content.as_bytes())
+        .and_then(|_| fs::rename(&temp_file_path, history_file_path))
+        .with_context(|| format!("failed to write {}", history_file_path.display()));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_file_path);
     }
-    let mut history_file = File::create(history_directory_path.join(history_file_name))?;
-    history_file.write_all(toml::to_string(&Histories::from(new_history)).unwrap().as_bytes())?;
-    history_file.flush()?;
 
-    Ok(())
+    // The lock is released when lock_file is dropped, but it is released explicitly for readability.
+    let _ = lock_file.unlock();
+    result
+}
+
+// write_history_error saves the error of storing the history next to the history file.
+// The history is stored after the TUI is closed, so the error is shown in the notification area on the next launch.
+pub fn write_history_error(history_file_path: &Path, error: &anyhow::Error) -> Result<()> {
+    let path = error_file_path(history_file_path);
+    fs::write(&path, format!("{:#}", error)).with_context(|| format!("failed to write {}", path.display()))
+}
+
+// take_history_error returns the error saved by write_history_error and removes it so that it is shown only once.
+pub fn take_history_error(history_file_path: &Path) -> Option<String> {
+    let path = error_file_path(history_file_path);
+    let error = fs::read_to_string(&path).ok()?;
+    let _ = fs::remove_file(&path);
+    Some(error)
+}
+
+pub(super) fn write_and_sync(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(content)?;
+    file.sync_all()
 }
 
 #[cfg(test)]
@@ -175,9 +361,41 @@ mod test {
     use anyhow::Result;
     use pretty_assertions::assert_eq;
 
-    // TODO: test serialize
     #[test]
-    fn serialize_test() {}
+    fn serialize_test() {
+        let histories = Histories::new(vec![History::new(
+            PathBuf::from("/Users/user/code/fzf-make"),
+            vec![
+                HistoryCommand::new(
+                    runner_type::RunnerType::Make,
+                    vec!["commit".to_string(), "-m".to_string(), "fix bug".to_string()],
+                ),
+                HistoryCommand {
+                    dir: Some(PathBuf::from("/Users/user/code/fzf-make/src")),
+                    ..HistoryCommand::new(runner_type::RunnerType::Make, vec!["test".to_string()])
+                },
+            ],
+        )]);
+
+        let content = toml::to_string(&histories).unwrap();
+        assert_eq!(
+            r#"[[histories]]
+path = "/Users/user/code/fzf-make"
+
+[[histories.commands]]
+runner-type = "make"
+argv = ["commit", "-m", "fix bug"]
+
+[[histories.commands]]
+runner-type = "make"
+argv = ["test"]
+dir = "/Users/user/code/fzf-make/src"
+"#,
+            content
+        );
+        // The arguments are stored losslessly.
+        assert_eq!(histories, parse_history(content).unwrap());
+    }
 
     #[test]
     fn parse_history_test() {
@@ -222,40 +440,79 @@ args = "app1 build"
                         History {
                             path: PathBuf::from("/Users/user/code/fzf-make"),
                             commands: vec![
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "test".to_string(),
-                                },
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "check".to_string(),
-                                },
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "spell-check".to_string(),
-                                },
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["test".to_string()]),
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["check".to_string()]),
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["spell-check".to_string()]),
                             ],
                         },
                         History {
                             path: PathBuf::from("/Users/user/code/react"),
                             commands: vec![
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::JsPackageManager(
-                                        runner_type::JsPackageManager::Pnpm,
-                                    ),
-                                    args: "test".to_string(),
-                                },
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::JsPackageManager(
-                                        runner_type::JsPackageManager::Pnpm,
-                                    ),
-                                    args: "app1 build".to_string(),
-                                },
+                                HistoryCommand::new(
+                                    runner_type::RunnerType::JsPackageManager(runner_type::JsPackageManager::Pnpm),
+                                    vec!["test".to_string()],
+                                ),
+                                HistoryCommand::new(
+                                    runner_type::RunnerType::JsPackageManager(runner_type::JsPackageManager::Pnpm),
+                                    vec!["app1".to_string(), "build".to_string()],
+                                ),
                             ],
                         },
                     ],
                 }),
             },
+            Case {
+                title: "Success(argv)",
+                content: r#"
+[[histories]]
+path = "/Users/user/code/fzf-make"
+
+[[histories.commands]]
+runner-type = "make"
+argv = ["commit", "-m", "fix bug", "FOO=a b"]
+                "#
+                .to_string(),
+                expect: Ok(Histories {
+                    histories: vec![History {
+                        path: PathBuf::from("/Users/user/code/fzf-make"),
+                        commands: vec![HistoryCommand::new(
+                            runner_type::RunnerType::Make,
+                            vec![
+                                "commit".to_string(),
+                                "-m".to_string(),
+                                "fix bug".to_string(),
+                                "FOO=a b".to_string(),
+                            ],
+                        )],
+                    }],
+                }),
+            },
+            Case {
+                title: "Success(with execution result)",
+                content: r#"
+[[histories]]
+path = "/Users/user/code/fzf-make"
+
+[[histories.commands]]
+runner-type = "make"
+argv = ["test"]
+executed-at = 1760000000
+duration-ms = 1234
+exit-code = 2
+                "#
+                .to_string(),
+                expect: Ok(Histories {
+                    histories: vec![History {
+                        path: PathBuf::from("/Users/user/code/fzf-make"),
+                        commands: vec![HistoryCommand {
+                            executed_at: Some(1760000000),
+                            duration_ms: Some(1234),
+                            exit_code: Some(2),
+                            ..HistoryCommand::new(runner_type::RunnerType::Make, vec!["test".to_string()])
+                        }],
+                    }],
+                }),
+            },
             Case {
                 title: "Error",
                 content: r#"
@@ -323,31 +580,16 @@ args = "echo1"
                         History {
                             path: PathBuf::from("/Users/user/code/fzf-make"),
                             commands: vec![
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "test".to_string(),
-                                },
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "check".to_string(),
-                                },
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "spell-check".to_string(),
-                                },
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["test".to_string()]),
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["check".to_string()]),
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["spell-check".to_string()]),
                             ],
                         },
                         History {
                             path: PathBuf::from("/Users/user/code/golang/go-playground"),
                             commands: vec![
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "run".to_string(),
-                                },
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "echo1".to_string(),
-                                },
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["run".to_string()]),
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["echo1".to_string()]),
                             ],
                         },
                     ],
@@ -370,31 +612,16 @@ executed-targets = ["run", "echo1"]
                         History {
                             path: PathBuf::from("/Users/user/code/fzf-make"),
                             commands: vec![
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "test".to_string(),
-                                },
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "check".to_string(),
-                                },
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "spell-check".to_string(),
-                                },
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["test".to_string()]),
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["check".to_string()]),
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["spell-check".to_string()]),
                             ],
                         },
                         History {
                             path: PathBuf::from("/Users/user/code/golang/go-playground"),
                             commands: vec![
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "run".to_string(),
-                                },
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "echo1".to_string(),
-                                },
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["run".to_string()]),
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["echo1".to_string()]),
                             ],
                         },
                     ],
@@ -412,10 +639,168 @@ executed-targets = ["run", "echo1"]
         for case in cases {
             assert_eq!(
                 case.expect,
-                Histories::parse_history_in_considering_history_file_format_version(case.content),
+                Histories::parse_history_in_considering_history_file_format_version(case.content).unwrap_or_default(),
                 "\nFailed: 🚨{:?}🚨\n",
                 case.title,
             )
         }
     }
+
+    #[test]
+    fn update_history_file_with_parallel_writers_test() {
+        use std::thread;
+        use uuid::Uuid;
+
+        const WRITERS: usize = 8;
+        const COMMANDS_PER_WRITER: usize = 10;
+        let dir = env::temp_dir().join(Uuid::new_v4().to_string());
+        let path = dir.join("history.toml");
+
+        // Each writer appends its commands to the history of its own directory one by one.
+        let handles: Vec<_> = (0..WRITERS)
+            .map(|writer| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    for command in 0..COMMANDS_PER_WRITER {
+                        update_history_file(&path, |h| {
+                            h.append(
+                                PathBuf::from(format!("/project{}", writer)),
+                                histories::HistoryCommand::new(
+                                    runner_type::RunnerType::Make,
+                                    vec![format!("command{}", command)],
+                                ),
+                                histories::DEFAULT_MAX_LENGTH,
+                            )
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        // No command is lost.
+        let histories = read_history_file(&path).unwrap().into();
+        assert_eq!(WRITERS, histories.histories.len());
+        for history in histories.histories {
+            assert_eq!(COMMANDS_PER_WRITER, history.commands.len(), "{:?}", history.path);
+        }
+        // No temporary file is left.
+        let mut file_names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        file_names.sort();
+        assert_eq!(vec!["history.toml", "history.toml.lock"], file_names);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn write_and_take_history_error_test() {
+        use uuid::Uuid;
+
+        let dir = env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("history.toml");
+
+        assert_eq!(None, take_history_error(&path));
+        write_history_error(&path, &anyhow!("failed to write").context("failed to store")).unwrap();
+        assert_eq!(Some("failed to store: failed to write".to_string()), take_history_error(&path));
+        // The error is shown only once.
+        assert_eq!(None, take_history_error(&path));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn update_history_file_does_not_overwrite_broken_file_test() {
+        use uuid::Uuid;
+
+        let dir = env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let broken_content = "[[histories]\npath = ";
+        fs::write(dir.join("history.toml"), broken_content).unwrap();
+
+        let result = update_history_file(&dir.join("history.toml"), |h| h);
+
+        assert!(result.is_err());
+        assert_eq!(broken_content, fs::read_to_string(dir.join("history.toml")).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn migrate_history_file_test() {
+        use uuid::Uuid;
+
+        struct Case {
+            title: &'static str,
+            // The files(relative to the temporary directory) which exist before the migration and their contents.
+            files: Vec<(&'static str, &'static str)>,
+            expected_path: &'static str,
+            expected_files: Vec<(&'static str, &'static str)>,
+        }
+        let cases = vec![
+            Case {
+                title: "the old file is moved to the new location",
+                files: vec![("old/history.toml", "old"), ("old/history.toml.lock", "")],
+                expected_path: "new/history.toml",
+                expected_files: vec![("new/history.toml", "old")],
+            },
+            Case {
+                title: "the first old file found is moved",
+                files: vec![("old2/history.toml", "old2")],
+                expected_path: "new/history.toml",
+                expected_files: vec![("new/history.toml", "old2")],
+            },
+            Case {
+                title: "the old file is not moved if the new one already exists",
+                files: vec![("old/history.toml", "old"), ("new/history.toml", "new")],
+                expected_path: "new/history.toml",
+                expected_files: vec![("new/history.toml", "new"), ("old/history.toml", "old")],
+            },
+            Case {
+                title: "nothing happens if there is no old file",
+                files: vec![],
+                expected_path: "new/history.toml",
+                expected_files: vec![],
+            },
+        ];
+
+        for case in cases {
+            let dir = env::temp_dir().join(Uuid::new_v4().to_string());
+            for (file, content) in &case.files {
+                fs::create_dir_all(dir.join(file).parent().unwrap()).unwrap();
+                fs::write(dir.join(file), content).unwrap();
+            }
+
+            let path = migrate_history_file(
+                &[dir.join("old/history.toml"), dir.join("old2/history.toml")],
+                dir.join("new/history.toml"),
+            );
+
+            assert_eq!(dir.join(case.expected_path), path, "\nFailed: 🚨{:?}🚨\n", case.title);
+            let mut files = vec![];
+            for sub_dir in ["old", "old2", "new"] {
+                for entry in fs::read_dir(dir.join(sub_dir)).into_iter().flatten() {
+                    let path = entry.unwrap().path();
+                    let file = path.strip_prefix(&dir).unwrap().to_string_lossy().to_string();
+                    files.push((file, fs::read_to_string(&path).unwrap()));
+                }
+            }
+            files.sort();
+            assert_eq!(
+                case.expected_files
+                    .iter()
+                    .map(|(f, c)| (f.to_string(), c.to_string()))
+                    .collect::<Vec<_>>(),
+                files,
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
 }
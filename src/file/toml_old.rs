@@ -15,7 +15,7 @@ impl Histories {
         for h in self.history.clone() {
             let mut commands: Vec<fzf_make_toml::HistoryCommand> = vec![];
             for c in h.executed_targets {
-                commands.push(fzf_make_toml::HistoryCommand::new(model::runner_type::RunnerType::Make, c));
+                commands.push(fzf_make_toml::HistoryCommand::new(model::runner_type::RunnerType::Make, vec![c]));
             }
             // NOTE: In old format, the path includes the file name but new format does not.
             let mut makefile_path = PathBuf::from(h.path);
@@ -65,8 +65,8 @@ mod test {
             after: fzf_make_toml::Histories::new(vec![fzf_make_toml::History::new(
                 PathBuf::from("path"),
                 vec![
-                    fzf_make_toml::HistoryCommand::new(runner_type::RunnerType::Make, "command1".to_string()),
-                    fzf_make_toml::HistoryCommand::new(runner_type::RunnerType::Make, "command2".to_string()),
+                    fzf_make_toml::HistoryCommand::new(runner_type::RunnerType::Make, vec!["command1".to_string()]),
+                    fzf_make_toml::HistoryCommand::new(runner_type::RunnerType::Make, vec!["command2".to_string()]),
                 ],
             )]),
         }];
@@ -100,19 +100,25 @@ executed-targets = ["run", "echo1"]
                     fzf_make_toml::History::new(
                         PathBuf::from("/Users/user/code/fzf-make"),
                         vec![
-                            fzf_make_toml::HistoryCommand::new(runner_type::RunnerType::Make, "test".to_string()),
-                            fzf_make_toml::HistoryCommand::new(runner_type::RunnerType::Make, "check".to_string()),
+                            fzf_make_toml::HistoryCommand::new(runner_type::RunnerType::Make, vec!["test".to_string()]),
                             fzf_make_toml::HistoryCommand::new(
                                 runner_type::RunnerType::Make,
-                                "spell-check".to_string(),
+                                vec!["check".to_string()],
+                            ),
+                            fzf_make_toml::HistoryCommand::new(
+                                runner_type::RunnerType::Make,
+                                vec!["spell-check".to_string()],
                             ),
                         ],
                     ),
                     fzf_make_toml::History::new(
                         PathBuf::from("/Users/user/code/golang/go-playground"),
                         vec![
-                            fzf_make_toml::HistoryCommand::new(runner_type::RunnerType::Make, "run".to_string()),
-                            fzf_make_toml::HistoryCommand::new(runner_type::RunnerType::Make, "echo1".to_string()),
+                            fzf_make_toml::HistoryCommand::new(runner_type::RunnerType::Make, vec!["run".to_string()]),
+                            fzf_make_toml::HistoryCommand::new(
+                                runner_type::RunnerType::Make,
+                                vec!["echo1".to_string()],
+                            ),
                         ],
                     ),
                 ])),
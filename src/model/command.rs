@@ -1,5 +1,5 @@
 use super::{histories, runner_type};
-use std::{fmt, path::PathBuf};
+use std::{fmt, path::PathBuf, process};
 
 #[derive(PartialEq, Clone, Debug)]
 pub struct CommandWithPreview {
@@ -7,6 +7,25 @@ pub struct CommandWithPreview {
     pub args: String,
     pub file_path: PathBuf,
     pub line_number: u32,
+    pub metadata: CommandMetadata,
+}
+
+// CommandMetadata holds the additional information of the command which only some runners provide.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct CommandMetadata {
+    pub description: Option<String>,
+    pub summary: Option<String>,
+    // Other names of the command which can be used to search it.
+    pub aliases: Vec<String>,
+    pub is_up_to_date: bool,
+    // The body of the command(e.g. the value of the script in package.json).
+    pub body: Option<String>,
+    // Everything which actually runs when the command is executed(e.g. chained scripts and lifecycle hooks).
+    pub expanded_body: Vec<String>,
+    // The name shown instead of the command(e.g. renamed in `.fzf-make.toml`).
+    pub display_name: Option<String>,
+    // Pinned commands are listed at the top.
+    pub pinned: bool,
 }
 
 impl CommandWithPreview {
@@ -16,7 +35,24 @@ impl CommandWithPreview {
             args,
             file_path,
             line_number,
+            metadata: CommandMetadata::default(),
+        }
+    }
+
+    pub fn with_metadata(self, metadata: CommandMetadata) -> Self {
+        Self { metadata, ..self }
+    }
+
+    // search_targets returns the strings which are used to narrow down the commands.
+    pub fn search_targets(&self) -> Vec<String> {
+        let mut targets = vec![self.to_string()];
+        for alias in &self.metadata.aliases {
+            targets.push(format!("{} {}", self.runner_type, alias));
+        }
+        if let Some(display_name) = &self.metadata.display_name {
+            targets.push(display_name.clone());
         }
+        targets
     }
 }
 
@@ -29,14 +65,46 @@ impl fmt::Display for CommandWithPreview {
 #[derive(PartialEq, Clone, Debug)]
 pub struct CommandForExec {
     pub runner_type: runner_type::RunnerType,
-    pub args: String,
+    // The arguments passed to the runner as is. Each element is passed as one argument without splitting.
+    pub args: Vec<String>,
+}
+
+impl CommandForExec {
+    // args_to_string joins the arguments quoting them following POSIX shell rules if needed.
+    // Arguments which are safe in shells(e.g. `--workspace=app1`) are not quoted to keep them readable.
+    pub fn args_to_string(&self) -> String {
+        self.args
+            .iter()
+            .map(|arg| {
+                let is_safe = !arg.is_empty()
+                    && arg
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+                if is_safe { arg.into() } else { shell_words::quote(arg) }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // append_shell_words appends the arguments in `input` which are parsed following POSIX shell rules.
+    // e.g. `-m "fix bug"` is parsed as `-m` and `fix bug`.
+    pub fn append_shell_words(&self, input: &str) -> Result<Self, String> {
+        let words = shell_words::split(input).map_err(|e| format!("failed to parse arguments: {}", e))?;
+        let mut args = self.args.clone();
+        args.extend(words);
+        Ok(Self {
+            runner_type: self.runner_type.clone(),
+            args,
+        })
+    }
 }
 
 impl From<CommandWithPreview> for CommandForExec {
     fn from(c: CommandWithPreview) -> CommandForExec {
         CommandForExec {
             runner_type: c.runner_type.clone(),
-            args: c.args.clone(),
+            // The args of CommandWithPreview are built by fzf-make(e.g. `--filter app build`), so they don't contain quotes.
+            args: c.args.split_whitespace().map(|a| a.to_string()).collect(),
         }
     }
 }
@@ -52,6 +120,136 @@ impl From<histories::HistoryCommand> for CommandForExec {
 
 impl fmt::Display for CommandForExec {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} {}", self.runner_type, self.args)
+        write!(f, "{} {}", self.runner_type, self.args_to_string())
+    }
+}
+
+// exit_code converts the exit status of the executed command into the exit code.
+// On Unix, `128 + signal number` is used when the command was killed by a signal as shells do.
+pub fn exit_code(status: process::ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[cfg(unix)]
+    #[test]
+    fn exit_code_test() {
+        use std::os::unix::process::ExitStatusExt;
+
+        struct Case {
+            title: &'static str,
+            // The raw wait status which is returned by waitpid(2).
+            raw_status: i32,
+            expected: i32,
+        }
+        let cases = vec![
+            Case {
+                title: "success",
+                raw_status: 0,
+                expected: 0,
+            },
+            Case {
+                title: "exited with code 2",
+                raw_status: 2 << 8,
+                expected: 2,
+            },
+            Case {
+                title: "killed by SIGKILL",
+                raw_status: 9,
+                expected: 137,
+            },
+            Case {
+                title: "killed by SIGINT",
+                raw_status: 2,
+                expected: 130,
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(
+                case.expected,
+                exit_code(process::ExitStatus::from_raw(case.raw_status)),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn append_shell_words_test() {
+        struct Case {
+            title: &'static str,
+            input: &'static str,
+            expected: Result<Vec<&'static str>, String>,
+        }
+        let cases = vec![
+            Case {
+                title: "double quoted argument is not split",
+                input: r#"-m "fix bug""#,
+                expected: Ok(vec!["commit", "-m", "fix bug"]),
+            },
+            Case {
+                title: "single quoted value of variable",
+                input: "FOO='a b' bar",
+                expected: Ok(vec!["commit", "FOO=a b", "bar"]),
+            },
+            Case {
+                title: "escaped whitespace",
+                input: r"a\ b",
+                expected: Ok(vec!["commit", "a b"]),
+            },
+            Case {
+                title: "empty input",
+                input: "  ",
+                expected: Ok(vec!["commit"]),
+            },
+            Case {
+                title: "unbalanced quote",
+                input: r#"-m "fix bug"#,
+                expected: Err("failed to parse arguments: missing closing quote".to_string()),
+            },
+        ];
+
+        let command = CommandForExec {
+            runner_type: runner_type::RunnerType::Make,
+            args: vec!["commit".to_string()],
+        };
+        for case in cases {
+            assert_eq!(
+                case.expected
+                    .map(|args| args.iter().map(|a| a.to_string()).collect::<Vec<_>>()),
+                command.append_shell_words(case.input).map(|c| c.args),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn display_test() {
+        let command = CommandForExec {
+            runner_type: runner_type::RunnerType::Make,
+            args: vec![
+                "commit".to_string(),
+                "-m".to_string(),
+                "fix bug".to_string(),
+                "FOO=a".to_string(),
+            ],
+        };
+        assert_eq!("make commit -m 'fix bug' FOO=a", command.to_string());
     }
 }
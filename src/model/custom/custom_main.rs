@@ -0,0 +1,238 @@
+use crate::model::{command, runner_type};
+use anyhow::{Result, anyhow};
+use std::{collections::BTreeMap, path::PathBuf, process};
+
+/// Custom is the runner of the commands defined in `custom-commands` of `.fzf-make.toml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Custom {
+    // The path of `.fzf-make.toml`. It is shown in the preview window as the definition of the commands.
+    path: PathBuf,
+    commands: Vec<CustomCommand>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomCommand {
+    pub name: String,
+    // The shell command to run.
+    pub run: String,
+    // The working directory. It is relative to the directory of `.fzf-make.toml`.
+    pub cwd: Option<PathBuf>,
+    pub env: BTreeMap<String, String>,
+    pub description: Option<String>,
+    pub line_number: u32,
+}
+
+impl Custom {
+    pub fn new(path: PathBuf, commands: Vec<CustomCommand>) -> Self {
+        Self { path, commands }
+    }
+
+    pub fn to_commands(&self) -> Vec<command::CommandWithPreview> {
+        self.commands
+            .iter()
+            .map(|c| {
+                command::CommandWithPreview::new(
+                    runner_type::RunnerType::Custom,
+                    c.name.clone(),
+                    self.path.clone(),
+                    c.line_number,
+                )
+                .with_metadata(command::CommandMetadata {
+                    description: c.description.clone(),
+                    body: Some(c.run.clone()),
+                    ..command::CommandMetadata::default()
+                })
+            })
+            .collect()
+    }
+
+    // path returns the directory where `.fzf-make.toml` is.
+    pub fn path(&self) -> PathBuf {
+        self.path.parent().map(|p| p.to_path_buf()).unwrap_or_default()
+    }
+
+    pub fn command_to_run(&self, command: &command::CommandForExec) -> Result<String> {
+        self.script(command)
+    }
+
+    pub fn execution_details(&self, command: &command::CommandForExec) -> Vec<String> {
+        let Some((custom_command, _)) = self.find_command(command) else {
+            return vec![];
+        };
+
+        let mut result = vec![format!(
+            "working directory: {}",
+            self.working_directory(custom_command).display()
+        )];
+        if !custom_command.env.is_empty() {
+            let env: Vec<String> = custom_command.env.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            result.push(format!("env: {}", env.join(" ")));
+        }
+        result
+    }
+
+    // process_command builds the process to run the command with the shell. It is spawned or exec-ed by Runner.
+    // It fails if the command is not defined(e.g. it was renamed in `.fzf-make.toml` after it was executed).
+    pub fn process_command(&self, command: &command::CommandForExec) -> Result<process::Command> {
+        let script = self.script(command)?;
+        #[cfg(windows)]
+        let mut process_command = {
+            let mut process_command = process::Command::new("cmd");
+            process_command.arg("/C").arg(script);
+            process_command
+        };
+        #[cfg(not(windows))]
+        let mut process_command = {
+            let mut process_command = process::Command::new("sh");
+            process_command.arg("-c").arg(script);
+            process_command
+        };
+        process_command.stdin(process::Stdio::inherit());
+        if let Some((custom_command, _)) = self.find_command(command) {
+            process_command
+                .current_dir(self.working_directory(custom_command))
+                .envs(&custom_command.env);
+        }
+        Ok(process_command)
+    }
+
+    // script returns the shell command to run. The additional arguments are appended to `run` being quoted.
+    fn script(&self, command: &command::CommandForExec) -> Result<String> {
+        match self.find_command(command) {
+            Some((custom_command, [])) => Ok(custom_command.run.clone()),
+            Some((custom_command, additional_args)) => {
+                Ok(format!("{} {}", custom_command.run, shell_words::join(additional_args)))
+            }
+            None => Err(anyhow!("custom command not found: {}", command.args_to_string())),
+        }
+    }
+
+    // find_command returns the custom command and the additional arguments passed to it.
+    // Since the name can contain whitespaces, `command.args` starting with the words of the name is searched.
+    fn find_command<'a>(&self, command: &'a command::CommandForExec) -> Option<(&CustomCommand, &'a [String])> {
+        self.commands
+            .iter()
+            .filter_map(|c| {
+                let words: Vec<&str> = c.name.split_whitespace().collect();
+                let matched =
+                    command.args.len() >= words.len() && command.args.iter().zip(&words).all(|(arg, word)| arg == word);
+                matched.then(|| (c, &command.args[words.len()..]))
+            })
+            // The longest name wins if the names share the prefix(e.g. `deploy` and `deploy staging`).
+            .max_by_key(|(c, _)| c.name.split_whitespace().count())
+    }
+
+    fn working_directory(&self, command: &CustomCommand) -> PathBuf {
+        match &command.cwd {
+            Some(cwd) => self.path().join(cwd),
+            None => self.path(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::Path;
+
+    fn custom() -> Custom {
+        let command = |name: &str, run: &str| CustomCommand {
+            name: name.to_string(),
+            run: run.to_string(),
+            cwd: None,
+            env: BTreeMap::new(),
+            description: None,
+            line_number: 1,
+        };
+        Custom::new(
+            PathBuf::from("/code/app/.fzf-make.toml"),
+            vec![
+                command("deploy", "./deploy.sh"),
+                CustomCommand {
+                    cwd: Some(PathBuf::from("infra")),
+                    env: BTreeMap::from([("STAGE".to_string(), "staging".to_string())]),
+                    ..command("deploy staging", "./deploy.sh --stage staging")
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn command_to_run_test() {
+        struct Case {
+            title: &'static str,
+            args: Vec<&'static str>,
+            expected: Result<&'static str, &'static str>,
+        }
+        let cases = vec![
+            Case {
+                title: "the command is found by its name",
+                args: vec!["deploy"],
+                expected: Ok("./deploy.sh"),
+            },
+            Case {
+                title: "the longest name wins",
+                args: vec!["deploy", "staging"],
+                expected: Ok("./deploy.sh --stage staging"),
+            },
+            Case {
+                title: "additional arguments are appended being quoted",
+                args: vec!["deploy", "--dry-run", "a b"],
+                expected: Ok("./deploy.sh --dry-run 'a b'"),
+            },
+            Case {
+                title: "unknown command",
+                args: vec!["build"],
+                expected: Err("custom command not found: build"),
+            },
+        ];
+
+        for case in cases {
+            let command = command::CommandForExec {
+                runner_type: runner_type::RunnerType::Custom,
+                args: case.args.iter().map(|a| a.to_string()).collect(),
+            };
+            assert_eq!(
+                case.expected.map(|s| s.to_string()).map_err(|e| e.to_string()),
+                custom().command_to_run(&command).map_err(|e| e.to_string()),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn execution_details_test() {
+        let command = command::CommandForExec {
+            runner_type: runner_type::RunnerType::Custom,
+            args: vec!["deploy".to_string(), "staging".to_string()],
+        };
+        assert_eq!(
+            vec![
+                "working directory: /code/app/infra".to_string(),
+                "env: STAGE=staging".to_string(),
+            ],
+            custom().execution_details(&command),
+        );
+    }
+
+    #[test]
+    fn process_command_test() {
+        let command = |args: Vec<&str>| command::CommandForExec {
+            runner_type: runner_type::RunnerType::Custom,
+            args: args.iter().map(|a| a.to_string()).collect(),
+        };
+
+        let process_command = custom().process_command(&command(vec!["deploy", "staging"])).unwrap();
+        assert_eq!(Some(Path::new("/code/app/infra")), process_command.get_current_dir());
+        // The command removed from `.fzf-make.toml` is not run as an empty script.
+        assert_eq!(
+            "custom command not found: build",
+            custom()
+                .process_command(&command(vec!["build"]))
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
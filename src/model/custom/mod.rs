@@ -0,0 +1 @@
+pub mod custom_main;
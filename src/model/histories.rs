@@ -1,5 +1,26 @@
-use super::{command, runner_type};
-use std::path::PathBuf;
+use super::{command, file_util, runner_type};
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// The max number of the commands stored for each directory by default.
+pub const DEFAULT_MAX_LENGTH: usize = 50;
+// The frecency score halves every week.
+const FRECENCY_HALF_LIFE_SECS: f64 = 7.0 * 24.0 * 60.0 * 60.0;
+// The files which indicate the root of the project of each runner. They are compared in lower case.
+const RUNNER_FILE_NAMES: [&str; 9] = [
+    "makefile",
+    "gnumakefile",
+    "package.json",
+    "justfile",
+    ".justfile",
+    "taskfile.yml",
+    "taskfile.yaml",
+    "taskfile.dist.yml",
+    "taskfile.dist.yaml",
+];
 
 /// Histories is a all collection of History. This equals whole content of history.toml.
 /// For now, we can define this as tuple like `pub struct Histories(Vec<History>);` but we don't.
@@ -10,14 +31,22 @@ pub struct Histories {
 }
 
 impl Histories {
-    pub fn append(&self, current_dir: PathBuf, command: command::CommandForExec) -> Self {
+    // append appends the command to the history of `current_dir`(the directory decided by Scope).
+    // append appends the command to the history of `current_dir`. At most `max_length` commands are kept.
+    pub fn append(&self, current_dir: PathBuf, command: HistoryCommand, max_length: usize) -> Self {
+        // The directory where the command was executed is stored only if it differs from `current_dir`.
+        let command = match &command.dir {
+            Some(dir) if *dir == current_dir => HistoryCommand { dir: None, ..command },
+            _ => command,
+        };
+
         // Update the command history for the current directory.
         let new_history = {
             match self.histories.iter().find(|h| h.path == current_dir) {
-                Some(history) => history.append(command.clone()),
+                Some(history) => history.append(command.clone(), max_length),
                 None => History {
                     path: current_dir,
-                    commands: vec![HistoryCommand::from(command)],
+                    commands: vec![command],
                 },
             }
         };
@@ -37,6 +66,44 @@ impl Histories {
             histories: new_histories,
         }
     }
+
+    // global_commands returns the commands executed in all directories sorted by the ranking.
+    // Since the order of execution across directories is not stored, they are sorted by the executed time.
+    pub fn global_commands(&self, ranking: Ranking, now: u64) -> Vec<GlobalHistoryCommand> {
+        let mut commands: Vec<GlobalHistoryCommand> = self
+            .histories
+            .iter()
+            .flat_map(|h| {
+                h.commands.iter().map(|c| GlobalHistoryCommand {
+                    path: h.path.clone(),
+                    command: c.clone(),
+                })
+            })
+            .collect();
+
+        // The commands written by older versions don't have the executed time, so they are placed at the end.
+        let executed_at = |c: &GlobalHistoryCommand| c.command.execution.as_ref().map(|e| e.executed_at);
+        commands.sort_by_key(|c| std::cmp::Reverse(executed_at(c)));
+        if ranking == Ranking::Frecency {
+            commands.sort_by(|a, b| b.command.frecency(now).total_cmp(&a.command.frecency(now)));
+        }
+        commands
+    }
+}
+
+/// GlobalHistoryCommand is a command in the history with the path of the history.
+/// It is used to list the commands executed in all directories.
+#[derive(PartialEq, Clone, Debug)]
+pub struct GlobalHistoryCommand {
+    pub path: PathBuf,
+    pub command: HistoryCommand,
+}
+
+impl GlobalHistoryCommand {
+    // dir returns the directory where the command was executed.
+    pub fn dir(&self) -> PathBuf {
+        self.command.dir.clone().unwrap_or(self.path.clone())
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -48,15 +115,30 @@ pub struct History {
 }
 
 impl History {
-    fn append(&self, executed_command: command::CommandForExec) -> Self {
+    fn append(&self, executed_command: HistoryCommand, max_length: usize) -> Self {
+        // Accumulate the run count and the frecency score of the same command executed before.
+        let previous_execution = self
+            .commands
+            .iter()
+            .find(|c| c.is_same_command(&executed_command))
+            .and_then(|c| c.execution.clone());
+        let executed_command = match (previous_execution, executed_command.execution.clone()) {
+            (Some(previous), Some(execution)) => executed_command.with_execution(Execution {
+                run_count: previous.run_count + 1,
+                frecency: previous.frecency * decay(execution.executed_at.saturating_sub(previous.executed_at))
+                    + execution.frecency,
+                ..execution
+            }),
+            _ => executed_command,
+        };
+
         let mut updated_commands = self.commands.clone();
         // removes the executed_command from the history
-        updated_commands.retain(|t| *t != HistoryCommand::from(executed_command.clone()));
-        updated_commands.insert(0, HistoryCommand::from(executed_command.clone()));
+        updated_commands.retain(|t| !t.is_same_command(&executed_command));
+        updated_commands.insert(0, executed_command);
 
-        const MAX_LENGTH: usize = 50;
-        if MAX_LENGTH < updated_commands.len() {
-            updated_commands.truncate(MAX_LENGTH);
+        if max_length < updated_commands.len() {
+            updated_commands.truncate(max_length);
         }
 
         Self {
@@ -73,14 +155,183 @@ impl History {
 #[derive(PartialEq, Clone, Debug)]
 pub struct HistoryCommand {
     pub runner_type: runner_type::RunnerType,
-    pub args: String,
+    pub args: Vec<String>,
+    /// The directory where the command was executed. None if it is the same as the path of the history.
+    /// It differs from the path of the history when the history is scoped by Scope::GitRoot or Scope::RunnerRoot.
+    pub dir: Option<PathBuf>,
+    /// The result of the last execution. This is None in the history written by older versions.
+    pub execution: Option<Execution>,
+}
+
+impl HistoryCommand {
+    pub fn new(runner_type: runner_type::RunnerType, args: Vec<String>) -> Self {
+        Self {
+            runner_type,
+            args,
+            dir: None,
+            execution: None,
+        }
+    }
+
+    pub fn with_execution(self, execution: Execution) -> Self {
+        Self {
+            execution: Some(execution),
+            ..self
+        }
+    }
+
+    // is_same_command returns true if both of them run the same command in the same directory
+    // regardless of their execution results.
+    pub fn is_same_command(&self, other: &HistoryCommand) -> bool {
+        self.runner_type == other.runner_type && self.args == other.args && self.dir == other.dir
+    }
+
+    // frecency returns the frecency score at `now`. The score decays as time passes since the last execution.
+    pub fn frecency(&self, now: u64) -> f64 {
+        self.execution
+            .as_ref()
+            .map(|e| e.frecency * decay(now.saturating_sub(e.executed_at)))
+            .unwrap_or_default()
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(
+            self.execution,
+            Some(Execution {
+                exit_code: Some(code),
+                ..
+            }) if code != 0
+        )
+    }
 }
 
 impl From<command::CommandForExec> for HistoryCommand {
     fn from(command: command::CommandForExec) -> Self {
+        Self::new(command.runner_type, command.args)
+    }
+}
+
+/// Execution is the result of the execution of the command.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Execution {
+    /// Unix time in seconds when the command was executed.
+    pub executed_at: u64,
+    /// These are None when the command was not waited(e.g. exec mode).
+    pub duration_ms: Option<u64>,
+    pub exit_code: Option<i32>,
+    /// How many times the command has been executed.
+    pub run_count: u32,
+    /// The frecency score at `executed_at`. Each execution adds 1 to the decayed score.
+    pub frecency: f64,
+}
+
+impl Execution {
+    pub fn new(executed_at: u64, duration_ms: Option<u64>, exit_code: Option<i32>) -> Self {
         Self {
-            runner_type: command.runner_type,
-            args: command.args,
+            executed_at,
+            duration_ms,
+            exit_code,
+            run_count: 1,
+            frecency: 1.0,
+        }
+    }
+}
+
+fn decay(elapsed_secs: u64) -> f64 {
+    0.5_f64.powf(elapsed_secs as f64 / FRECENCY_HALF_LIFE_SECS)
+}
+
+// now returns the current unix time in seconds.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Scope decides the directory whose history is used in the current directory.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scope {
+    /// The current directory itself.
+    #[default]
+    Exact,
+    /// The root directory of the git repository.
+    GitRoot,
+    /// The nearest directory which has a file of any runner(e.g. Makefile, package.json).
+    RunnerRoot,
+}
+
+impl Scope {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "exact" => Some(Self::Exact),
+            "git-root" => Some(Self::GitRoot),
+            "runner-root" => Some(Self::RunnerRoot),
+            _ => None,
+        }
+    }
+
+    // key returns the directory whose history is used in `current_dir`.
+    // It falls back to `current_dir` if the directory is not found.
+    pub fn key(&self, current_dir: &Path) -> PathBuf {
+        let file_names = match self {
+            Scope::Exact => return current_dir.to_path_buf(),
+            Scope::GitRoot => vec![".git"],
+            Scope::RunnerRoot => RUNNER_FILE_NAMES.to_vec(),
+        };
+        file_util::find_file_in_ancestors(current_dir.to_path_buf(), file_names)
+            .and_then(|file| file.parent().map(|dir| dir.to_path_buf()))
+            .unwrap_or(current_dir.to_path_buf())
+    }
+}
+
+/// Ranking decides how the commands in the history are ranked.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Ranking {
+    /// Most recently used commands first.
+    Mru,
+    /// Frequently and recently used commands first.
+    #[default]
+    Frecency,
+    /// The history is shown in the order of execution and the commands list is not affected.
+    Off,
+}
+
+impl Ranking {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "mru" => Some(Self::Mru),
+            "frecency" => Some(Self::Frecency),
+            "off" => Some(Self::Off),
+            _ => None,
+        }
+    }
+
+    // rank sorts the commands(which are sorted in MRU order) by this ranking.
+    pub fn rank(&self, commands: Vec<HistoryCommand>, now: u64) -> Vec<HistoryCommand> {
+        match self {
+            Ranking::Frecency => {
+                let mut commands = commands;
+                commands.sort_by(|a, b| b.frecency(now).total_cmp(&a.frecency(now)));
+                commands
+            }
+            Ranking::Mru | Ranking::Off => commands,
+        }
+    }
+
+    // scores returns the scores of the commands(which are sorted in MRU order). Higher is better.
+    // They are used as a tie-breaker of the commands which have the same fuzzy score.
+    pub fn scores<'a>(&self, commands: &'a [HistoryCommand], now: u64) -> Vec<(&'a HistoryCommand, f64)> {
+        match self {
+            Ranking::Mru => commands
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (c, (commands.len() - i) as f64))
+                .collect(),
+            Ranking::Frecency => commands.iter().map(|c| (c, c.frecency(now))).collect(),
+            Ranking::Off => vec![],
         }
     }
 }
@@ -110,44 +361,38 @@ mod test {
                     histories: vec![
                         History {
                             path: PathBuf::from("/Users/user/code/rustc".to_string()),
-                            commands: vec![HistoryCommand {
-                                runner_type: runner_type::RunnerType::Make,
-                                args: "history0".to_string(),
-                            }],
+                            commands: vec![HistoryCommand::new(
+                                runner_type::RunnerType::Make,
+                                vec!["history0".to_string()],
+                            )],
                         },
                         History {
                             path: path_to_append.clone(),
-                            commands: vec![HistoryCommand {
-                                runner_type: runner_type::RunnerType::Make,
-                                args: "history0".to_string(),
-                            }],
+                            commands: vec![HistoryCommand::new(
+                                runner_type::RunnerType::Make,
+                                vec!["history0".to_string()],
+                            )],
                         },
                     ],
                 },
                 command_to_append: command::CommandForExec {
                     runner_type: runner_type::RunnerType::Make,
-                    args: "append".to_string(),
+                    args: vec!["append".to_string()],
                 },
                 after: Histories {
                     histories: vec![
                         History {
                             path: PathBuf::from("/Users/user/code/rustc".to_string()),
-                            commands: vec![HistoryCommand {
-                                runner_type: runner_type::RunnerType::Make,
-                                args: "history0".to_string(),
-                            }],
+                            commands: vec![HistoryCommand::new(
+                                runner_type::RunnerType::Make,
+                                vec!["history0".to_string()],
+                            )],
                         },
                         History {
                             path: path_to_append.clone(),
                             commands: vec![
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "append".to_string(),
-                                },
-                                HistoryCommand {
-                                    runner_type: runner_type::RunnerType::Make,
-                                    args: "history0".to_string(),
-                                },
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["append".to_string()]),
+                                HistoryCommand::new(runner_type::RunnerType::Make, vec!["history0".to_string()]),
                             ],
                         },
                     ],
@@ -158,31 +403,31 @@ mod test {
                 before: Histories {
                     histories: vec![History {
                         path: PathBuf::from("/Users/user/code/rustc".to_string()),
-                        commands: vec![HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history0".to_string(),
-                        }],
+                        commands: vec![HistoryCommand::new(
+                            runner_type::RunnerType::Make,
+                            vec!["history0".to_string()],
+                        )],
                     }],
                 },
                 command_to_append: command::CommandForExec {
                     runner_type: runner_type::RunnerType::Make,
-                    args: "append".to_string(),
+                    args: vec!["append".to_string()],
                 },
                 after: Histories {
                     histories: vec![
                         History {
                             path: path_to_append.clone(),
-                            commands: vec![HistoryCommand {
-                                runner_type: runner_type::RunnerType::Make,
-                                args: "append".to_string(),
-                            }],
+                            commands: vec![HistoryCommand::new(
+                                runner_type::RunnerType::Make,
+                                vec!["append".to_string()],
+                            )],
                         },
                         History {
                             path: PathBuf::from("/Users/user/code/rustc".to_string()),
-                            commands: vec![HistoryCommand {
-                                runner_type: runner_type::RunnerType::Make,
-                                args: "history0".to_string(),
-                            }],
+                            commands: vec![HistoryCommand::new(
+                                runner_type::RunnerType::Make,
+                                vec!["history0".to_string()],
+                            )],
                         },
                     ],
                 },
@@ -192,13 +437,247 @@ mod test {
         for case in cases {
             assert_eq!(
                 case.after,
-                case.before.append(path_to_append.clone(), case.command_to_append),
+                case.before
+                    .append(path_to_append.clone(), case.command_to_append.into(), DEFAULT_MAX_LENGTH),
                 "\nFailed: 🚨{:?}🚨\n",
                 case.title,
             )
         }
     }
 
+    #[test]
+    fn global_commands_test() {
+        struct Case {
+            title: &'static str,
+            ranking: Ranking,
+            expected: Vec<(&'static str, &'static str)>,
+        }
+        const NOW: u64 = 14 * 24 * 60 * 60;
+        let command = |args: &str, execution: Option<(u64, f64)>| HistoryCommand {
+            execution: execution.map(|(executed_at, frecency)| Execution {
+                frecency,
+                ..Execution::new(executed_at, None, None)
+            }),
+            ..HistoryCommand::new(runner_type::RunnerType::Make, vec![args.to_string()])
+        };
+        let histories = Histories {
+            histories: vec![
+                History {
+                    path: PathBuf::from("/code/app"),
+                    commands: vec![command("test", Some((NOW - 10, 1.0))), command("legacy", None)],
+                },
+                History {
+                    path: PathBuf::from("/code/lib"),
+                    commands: vec![command("lint", Some((NOW, 1.0))), command("build", Some((0, 8.0)))],
+                },
+            ],
+        };
+        let cases = vec![
+            Case {
+                title: "mru: sorted by the executed time across directories",
+                ranking: Ranking::Mru,
+                expected: vec![
+                    ("/code/lib", "lint"),
+                    ("/code/app", "test"),
+                    ("/code/lib", "build"),
+                    ("/code/app", "legacy"),
+                ],
+            },
+            Case {
+                title: "frecency",
+                ranking: Ranking::Frecency,
+                expected: vec![
+                    ("/code/lib", "build"),
+                    ("/code/lib", "lint"),
+                    ("/code/app", "test"),
+                    ("/code/app", "legacy"),
+                ],
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(
+                case.expected
+                    .iter()
+                    .map(|(path, args)| (PathBuf::from(path), args.to_string()))
+                    .collect::<Vec<_>>(),
+                histories
+                    .global_commands(case.ranking, NOW)
+                    .into_iter()
+                    .map(|c| (c.path, c.command.args[0].clone()))
+                    .collect::<Vec<_>>(),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn history_append_accumulates_frecency_test() {
+        const WEEK: u64 = 7 * 24 * 60 * 60;
+        let command = |args: &str| HistoryCommand::new(runner_type::RunnerType::Make, vec![args.to_string()]);
+        let history = History {
+            path: PathBuf::from("/Users/user/code/fzf-make"),
+            commands: vec![],
+        }
+        .append(command("build").with_execution(Execution::new(0, Some(10), Some(0))), DEFAULT_MAX_LENGTH)
+        .append(command("test").with_execution(Execution::new(WEEK, Some(10), Some(0))), DEFAULT_MAX_LENGTH)
+        .append(command("build").with_execution(Execution::new(WEEK, Some(10), Some(1))), DEFAULT_MAX_LENGTH);
+
+        // The score of the first execution is halved after a week.
+        assert_eq!(
+            vec![
+                command("build").with_execution(Execution {
+                    run_count: 2,
+                    frecency: 1.5,
+                    ..Execution::new(WEEK, Some(10), Some(1))
+                }),
+                command("test").with_execution(Execution::new(WEEK, Some(10), Some(0))),
+            ],
+            history.commands
+        );
+        assert_eq!(0.75, history.commands[0].frecency(2 * WEEK));
+    }
+
+    #[test]
+    fn ranking_test() {
+        struct Case {
+            title: &'static str,
+            ranking: Ranking,
+            expected_rank: Vec<&'static str>,
+            expected_scores: Vec<(&'static str, f64)>,
+        }
+        const NOW: u64 = 14 * 24 * 60 * 60;
+        let command = |args: &str, executed_at: u64, frecency: f64| HistoryCommand {
+            execution: Some(Execution {
+                frecency,
+                ..Execution::new(executed_at, None, None)
+            }),
+            ..HistoryCommand::new(runner_type::RunnerType::Make, vec![args.to_string()])
+        };
+        // MRU order. `build` was executed two weeks ago but many times.
+        let commands = vec![
+            command("test", NOW, 1.0),
+            command("lint", NOW - 7 * 24 * 60 * 60, 1.0),
+            command("build", 0, 8.0),
+        ];
+        let cases = vec![
+            Case {
+                title: "mru",
+                ranking: Ranking::Mru,
+                expected_rank: vec!["test", "lint", "build"],
+                expected_scores: vec![("test", 3.0), ("lint", 2.0), ("build", 1.0)],
+            },
+            Case {
+                title: "frecency",
+                ranking: Ranking::Frecency,
+                expected_rank: vec!["build", "test", "lint"],
+                expected_scores: vec![("test", 1.0), ("lint", 0.5), ("build", 2.0)],
+            },
+            Case {
+                title: "off",
+                ranking: Ranking::Off,
+                expected_rank: vec!["test", "lint", "build"],
+                expected_scores: vec![],
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(
+                case.expected_rank,
+                case.ranking
+                    .rank(commands.clone(), NOW)
+                    .iter()
+                    .map(|c| c.args[0].as_str())
+                    .collect::<Vec<_>>(),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+            assert_eq!(
+                case.expected_scores,
+                case.ranking
+                    .scores(&commands, NOW)
+                    .iter()
+                    .map(|(c, score)| (c.args[0].as_str(), *score))
+                    .collect::<Vec<_>>(),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn scope_key_test() {
+        use std::fs::{self, File};
+        use uuid::Uuid;
+
+        // Layout:
+        //   <tmp_root>/.git/
+        //   <tmp_root>/app/Makefile
+        //   <tmp_root>/app/src       <- current directory
+        let tmp_root = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        let current_dir = tmp_root.join("app").join("src");
+        fs::create_dir_all(&current_dir).unwrap();
+        fs::create_dir_all(tmp_root.join(".git")).unwrap();
+        File::create(tmp_root.join("app").join("Makefile")).unwrap();
+
+        struct Case {
+            title: &'static str,
+            scope: Scope,
+            expected: PathBuf,
+        }
+        let cases = vec![
+            Case {
+                title: "exact",
+                scope: Scope::Exact,
+                expected: current_dir.clone(),
+            },
+            Case {
+                title: "git root",
+                scope: Scope::GitRoot,
+                expected: tmp_root.clone(),
+            },
+            Case {
+                title: "runner root",
+                scope: Scope::RunnerRoot,
+                expected: tmp_root.join("app"),
+            },
+        ];
+        for case in cases {
+            assert_eq!(case.expected, case.scope.key(&current_dir), "\nFailed: 🚨{:?}🚨\n", case.title);
+        }
+
+        // It falls back to the current directory if the root is not found.
+        fs::remove_dir_all(tmp_root.join(".git")).unwrap();
+        assert_eq!(current_dir, Scope::GitRoot.key(&current_dir));
+
+        fs::remove_dir_all(&tmp_root).unwrap();
+    }
+
+    #[test]
+    fn histories_append_with_dir_test() {
+        let root = PathBuf::from("/code/app");
+        let command = |args: &str, dir: Option<&str>| HistoryCommand {
+            dir: dir.map(PathBuf::from),
+            ..HistoryCommand::new(runner_type::RunnerType::Make, vec![args.to_string()])
+        };
+        let histories = Histories { histories: vec![] }
+            .append(root.clone(), command("build", Some("/code/app/src")), DEFAULT_MAX_LENGTH)
+            .append(root.clone(), command("test", Some("/code/app")), DEFAULT_MAX_LENGTH)
+            .append(root.clone(), command("build", Some("/code/app")), DEFAULT_MAX_LENGTH);
+
+        // The directory is omitted if it is the same as the path of the history.
+        // The commands executed in different directories are stored separately.
+        assert_eq!(
+            vec![
+                command("build", None),
+                command("test", None),
+                command("build", Some("/code/app/src")),
+            ],
+            histories.histories[0].commands
+        );
+    }
+
     #[test]
     fn history_append_test() {
         struct Case {
@@ -214,35 +693,20 @@ mod test {
                 before: History {
                     path: path.clone(),
                     commands: vec![
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history0".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history1".to_string(),
-                        },
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history0".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history1".to_string()]),
                     ],
                 },
                 command_to_append: command::CommandForExec {
                     runner_type: runner_type::RunnerType::Make,
-                    args: "history2".to_string(),
+                    args: vec!["history2".to_string()],
                 },
                 after: History {
                     path: path.clone(),
                     commands: vec![
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history2".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history0".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history1".to_string(),
-                        },
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history2".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history0".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history1".to_string()]),
                     ],
                 },
             },
@@ -254,14 +718,14 @@ mod test {
                 },
                 command_to_append: command::CommandForExec {
                     runner_type: runner_type::RunnerType::Make,
-                    args: "history0".to_string(),
+                    args: vec!["history0".to_string()],
                 },
                 after: History {
                     path: path.clone(),
-                    commands: vec![HistoryCommand {
-                        runner_type: runner_type::RunnerType::Make,
-                        args: "history0".to_string(),
-                    }],
+                    commands: vec![HistoryCommand::new(
+                        runner_type::RunnerType::Make,
+                        vec!["history0".to_string()],
+                    )],
                 },
             },
             Case {
@@ -269,39 +733,21 @@ mod test {
                 before: History {
                     path: path.clone(),
                     commands: vec![
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history0".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history1".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history2".to_string(),
-                        },
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history0".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history1".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history2".to_string()]),
                     ],
                 },
                 command_to_append: command::CommandForExec {
                     runner_type: runner_type::RunnerType::Make,
-                    args: "history2".to_string(),
+                    args: vec!["history2".to_string()],
                 },
                 after: History {
                     path: path.clone(),
                     commands: vec![
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history2".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history0".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history1".to_string(),
-                        },
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history2".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history0".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history1".to_string()]),
                     ],
                 },
             },
@@ -310,422 +756,127 @@ mod test {
                 before: History {
                     path: path.clone(),
                     commands: vec![
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history0".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history1".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history2".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history3".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history4".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history5".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history6".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history7".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history8".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history9".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history10".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history11".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history12".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history13".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history14".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history15".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history16".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history17".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history18".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history19".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history20".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history21".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history22".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history23".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history24".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history25".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history26".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history27".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history28".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history29".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history30".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history31".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history32".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history33".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history34".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history35".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history36".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history37".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history38".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history39".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history40".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history41".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history42".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history43".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history44".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history45".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history46".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history47".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history48".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history49".to_string(),
-                        },
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history0".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history1".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history2".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history3".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history4".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history5".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history6".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history7".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history8".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history9".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history10".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history11".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history12".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history13".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history14".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history15".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history16".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history17".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history18".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history19".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history20".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history21".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history22".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history23".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history24".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history25".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history26".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history27".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history28".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history29".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history30".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history31".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history32".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history33".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history34".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history35".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history36".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history37".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history38".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history39".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history40".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history41".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history42".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history43".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history44".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history45".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history46".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history47".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history48".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history49".to_string()]),
                     ],
                 },
                 command_to_append: command::CommandForExec {
                     runner_type: runner_type::RunnerType::Make,
-                    args: "history50".to_string(),
+                    args: vec!["history50".to_string()],
                 },
                 after: History {
                     path: path.clone(),
                     commands: vec![
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history50".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history0".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history1".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history2".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history3".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history4".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history5".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history6".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history7".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history8".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history9".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history10".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history11".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history12".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history13".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history14".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history15".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history16".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history17".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history18".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history19".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history20".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history21".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history22".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history23".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history24".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history25".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history26".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history27".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history28".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history29".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history30".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history31".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history32".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history33".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history34".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history35".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history36".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history37".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history38".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history39".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history40".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history41".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history42".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history43".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history44".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history45".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history46".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history47".to_string(),
-                        },
-                        HistoryCommand {
-                            runner_type: runner_type::RunnerType::Make,
-                            args: "history48".to_string(),
-                        },
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history50".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history0".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history1".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history2".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history3".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history4".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history5".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history6".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history7".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history8".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history9".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history10".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history11".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history12".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history13".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history14".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history15".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history16".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history17".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history18".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history19".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history20".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history21".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history22".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history23".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history24".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history25".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history26".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history27".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history28".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history29".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history30".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history31".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history32".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history33".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history34".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history35".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history36".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history37".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history38".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history39".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history40".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history41".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history42".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history43".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history44".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history45".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history46".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history47".to_string()]),
+                        HistoryCommand::new(runner_type::RunnerType::Make, vec!["history48".to_string()]),
                     ],
                 },
             },
         ];
 
         for case in cases {
-            assert_eq!(case.after, case.before.append(case.command_to_append), "\nFailed: 🚨{:?}🚨\n", case.title,)
+            assert_eq!(
+                case.after,
+                case.before.append(case.command_to_append.into(), DEFAULT_MAX_LENGTH),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            )
         }
     }
 }
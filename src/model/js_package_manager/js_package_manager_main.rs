@@ -1,13 +1,26 @@
 use super::{npm, pnpm, yarn};
-use crate::model::command;
+use crate::model::{command, runner_type};
 use anyhow::Result;
 use codespan::Files;
 use json_spanned_value::{self as jsv, spanned};
-use std::{fs, path::PathBuf};
+use regex::Regex;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::OnceLock,
+};
 
 pub(super) const METADATA_FILE_NAME: &str = "package.json";
 const METADATA_PACKAGE_NAME_KEY: &str = "name";
 const METADATA_COMMAND_KEY: &str = "scripts";
+// ref: https://nodejs.org/api/packages.html#packagemanager
+const METADATA_PACKAGE_MANAGER_KEY: &str = "packageManager";
+const LOCKFILES: [(&str, runner_type::JsPackageManager); 3] = [
+    (npm::NPM_LOCKFILE_NAME, runner_type::JsPackageManager::Npm),
+    (pnpm::PNPM_LOCKFILE_NAME, runner_type::JsPackageManager::Pnpm),
+    (yarn::YARN_LOCKFILE_NAME, runner_type::JsPackageManager::Yarn),
+];
 
 #[allow(clippy::enum_variant_names)]
 #[derive(Clone, Debug, PartialEq)]
@@ -34,11 +47,11 @@ impl JsPackageManager {
         }
     }
 
-    pub fn execute(&self, command: &command::CommandForExec) -> Result<()> {
+    pub fn process_command(&self, command: &command::CommandForExec) -> process::Command {
         match self {
-            JsPackageManager::JsNpm(npm) => npm.execute(command),
-            JsPackageManager::JsPnpm(pnpm) => pnpm.execute(command),
-            JsPackageManager::JsYarn(yarn) => yarn.execute(command),
+            JsPackageManager::JsNpm(npm) => npm.process_command(command),
+            JsPackageManager::JsPnpm(pnpm) => pnpm.process_command(command),
+            JsPackageManager::JsYarn(yarn) => yarn.process_command(command),
         }
     }
 
@@ -50,7 +63,65 @@ impl JsPackageManager {
         }
     }
 
-    fn new(current_dir: PathBuf, file_names: Vec<String>) -> Option<Self> {
+    // new returns the runner and the warnings which should be shown to the user.
+    // The package manager declared by `packageManager` field(which is used by Corepack) is preferred to lockfiles.
+    fn new(current_dir: PathBuf, file_names: Vec<String>) -> (Option<Self>, Vec<String>) {
+        let lockfiles = find_lockfiles(&current_dir);
+        let mut warnings = vec![];
+
+        match declared_package_manager(&current_dir) {
+            Some(Ok(declared)) => {
+                let stray_lockfiles: Vec<String> = lockfiles
+                    .iter()
+                    .filter(|(package_manager, _)| *package_manager != declared)
+                    .map(|(_, path)| path.display().to_string())
+                    .collect();
+                if !stray_lockfiles.is_empty() {
+                    warnings.push(format!(
+                        "`packageManager` is {} but lockfiles of other package managers exist: {}",
+                        runner_type::RunnerType::JsPackageManager(declared.clone()),
+                        stray_lockfiles.join(", ")
+                    ));
+                }
+                return (Self::new_declared(declared, current_dir, file_names), warnings);
+            }
+            Some(Err(e)) => warnings.push(e),
+            None => {}
+        }
+
+        if lockfiles.len() > 1 {
+            warnings.push(format!(
+                "Conflicting lockfiles found: {}. Set `packageManager` field in package.json to choose the package manager.",
+                lockfiles
+                    .iter()
+                    .map(|(_, path)| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+            return (None, warnings);
+        }
+
+        (Self::new_by_lockfile(current_dir, file_names), warnings)
+    }
+
+    // new_declared does not require lockfiles because they may not be created yet in new projects.
+    fn new_declared(
+        package_manager: runner_type::JsPackageManager,
+        current_dir: PathBuf,
+        file_names: Vec<String>,
+    ) -> Option<Self> {
+        if !file_names.iter().any(|f| f == METADATA_FILE_NAME) {
+            return None;
+        }
+
+        match package_manager {
+            runner_type::JsPackageManager::Npm => npm::Npm::new_declared(current_dir).map(JsPackageManager::JsNpm),
+            runner_type::JsPackageManager::Pnpm => pnpm::Pnpm::new_declared(current_dir).map(JsPackageManager::JsPnpm),
+            runner_type::JsPackageManager::Yarn => yarn::Yarn::new_declared(current_dir).map(JsPackageManager::JsYarn),
+        }
+    }
+
+    fn new_by_lockfile(current_dir: PathBuf, file_names: Vec<String>) -> Option<Self> {
         if let Some(r) = pnpm::Pnpm::new(current_dir.clone(), file_names.clone()) {
             return Some(JsPackageManager::JsPnpm(r));
         }
@@ -103,7 +174,103 @@ impl JsPackageManager {
     }
 }
 
-pub fn get_js_package_manager_runner(current_dir: PathBuf) -> Option<JsPackageManager> {
+// script_metadata returns the metadata of the script `name` which includes its body and everything which actually runs.
+// `run_hooks` represents whether the package manager runs `pre`/`post` lifecycle hooks of the script.
+pub(super) fn script_metadata(
+    scripts: &[(String, String, u32)],
+    name: &str,
+    run_hooks: bool,
+) -> command::CommandMetadata {
+    let mut expanded_body = vec![];
+    expand_script(scripts, name, run_hooks, 0, &mut vec![], &mut expanded_body);
+
+    command::CommandMetadata {
+        body: scripts.iter().find(|(k, _, _)| k == name).map(|(_, v, _)| v.clone()),
+        expanded_body,
+        ..command::CommandMetadata::default()
+    }
+}
+
+// expand_script pushes `{indent}{script name} → {script body}` of the script and the scripts it runs to `result`.
+// The scripts referenced like `npm run lint` are expanded recursively with deeper indent.
+// `stack` holds the scripts which are being expanded to prevent infinite recursion by circular references.
+fn expand_script(
+    scripts: &[(String, String, u32)],
+    name: &str,
+    run_hooks: bool,
+    depth: usize,
+    stack: &mut Vec<String>,
+    result: &mut Vec<String>,
+) {
+    let Some((_, body, _)) = scripts.iter().find(|(k, _, _)| k == name) else {
+        return;
+    };
+    if stack.iter().any(|s| s == name) {
+        return;
+    }
+    stack.push(name.to_string());
+
+    // Lifecycle hooks of the hooks themselves are not run.
+    if run_hooks {
+        expand_script(scripts, &format!("pre{}", name), false, depth, stack, result);
+    }
+    result.push(format!("{}{} → {}", "  ".repeat(depth), name, body));
+    for referenced in referenced_scripts(body) {
+        expand_script(scripts, &referenced, true, depth + 1, stack, result);
+    }
+    if run_hooks {
+        expand_script(scripts, &format!("post{}", name), false, depth, stack, result);
+    }
+
+    stack.pop();
+}
+
+// referenced_scripts returns the names of the scripts called in `body` like `npm run build`, `pnpm build` or `yarn build`.
+// The names may not be scripts(e.g. `yarn install`), so callers should check whether they exist.
+fn referenced_scripts(body: &str) -> Vec<String> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r#"\b(?:npm|pnpm|yarn)\s+(?:run(?:-script)?\s+)?([^\s&|;'"]+)"#).unwrap());
+    re.captures_iter(body).map(|c| c[1].to_string()).collect()
+}
+
+// declared_package_manager reads `packageManager` field(e.g. `pnpm@9.1.0`) from the nearest package.json which has it.
+// Returns Some(Err) with a warning if the declared package manager is not supported.
+fn declared_package_manager(current_dir: &Path) -> Option<Result<runner_type::JsPackageManager, String>> {
+    current_dir.ancestors().find_map(|dir| {
+        let content = fs::read_to_string(dir.join(METADATA_FILE_NAME)).ok()?;
+        let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+        let value = json.get(METADATA_PACKAGE_MANAGER_KEY)?.as_str()?;
+        Some(parse_package_manager_field(value))
+    })
+}
+
+fn parse_package_manager_field(value: &str) -> Result<runner_type::JsPackageManager, String> {
+    // The format is `<name>@<version>(+<hash>)`.
+    match value.split('@').next().unwrap_or_default().trim() {
+        "npm" => Ok(runner_type::JsPackageManager::Npm),
+        "pnpm" => Ok(runner_type::JsPackageManager::Pnpm),
+        "yarn" => Ok(runner_type::JsPackageManager::Yarn),
+        _ => Err(format!("`packageManager` {} is not supported.", value)),
+    }
+}
+
+// find_lockfiles returns the lockfiles in the nearest directory which has any lockfile.
+fn find_lockfiles(current_dir: &Path) -> Vec<(runner_type::JsPackageManager, PathBuf)> {
+    for dir in current_dir.ancestors() {
+        let lockfiles: Vec<(runner_type::JsPackageManager, PathBuf)> = LOCKFILES
+            .iter()
+            .map(|(file_name, package_manager)| (package_manager.clone(), dir.join(file_name)))
+            .filter(|(_, path)| path.is_file())
+            .collect();
+        if !lockfiles.is_empty() {
+            return lockfiles;
+        }
+    }
+    vec![]
+}
+
+// get_js_package_manager_runner returns the runner and the warnings which should be shown to the user.
+pub fn get_js_package_manager_runner(current_dir: PathBuf) -> (Option<JsPackageManager>, Vec<String>) {
     let entries = fs::read_dir(current_dir.clone()).unwrap();
     let file_names = entries.map(|e| e.unwrap().file_name().into_string().unwrap()).collect();
 
@@ -114,6 +281,179 @@ pub fn get_js_package_manager_runner(current_dir: PathBuf) -> Option<JsPackageMa
 mod test {
     use super::*;
     use pretty_assertions::assert_eq;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_parse_package_manager_field() {
+        struct Case {
+            title: &'static str,
+            value: &'static str,
+            expected: Result<runner_type::JsPackageManager, String>,
+        }
+        let cases = vec![
+            Case {
+                title: "pnpm with version",
+                value: "pnpm@9.1.0",
+                expected: Ok(runner_type::JsPackageManager::Pnpm),
+            },
+            Case {
+                title: "yarn with version and hash",
+                value: "yarn@4.5.3+sha512.abc",
+                expected: Ok(runner_type::JsPackageManager::Yarn),
+            },
+            Case {
+                title: "npm",
+                value: "npm@10.8.2",
+                expected: Ok(runner_type::JsPackageManager::Npm),
+            },
+            Case {
+                title: "unsupported package manager",
+                value: "bun@1.1.0",
+                expected: Err("`packageManager` bun@1.1.0 is not supported.".to_string()),
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(case.expected, parse_package_manager_field(case.value), "\nfailed: 🚨{:?}🚨\n", case.title,);
+        }
+    }
+
+    #[test]
+    fn test_new_respects_package_manager_field_and_lockfiles() {
+        let write = |path: PathBuf, content: &str| {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        let file_names = vec![METADATA_FILE_NAME.to_string()];
+
+        // The root package.json declares pnpm, and there are stray lockfiles of npm.
+        // The child package.json does not declare packageManager, so the one in the root is used.
+        let root = std::env::temp_dir().join(format!("fzf-make-{}", Uuid::new_v4()));
+        write(root.join(METADATA_FILE_NAME), r#"{"packageManager": "pnpm@9.1.0"}"#);
+        write(root.join(npm::NPM_LOCKFILE_NAME), "{}");
+        write(root.join("packages/app/package.json"), r#"{"name": "app", "scripts": {"build": "tsc"}}"#);
+
+        let (runner, warnings) = JsPackageManager::new(root.join("packages/app"), file_names.clone());
+        assert!(matches!(runner, Some(JsPackageManager::JsPnpm(_))));
+        assert_eq!(
+            vec![format!(
+                "`packageManager` is pnpm but lockfiles of other package managers exist: {}",
+                root.join(npm::NPM_LOCKFILE_NAME).display()
+            )],
+            warnings
+        );
+
+        // Without packageManager field, conflicting lockfiles are not guessed.
+        let conflicting = std::env::temp_dir().join(format!("fzf-make-{}", Uuid::new_v4()));
+        write(conflicting.join(METADATA_FILE_NAME), r#"{"scripts": {"build": "tsc"}}"#);
+        write(conflicting.join(npm::NPM_LOCKFILE_NAME), "{}");
+        write(conflicting.join(yarn::YARN_LOCKFILE_NAME), "");
+
+        let (runner, warnings) = JsPackageManager::new(conflicting.clone(), file_names.clone());
+        assert_eq!(None, runner);
+        assert_eq!(
+            vec![format!(
+                "Conflicting lockfiles found: {}, {}. Set `packageManager` field in package.json to choose the package manager.",
+                conflicting.join(npm::NPM_LOCKFILE_NAME).display(),
+                conflicting.join(yarn::YARN_LOCKFILE_NAME).display(),
+            )],
+            warnings
+        );
+
+        fs::remove_dir_all(root).unwrap();
+        fs::remove_dir_all(conflicting).unwrap();
+    }
+
+    #[test]
+    fn test_script_metadata() {
+        let scripts: Vec<(String, String, u32)> = vec![
+            ("clean", "rimraf dist"),
+            ("prebuild", "npm run clean"),
+            ("build", "tsc -p . && npm run lint -- --fix && yarn install"),
+            ("postbuild", "echo done"),
+            ("lint", "eslint ."),
+            ("prelint", "echo prelint"),
+            ("ping", "pnpm pong"),
+            ("pong", "pnpm run ping"),
+            ("start", "node index.js"),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (k, v))| (k.to_string(), v.to_string(), i as u32 + 1))
+        .collect();
+
+        struct Case {
+            title: &'static str,
+            name: &'static str,
+            run_hooks: bool,
+            expected_body: Option<&'static str>,
+            expected_expanded_body: Vec<&'static str>,
+        }
+        let cases = vec![
+            Case {
+                title: "script without references",
+                name: "start",
+                run_hooks: true,
+                expected_body: Some("node index.js"),
+                expected_expanded_body: vec!["start → node index.js"],
+            },
+            Case {
+                title: "chained scripts and lifecycle hooks are expanded",
+                name: "build",
+                run_hooks: true,
+                expected_body: Some("tsc -p . && npm run lint -- --fix && yarn install"),
+                expected_expanded_body: vec![
+                    "prebuild → npm run clean",
+                    "  clean → rimraf dist",
+                    "build → tsc -p . && npm run lint -- --fix && yarn install",
+                    "  prelint → echo prelint",
+                    "  lint → eslint .",
+                    "postbuild → echo done",
+                ],
+            },
+            Case {
+                title: "lifecycle hooks are not expanded if the package manager does not run them",
+                name: "build",
+                run_hooks: false,
+                expected_body: Some("tsc -p . && npm run lint -- --fix && yarn install"),
+                expected_expanded_body: vec![
+                    "build → tsc -p . && npm run lint -- --fix && yarn install",
+                    "  prelint → echo prelint",
+                    "  lint → eslint .",
+                ],
+            },
+            Case {
+                title: "circular references do not cause infinite recursion",
+                name: "ping",
+                run_hooks: true,
+                expected_body: Some("pnpm pong"),
+                expected_expanded_body: vec!["ping → pnpm pong", "  pong → pnpm run ping"],
+            },
+            Case {
+                title: "script which does not exist",
+                name: "not_exist",
+                run_hooks: true,
+                expected_body: None,
+                expected_expanded_body: vec![],
+            },
+        ];
+
+        for case in cases {
+            let metadata = script_metadata(&scripts, case.name, case.run_hooks);
+            assert_eq!(
+                (
+                    case.expected_body.map(|b| b.to_string()),
+                    case.expected_expanded_body
+                        .iter()
+                        .map(|l| l.to_string())
+                        .collect::<Vec<_>>()
+                ),
+                (metadata.body, metadata.expanded_body),
+                "\nfailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
 
     #[test]
     fn test_parse_package_json() {
@@ -1,4 +1,5 @@
 pub(crate) mod js_package_manager_main;
 pub(super) mod npm;
 pub(super) mod pnpm;
+mod workspace;
 pub(super) mod yarn;
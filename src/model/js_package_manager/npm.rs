@@ -1,12 +1,18 @@
-use super::js_package_manager_main as js;
+use super::{js_package_manager_main as js, workspace};
 use crate::{
     file::path_to_content,
     model::{command, file_util, runner_type},
 };
 use anyhow::{Result, anyhow};
-use std::{path::PathBuf, process};
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
 
-const NPM_LOCKFILE_NAME: &str = "package-lock.json";
+pub(super) const NPM_LOCKFILE_NAME: &str = "package-lock.json";
+// npm runs `pre`/`post` lifecycle hooks of user defined scripts.
+// ref: https://docs.npmjs.com/cli/using-npm/scripts#pre--post-scripts
+const RUN_HOOKS: bool = true;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Npm {
@@ -16,22 +22,14 @@ pub struct Npm {
 
 impl Npm {
     pub fn command_to_run(&self, command: &command::CommandForExec) -> Result<String> {
-        Ok(format!("npm {}", command.args))
+        Ok(format!("npm {}", command.args_to_string()))
     }
 
-    pub fn execute(&self, command: &command::CommandForExec) -> Result<()> {
-        let child = process::Command::new("npm")
-            .stdin(process::Stdio::inherit())
-            .args(command.args.split_whitespace().collect::<Vec<&str>>())
-            .spawn();
-
-        match child {
-            Ok(mut child) => match child.wait() {
-                Ok(_) => Ok(()),
-                Err(e) => Err(anyhow!("failed to run: {}", e)),
-            },
-            Err(e) => Err(anyhow!("failed to spawn: {}", e)),
-        }
+    // process_command builds the process to run the command. It is spawned or exec-ed by Runner.
+    pub fn process_command(&self, command: &command::CommandForExec) -> process::Command {
+        let mut process_command = process::Command::new("npm");
+        process_command.stdin(process::Stdio::inherit()).args(&command.args);
+        process_command
     }
 
     pub fn new(current_dir: PathBuf, cwd_file_names: Vec<String>) -> Option<Npm> {
@@ -58,6 +56,14 @@ impl Npm {
         }
     }
 
+    // new_declared is used when npm is declared by `packageManager` field. Lockfiles are not required.
+    pub fn new_declared(current_dir: PathBuf) -> Option<Npm> {
+        Npm::collect_workspace_scripts(current_dir.clone()).map(|commands| Npm {
+            path: current_dir,
+            commands,
+        })
+    }
+
     // scripts_to_commands collects all scripts by following steps:
     // 1. Collect scripts defined in package.json in the current directory(which fzf-make is launched)
     // 2. Collect the paths of all `package.json` in the workspace.
@@ -67,10 +73,14 @@ impl Npm {
         let mut result = Self::collect_scripts_in_package_json(current_dir.clone())?;
 
         // Collect the paths of all `package.json` in the workspace.
+        // `npm query` is used only when the workspace can not be resolved from package.json.
         // If npm query fails (e.g., for non-monorepo projects), just return scripts from current package.json
-        let workspace_package_json_paths = match Self::get_workspace_packages() {
-            Ok(result) => result,
-            Err(_) => return Some(result),
+        let workspace_package_json_paths = match workspace::package_json_workspaces(&current_dir) {
+            Some(paths) => paths,
+            None => match Self::get_workspace_packages(&current_dir) {
+                Ok(result) => result,
+                Err(_) => return Some(result),
+            },
         };
 
         // Collect all scripts defined in given `package.json` paths.
@@ -83,15 +93,18 @@ impl Npm {
             if let Ok(c) = path_to_content::path_to_content(&path)
                 && let Some((name, parsing_result)) = js::JsPackageManager::parse_package_json(&c)
             {
-                for (key, _, line_number) in parsing_result {
-                    result.push(command::CommandWithPreview::new(
-                        runner_type::RunnerType::JsPackageManager(runner_type::JsPackageManager::Npm),
-                        // npm executes workspace script following format: `npm run {script_name} --workspace={package_name}`
-                        // e.g. `npm run build --workspace=app1`
-                        format!("run {} --workspace={}", key.as_str(), name.clone()),
-                        path.clone(),
-                        line_number,
-                    ));
+                for (key, _, line_number) in &parsing_result {
+                    result.push(
+                        command::CommandWithPreview::new(
+                            runner_type::RunnerType::JsPackageManager(runner_type::JsPackageManager::Npm),
+                            // npm executes workspace script following format: `npm run {script_name} --workspace={package_name}`
+                            // e.g. `npm run build --workspace=app1`
+                            format!("run {} --workspace={}", key.as_str(), name.clone()),
+                            path.clone(),
+                            *line_number,
+                        )
+                        .with_metadata(js::script_metadata(&parsing_result, key, RUN_HOOKS)),
+                    );
                 }
             };
         }
@@ -119,6 +132,11 @@ impl Npm {
                         current_dir.clone().join(js::METADATA_FILE_NAME),
                         *line_number,
                     )
+                    .with_metadata(js::script_metadata(
+                        &parsed_scripts_part_of_package_json,
+                        key,
+                        RUN_HOOKS,
+                    ))
                 })
                 .collect(),
         )
@@ -126,11 +144,12 @@ impl Npm {
 
     // get_workspace_packages uses `npm query .workspace` to get workspace package.json paths.
     // This requires npm 8.16.0+ but provides a clean JSON output.
-    fn get_workspace_packages() -> Result<Vec<PathBuf>> {
+    fn get_workspace_packages(current_dir: &Path) -> Result<Vec<PathBuf>> {
         let output = process::Command::new("npm")
             .arg("query")
             .arg(".workspace")
             .arg("--json")
+            .current_dir(current_dir)
             .output()?;
 
         if !output.status.success() {
@@ -195,7 +214,7 @@ mod test {
 
         let command = command::CommandForExec {
             runner_type: runner_type::RunnerType::JsPackageManager(runner_type::JsPackageManager::Npm),
-            args: "run build".to_string(),
+            args: vec!["run".to_string(), "build".to_string()],
         };
 
         let result = npm.command_to_run(&command);
@@ -213,7 +232,7 @@ mod test {
 
         let command = command::CommandForExec {
             runner_type: runner_type::RunnerType::JsPackageManager(runner_type::JsPackageManager::Npm),
-            args: "run build --workspace=app1".to_string(),
+            args: vec!["run".to_string(), "build".to_string(), "--workspace=app1".to_string()],
         };
 
         let result = npm.command_to_run(&command);
@@ -1,12 +1,20 @@
-use super::js_package_manager_main as js;
+use super::{js_package_manager_main as js, workspace};
 use crate::{
     file::path_to_content,
     model::{command, file_util, runner_type},
 };
-use anyhow::{Result, anyhow};
-use std::{path::PathBuf, process, process::Command, sync::OnceLock};
+use anyhow::Result;
+use std::{
+    path::{Path, PathBuf},
+    process,
+    process::Command,
+    sync::OnceLock,
+};
 
-const PNPM_LOCKFILE_NAME: &str = "pnpm-lock.yaml";
+pub(super) const PNPM_LOCKFILE_NAME: &str = "pnpm-lock.yaml";
+// pnpm runs `pre`/`post` lifecycle hooks of user defined scripts since v8(`enable-pre-post-scripts` is true by default).
+// ref: https://pnpm.io/npmrc#enable-pre-post-scripts
+const RUN_HOOKS: bool = true;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Pnpm {
@@ -16,22 +24,14 @@ pub struct Pnpm {
 
 impl Pnpm {
     pub fn command_to_run(&self, command: &command::CommandForExec) -> Result<String> {
-        Ok(format!("pnpm {}", command.args))
+        Ok(format!("pnpm {}", command.args_to_string()))
     }
 
-    pub fn execute(&self, command: &command::CommandForExec) -> Result<()> {
-        let child = process::Command::new("pnpm")
-            .stdin(process::Stdio::inherit())
-            .args(command.args.split_whitespace().collect::<Vec<&str>>())
-            .spawn();
-
-        match child {
-            Ok(mut child) => match child.wait() {
-                Ok(_) => Ok(()),
-                Err(e) => Err(anyhow!("failed to run: {}", e)),
-            },
-            Err(e) => Err(anyhow!("failed to spawn: {}", e)),
-        }
+    // process_command builds the process to run the command. It is spawned or exec-ed by Runner.
+    pub fn process_command(&self, command: &command::CommandForExec) -> process::Command {
+        let mut process_command = process::Command::new("pnpm");
+        process_command.stdin(process::Stdio::inherit()).args(&command.args);
+        process_command
     }
 
     pub fn new(current_dir: PathBuf, cwd_file_names: Vec<String>) -> Option<Pnpm> {
@@ -58,6 +58,14 @@ impl Pnpm {
         }
     }
 
+    // new_declared is used when pnpm is declared by `packageManager` field. Lockfiles are not required.
+    pub fn new_declared(current_dir: PathBuf) -> Option<Pnpm> {
+        Pnpm::collect_workspace_scripts(current_dir.clone()).map(|commands| Pnpm {
+            path: current_dir,
+            commands,
+        })
+    }
+
     // scripts_to_commands collects all scripts by following steps:
     // 1. Collect scripts defined in package.json in the current directory(which fzf-make is launched)
     // 2. Collect the paths of all `package.json` in the workspace.
@@ -67,9 +75,13 @@ impl Pnpm {
         let mut result = Self::collect_scripts_in_package_json(current_dir.clone())?;
 
         // Collect the paths of all `package.json` in the workspace.
-        let workspace_package_json_paths = match Self::get_workspace_packages() {
-            Ok(result) => result,
-            Err(_) => return None,
+        // `pnpm -r exec pwd` is used only when the workspace can not be resolved from pnpm-workspace.yaml.
+        let workspace_package_json_paths = match workspace::pnpm_workspaces(&current_dir) {
+            Some(paths) => paths,
+            None => match Self::get_workspace_packages(&current_dir) {
+                Ok(result) => result,
+                Err(_) => return None,
+            },
         };
 
         // Collect all scripts defined in given `package.json` paths.
@@ -82,21 +94,24 @@ impl Pnpm {
             if let Ok(c) = path_to_content::path_to_content(&path)
                 && let Some((name, parsing_result)) = js::JsPackageManager::parse_package_json(&c)
             {
-                for (key, value, line_number) in parsing_result {
-                    if Self::use_filtering(value) {
+                for (key, value, line_number) in &parsing_result {
+                    if Self::use_filtering(value.to_string()) {
                         continue;
                     }
-                    if Self::is_hidden_script(&key) {
+                    if Self::is_hidden_script(key) {
                         continue;
                     }
-                    result.push(command::CommandWithPreview::new(
-                        runner_type::RunnerType::JsPackageManager(runner_type::JsPackageManager::Pnpm),
-                        // pnpm executes workspace script following format: `pnpm --filter {package_name} {script_name}`
-                        // e.g. `pnpm --filter app4 build`
-                        format!("--filter {} {}", name.clone(), key.as_str()),
-                        path.clone(),
-                        line_number,
-                    ));
+                    result.push(
+                        command::CommandWithPreview::new(
+                            runner_type::RunnerType::JsPackageManager(runner_type::JsPackageManager::Pnpm),
+                            // pnpm executes workspace script following format: `pnpm --filter {package_name} {script_name}`
+                            // e.g. `pnpm --filter app4 build`
+                            format!("--filter {} {}", name.clone(), key.as_str()),
+                            path.clone(),
+                            *line_number,
+                        )
+                        .with_metadata(js::script_metadata(&parsing_result, key, RUN_HOOKS)),
+                    );
                 }
             };
         }
@@ -126,17 +141,23 @@ impl Pnpm {
                         current_dir.clone().join(js::METADATA_FILE_NAME),
                         *line_number,
                     )
+                    .with_metadata(js::script_metadata(
+                        &parsed_scripts_part_of_package_json,
+                        key,
+                        RUN_HOOKS,
+                    ))
                 })
                 .collect(),
         )
     }
 
     // get_workspaces_list parses the result of `pnpm -r exec pwd` and return path of `package.json` of each package.
-    fn get_workspace_packages() -> Result<Vec<PathBuf>> {
+    fn get_workspace_packages(current_dir: &Path) -> Result<Vec<PathBuf>> {
         let output = process::Command::new("pnpm")
             .arg("-r")
             .arg("exec")
             .arg("pwd")
+            .current_dir(current_dir)
             .output()?;
         /* Example output:
             /Users/kyu08/code/fzf-make/test_data/pnpm_monorepo/packages/app1
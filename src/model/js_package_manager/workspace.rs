@@ -0,0 +1,287 @@
+use super::js_package_manager_main as js;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+const PACKAGE_JSON_WORKSPACES_KEY: &str = "workspaces";
+const PNPM_WORKSPACE_FILE_NAME: &str = "pnpm-workspace.yaml";
+const NODE_MODULES: &str = "node_modules";
+
+// These functions resolve workspaces without spawning package managers.
+// They return None only when the workspace can not be determined(e.g. the file is broken)
+// so that callers can fall back to asking the package manager.
+
+// package_json_workspaces resolves the workspace packages from `workspaces` field in `package.json` in `root_dir`.
+// It is used by npm and yarn.
+// ref: https://docs.npmjs.com/cli/using-npm/workspaces
+pub fn package_json_workspaces(root_dir: &Path) -> Option<Vec<PathBuf>> {
+    let content = fs::read_to_string(root_dir.join(js::METADATA_FILE_NAME)).ok()?;
+    let patterns = parse_package_json_workspaces(&content)?;
+    Some(resolve_workspace_patterns(root_dir, &patterns))
+}
+
+// pnpm_workspaces resolves the workspace packages from `packages` field in `pnpm-workspace.yaml` in `root_dir`.
+// ref: https://pnpm.io/pnpm-workspace_yaml
+pub fn pnpm_workspaces(root_dir: &Path) -> Option<Vec<PathBuf>> {
+    let content = match fs::read_to_string(root_dir.join(PNPM_WORKSPACE_FILE_NAME)) {
+        Ok(content) => content,
+        // pnpm-workspace.yaml is required to declare a workspace.
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Some(vec![]),
+        Err(_) => return None,
+    };
+    let patterns = parse_pnpm_workspace_yaml(&content)?;
+    Some(resolve_workspace_patterns(root_dir, &patterns))
+}
+
+// parse_package_json_workspaces supports both of the array form and the object form(yarn v1).
+// e.g. `"workspaces": ["packages/*"]` or `"workspaces": {"packages": ["packages/*"]}`
+fn parse_package_json_workspaces(content: &str) -> Option<Vec<String>> {
+    let json: serde_json::Value = serde_json::from_str(content).ok()?;
+    let Some(workspaces) = json.get(PACKAGE_JSON_WORKSPACES_KEY) else {
+        return Some(vec![]);
+    };
+    let patterns = match workspaces {
+        serde_json::Value::Array(_) => workspaces,
+        serde_json::Value::Object(_) => workspaces.get("packages")?,
+        _ => return None,
+    };
+
+    patterns.as_array().map(|patterns| {
+        patterns
+            .iter()
+            .filter_map(|p| p.as_str().map(|p| p.to_string()))
+            .collect()
+    })
+}
+
+fn parse_pnpm_workspace_yaml(content: &str) -> Option<Vec<String>> {
+    let yaml: serde_yaml_ng::Value = serde_yaml_ng::from_str(content).ok()?;
+    let Some(packages) = yaml.get("packages") else {
+        return Some(vec![]);
+    };
+    packages.as_sequence().map(|patterns| {
+        patterns
+            .iter()
+            .filter_map(|p| p.as_str().map(|p| p.to_string()))
+            .collect()
+    })
+}
+
+// resolve_workspace_patterns returns the paths of `package.json` of the packages which match `patterns`.
+// Patterns starting with `!` exclude the packages matched by the rest of the pattern.
+// The package.json in `root_dir` itself is not included.
+fn resolve_workspace_patterns(root_dir: &Path, patterns: &[String]) -> Vec<PathBuf> {
+    let normalize = |pattern: &str| pattern.trim_start_matches("./").trim_end_matches('/').to_string();
+    let (negated, included): (Vec<&String>, Vec<&String>) = patterns.iter().partition(|p| p.starts_with('!'));
+    let negated: Vec<glob::Pattern> = negated
+        .iter()
+        .filter_map(|p| glob::Pattern::new(&normalize(&p[1..])).ok())
+        .collect();
+
+    let mut result = vec![];
+    for pattern in included {
+        // root_dir is escaped since it may contain glob metacharacters(e.g. `[`) which must be matched literally.
+        let pattern = Path::new(&glob::Pattern::escape(&root_dir.to_string_lossy()))
+            .join(normalize(pattern))
+            .join(js::METADATA_FILE_NAME);
+        let Ok(paths) = glob::glob(&pattern.to_string_lossy()) else {
+            continue;
+        };
+
+        for package_json in paths.flatten() {
+            let Some(package_dir) = package_json.parent().and_then(|dir| dir.strip_prefix(root_dir).ok()) else {
+                continue;
+            };
+            if package_dir.as_os_str().is_empty()
+                || package_dir.components().any(|c| c.as_os_str() == NODE_MODULES)
+                || negated.iter().any(|n| n.matches_path(package_dir))
+                || result.contains(&package_json)
+            {
+                continue;
+            }
+            result.push(package_json);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_package_json_workspaces_test() {
+        struct Case {
+            title: &'static str,
+            content: &'static str,
+            expected: Option<Vec<&'static str>>,
+        }
+        let cases = vec![
+            Case {
+                title: "array form",
+                content: r#"{"workspaces": ["packages/*", "!packages/ignored"]}"#,
+                expected: Some(vec!["packages/*", "!packages/ignored"]),
+            },
+            Case {
+                title: "object form(yarn v1)",
+                content: r#"{"workspaces": {"packages": ["packages/*"], "nohoist": ["**/react"]}}"#,
+                expected: Some(vec!["packages/*"]),
+            },
+            Case {
+                title: "workspaces is not declared",
+                content: r#"{"name": "project"}"#,
+                expected: Some(vec![]),
+            },
+            Case {
+                title: "invalid json",
+                content: "not a json",
+                expected: None,
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(
+                case.expected
+                    .map(|e| e.iter().map(|p| p.to_string()).collect::<Vec<_>>()),
+                parse_package_json_workspaces(case.content),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_workspaces_test() {
+        struct Case {
+            title: &'static str,
+            root_dir: &'static str,
+            resolve: fn(&Path) -> Option<Vec<PathBuf>>,
+            expected: Option<Vec<&'static str>>,
+        }
+        let cases = vec![
+            Case {
+                title: "npm: workspaces in package.json",
+                root_dir: "test_data/npm_monorepo",
+                resolve: package_json_workspaces,
+                expected: Some(vec!["packages/app1", "packages/app2", "packages/app3"]),
+            },
+            Case {
+                title: "yarn: workspaces in package.json",
+                root_dir: "test_data/yarn_monorepo",
+                resolve: package_json_workspaces,
+                expected: Some(vec!["packages/app1", "packages/app2", "packages/app3"]),
+            },
+            Case {
+                title: "pnpm: packages in pnpm-workspace.yaml",
+                root_dir: "test_data/pnpm_monorepo",
+                resolve: pnpm_workspaces,
+                expected: Some(vec![
+                    "packages/app1",
+                    "packages/app2",
+                    "packages/app3",
+                    "packages/sub_packages/sub_app",
+                ]),
+            },
+            Case {
+                title: "pnpm-workspace.yaml does not exist",
+                root_dir: "test_data/npm_monorepo",
+                resolve: pnpm_workspaces,
+                expected: Some(vec![]),
+            },
+            Case {
+                title: "workspaces is not declared in package.json",
+                root_dir: "test_data/pnpm_monorepo",
+                resolve: package_json_workspaces,
+                expected: Some(vec![]),
+            },
+            Case {
+                title: "package.json does not exist",
+                root_dir: "test_data/not_exist",
+                resolve: package_json_workspaces,
+                expected: None,
+            },
+        ];
+
+        for case in cases {
+            let root_dir = PathBuf::from(case.root_dir);
+            assert_eq!(
+                case.expected.map(|e| e
+                    .iter()
+                    .map(|dir| root_dir.join(dir).join(js::METADATA_FILE_NAME))
+                    .collect::<Vec<_>>()),
+                (case.resolve)(&root_dir),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_workspace_patterns_test() {
+        struct Case {
+            title: &'static str,
+            patterns: Vec<&'static str>,
+            expected: Vec<&'static str>,
+        }
+        let cases = vec![
+            Case {
+                title: "negated pattern excludes packages",
+                patterns: vec!["packages/*", "!packages/app2"],
+                expected: vec!["packages/app1", "packages/app3"],
+            },
+            Case {
+                title: "recursive pattern and duplicated matches",
+                patterns: vec!["./packages/**/", "packages/app1"],
+                expected: vec![
+                    "packages/app1",
+                    "packages/app2",
+                    "packages/app3",
+                    "packages",
+                    "packages/sub_packages/sub_app",
+                ],
+            },
+            Case {
+                title: "negated pattern with wildcard",
+                patterns: vec!["packages/**", "!packages/sub_packages/**", "!packages"],
+                expected: vec!["packages/app1", "packages/app2", "packages/app3"],
+            },
+            Case {
+                title: "pattern which matches nothing",
+                patterns: vec!["apps/*"],
+                expected: vec![],
+            },
+        ];
+
+        let root_dir = PathBuf::from("test_data/pnpm_monorepo");
+        for case in cases {
+            let patterns: Vec<String> = case.patterns.iter().map(|p| p.to_string()).collect();
+            assert_eq!(
+                case.expected
+                    .iter()
+                    .map(|dir| root_dir.join(dir).join(js::METADATA_FILE_NAME))
+                    .collect::<Vec<_>>(),
+                resolve_workspace_patterns(&root_dir, &patterns),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_workspace_patterns_with_glob_metacharacters_in_root_dir_test() {
+        let dir = std::env::temp_dir().join(format!("fzf-make-workspace-{}", uuid::Uuid::new_v4()));
+        let root_dir = dir.join("project[1]");
+        fs::create_dir_all(root_dir.join("packages/app1")).unwrap();
+        fs::write(root_dir.join("packages/app1").join(js::METADATA_FILE_NAME), "{}").unwrap();
+
+        assert_eq!(
+            vec![root_dir.join("packages/app1").join(js::METADATA_FILE_NAME)],
+            resolve_workspace_patterns(&root_dir, &["packages/*".to_string()]),
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,12 +1,16 @@
-use super::js_package_manager_main as js;
+use super::{js_package_manager_main as js, workspace};
 use crate::{
     file::path_to_content,
     model::{command, runner_type},
 };
 use anyhow::{Result, anyhow};
-use std::{path::PathBuf, process};
+use std::{
+    cell::OnceCell,
+    path::{Path, PathBuf},
+    process,
+};
 
-const YARN_LOCKFILE_NAME: &str = "yarn.lock";
+pub(super) const YARN_LOCKFILE_NAME: &str = "yarn.lock";
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Yarn {
@@ -14,36 +18,70 @@ pub struct Yarn {
     commands: Vec<command::CommandWithPreview>,
 }
 
+#[derive(Clone, Copy)]
 enum YarnVersion {
     V1,
     V2OrLater,
 }
 
-impl Yarn {
-    pub fn command_to_run(&self, command: &command::CommandForExec) -> Result<String> {
-        Ok(format!("yarn {}", command.args))
+impl YarnVersion {
+    // Only yarn v1 runs `pre`/`post` lifecycle hooks of user defined scripts.
+    // ref: https://yarnpkg.com/advanced/lifecycle-scripts
+    fn run_hooks(&self) -> bool {
+        matches!(self, YarnVersion::V1)
     }
+}
 
-    pub fn execute(&self, command: &command::CommandForExec) -> Result<()> {
-        let child = process::Command::new("yarn")
-            .stdin(process::Stdio::inherit())
-            .args(command.args.split_whitespace().collect::<Vec<&str>>())
-            .spawn();
+// LazyYarnVersion runs `yarn --version` at most once and only when the version is actually needed,
+// since spawning yarn takes a while.
+struct LazyYarnVersion {
+    // current_dir is the directory where yarn is run.
+    current_dir: PathBuf,
+    version: OnceCell<Option<YarnVersion>>,
+}
 
-        match child {
-            Ok(mut child) => match child.wait() {
-                Ok(_) => Ok(()),
-                Err(e) => Err(anyhow!("failed to run: {}", e)),
-            },
-            Err(e) => Err(anyhow!("failed to spawn: {}", e)),
+impl LazyYarnVersion {
+    fn new(current_dir: PathBuf) -> Self {
+        Self {
+            current_dir,
+            version: OnceCell::new(),
         }
     }
 
+    fn get(&self) -> Option<YarnVersion> {
+        *self.version.get_or_init(|| Yarn::get_yarn_version(&self.current_dir))
+    }
+
+    // run_hooks asks the version only when `scripts` has lifecycle hooks which would be run.
+    fn run_hooks(&self, scripts: &[(String, String, u32)]) -> bool {
+        let is_hook = |name: &str| {
+            [name.strip_prefix("pre"), name.strip_prefix("post")]
+                .into_iter()
+                .flatten()
+                .any(|target| scripts.iter().any(|(k, _, _)| k == target))
+        };
+        scripts.iter().any(|(k, _, _)| is_hook(k)) && self.get().is_some_and(|v| v.run_hooks())
+    }
+}
+
+impl Yarn {
+    pub fn command_to_run(&self, command: &command::CommandForExec) -> Result<String> {
+        Ok(format!("yarn {}", command.args_to_string()))
+    }
+
+    // process_command builds the process to run the command. It is spawned or exec-ed by Runner.
+    pub fn process_command(&self, command: &command::CommandForExec) -> process::Command {
+        let mut process_command = process::Command::new("yarn");
+        process_command.stdin(process::Stdio::inherit()).args(&command.args);
+        process_command
+    }
+
     pub fn new(current_dir: PathBuf, cwd_file_names: Vec<String>) -> Option<Yarn> {
         Iterator::find(&mut cwd_file_names.iter(), |&f| f == js::METADATA_FILE_NAME)?;
+        let yarn_version = LazyYarnVersion::new(current_dir.clone());
         if Iterator::find(&mut cwd_file_names.iter(), |&f| f == YARN_LOCKFILE_NAME).is_some() {
             // package.json and yarn.lock exist
-            match Yarn::collect_workspace_scripts(current_dir.clone()) {
+            match Yarn::collect_workspace_scripts(current_dir.clone(), &yarn_version) {
                 Some(commands) => {
                     return Some(Yarn {
                         path: current_dir,
@@ -56,18 +94,20 @@ impl Yarn {
         // package.json exists, but yarn.lock does not exist
 
         // executed in child packages of yarn workspaces || not in yarn workspace (including using an other package manager)
-        match Self::get_yarn_version() {
-            Some(yarn_version) => {
-                let workspace_output = match yarn_version {
+        match yarn_version.get() {
+            Some(version) => {
+                let workspace_output = match version {
                     YarnVersion::V1 => process::Command::new("yarn")
                         .arg("workspaces")
                         .arg("info")
                         .arg("--json")
+                        .current_dir(&current_dir)
                         .output(),
                     YarnVersion::V2OrLater => process::Command::new("yarn")
                         .arg("workspaces")
                         .arg("list")
                         .arg("--json")
+                        .current_dir(&current_dir)
                         .output(),
                 };
                 let workspace_output = match workspace_output {
@@ -81,7 +121,7 @@ impl Yarn {
                 }
 
                 // not in yarn workspace, but has a package.json
-                Self::collect_scripts_in_package_json(current_dir.clone()).map(|commands| Yarn {
+                Self::collect_scripts_in_package_json(current_dir.clone(), &yarn_version).map(|commands| Yarn {
                     path: current_dir,
                     commands,
                 })
@@ -90,6 +130,16 @@ impl Yarn {
         }
     }
 
+    // new_declared is used when yarn is declared by `packageManager` field. Lockfiles are not required.
+    pub fn new_declared(current_dir: PathBuf) -> Option<Yarn> {
+        Yarn::collect_workspace_scripts(current_dir.clone(), &LazyYarnVersion::new(current_dir.clone())).map(
+            |commands| Yarn {
+                path: current_dir,
+                commands,
+            },
+        )
+    }
+
     pub fn to_commands(&self) -> Vec<command::CommandWithPreview> {
         self.commands.clone()
     }
@@ -98,15 +148,22 @@ impl Yarn {
     // 1. Collect scripts defined in package.json in the current directory(which fzf-make is launched)
     // 2. Collect the paths of all `package.json` in the workspace.
     // 3. Collect all scripts defined in given `package.json` paths.
-    fn collect_workspace_scripts(current_dir: PathBuf) -> Option<Vec<command::CommandWithPreview>> {
+    fn collect_workspace_scripts(
+        current_dir: PathBuf,
+        yarn_version: &LazyYarnVersion,
+    ) -> Option<Vec<command::CommandWithPreview>> {
         // Collect scripts defined in package.json in the current directory(which fzf-make is launched)
-        let mut result = Self::collect_scripts_in_package_json(current_dir.clone())?;
+        let mut result = Self::collect_scripts_in_package_json(current_dir.clone(), yarn_version)?;
 
         // Collect the paths of all `package.json` in the workspace.
-        let package_json_in_workspace = match Self::get_yarn_version() {
-            Some(YarnVersion::V1) => Self::get_workspace_packages_for_v1(),
-            Some(YarnVersion::V2OrLater) => Self::get_workspace_packages_for_v2_or_later(),
-            None => return None,
+        // `yarn workspaces (info|list)` is used only when the workspace can not be resolved from package.json.
+        let package_json_in_workspace = match workspace::package_json_workspaces(&current_dir) {
+            Some(paths) => Ok(paths),
+            None => match yarn_version.get() {
+                Some(YarnVersion::V1) => Self::get_workspace_packages_for_v1(&current_dir),
+                Some(YarnVersion::V2OrLater) => Self::get_workspace_packages_for_v2_or_later(&current_dir),
+                None => return None,
+            },
         };
 
         // Collect all scripts defined in given `package.json` paths.
@@ -115,15 +172,23 @@ impl Yarn {
                 if let Ok(c) = path_to_content::path_to_content(&path)
                     && let Some((name, parsing_result)) = js::JsPackageManager::parse_package_json(&c)
                 {
-                    for (key, _, line_number) in parsing_result {
-                        result.push(command::CommandWithPreview::new(
-                            runner_type::RunnerType::JsPackageManager(runner_type::JsPackageManager::Yarn),
-                            // yarn executes workspace script following format: `yarn workspace {package_name} {script_name}`
-                            // e.g. `yarn workspace app4 build`
-                            format!("workspace {} {}", name.clone(), key.as_str()),
-                            path.clone(),
-                            line_number,
-                        ));
+                    let run_hooks = yarn_version.run_hooks(&parsing_result);
+                    for (key, _, line_number) in &parsing_result {
+                        result.push(
+                            command::CommandWithPreview::new(
+                                runner_type::RunnerType::JsPackageManager(runner_type::JsPackageManager::Yarn),
+                                // yarn executes workspace script following format: `yarn workspace {package_name} {script_name}`
+                                // e.g. `yarn workspace app4 build`
+                                format!("workspace {} {}", name.clone(), key.as_str()),
+                                path.clone(),
+                                *line_number,
+                            )
+                            .with_metadata(js::script_metadata(
+                                &parsing_result,
+                                key,
+                                run_hooks,
+                            )),
+                        );
                     }
                 };
             }
@@ -132,7 +197,10 @@ impl Yarn {
         Some(result)
     }
 
-    fn collect_scripts_in_package_json(current_dir: PathBuf) -> Option<Vec<command::CommandWithPreview>> {
+    fn collect_scripts_in_package_json(
+        current_dir: PathBuf,
+        yarn_version: &LazyYarnVersion,
+    ) -> Option<Vec<command::CommandWithPreview>> {
         let parsed_scripts_part_of_package_json =
             match path_to_content::path_to_content(&current_dir.join(js::METADATA_FILE_NAME)) {
                 Ok(c) => match js::JsPackageManager::parse_package_json(&c) {
@@ -142,6 +210,7 @@ impl Yarn {
                 Err(_) => return None,
             };
 
+        let run_hooks = yarn_version.run_hooks(&parsed_scripts_part_of_package_json);
         Some(
             parsed_scripts_part_of_package_json
                 .iter()
@@ -152,6 +221,11 @@ impl Yarn {
                         current_dir.clone().join(js::METADATA_FILE_NAME),
                         *line_number,
                     )
+                    .with_metadata(js::script_metadata(
+                        &parsed_scripts_part_of_package_json,
+                        key,
+                        run_hooks,
+                    ))
                 })
                 .collect(),
         )
@@ -165,8 +239,11 @@ impl Yarn {
     /// - `Some(YarnVersion::V1)` if Yarn v1 is detected.
     /// - `Some(YarnVersion::V2OrLater)` if Yarn v2 or later is detected.
     /// - `None` if Yarn is not installed or cannot be executed.
-    fn get_yarn_version() -> Option<YarnVersion> {
-        let output = process::Command::new("yarn").arg("--version").output();
+    fn get_yarn_version(current_dir: &Path) -> Option<YarnVersion> {
+        let output = process::Command::new("yarn")
+            .arg("--version")
+            .current_dir(current_dir)
+            .output();
 
         match output {
             Ok(output) => {
@@ -190,11 +267,12 @@ impl Yarn {
     }
 
     // get_workspaces_list parses the result of `yarn workspaces info --json` and return path of `package.json` of each package.
-    fn get_workspace_packages_for_v1() -> Result<Vec<PathBuf>> {
+    fn get_workspace_packages_for_v1(current_dir: &Path) -> Result<Vec<PathBuf>> {
         let output = process::Command::new("yarn")
             .arg("workspaces")
             .arg("info")
             .arg("--json")
+            .current_dir(current_dir)
             .output()?;
         /* Example output:
         yarn workspaces v1.22.22
@@ -244,16 +322,17 @@ impl Yarn {
 
         Ok(workspaces
             .iter()
-            .map(|w| PathBuf::from(w.location.clone()).join(js::METADATA_FILE_NAME))
+            .map(|w| current_dir.join(&w.location).join(js::METADATA_FILE_NAME))
             .collect())
     }
 
     // get_workspaces_list parses the result of `yarn workspaces list --json` and return path of `package.json` of each package.
-    fn get_workspace_packages_for_v2_or_later() -> Result<Vec<PathBuf>> {
+    fn get_workspace_packages_for_v2_or_later(current_dir: &Path) -> Result<Vec<PathBuf>> {
         let output = process::Command::new("yarn")
             .arg("workspaces")
             .arg("list")
             .arg("--json")
+            .current_dir(current_dir)
             .output()?;
 
         // The format is the same as v1 by chance, so we do not unify intentionally.
@@ -279,7 +358,61 @@ impl Yarn {
         Ok(workspaces
             .iter()
             .filter(|workspace| workspace.location != ".") // Ignore package.json in the current directory.
-            .map(|w| PathBuf::from(w.location.clone()).join(js::METADATA_FILE_NAME))
+            .map(|w| current_dir.join(&w.location).join(js::METADATA_FILE_NAME))
             .collect())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn lazy_yarn_version_run_hooks_test() {
+        struct Case {
+            title: &'static str,
+            scripts: Vec<&'static str>,
+            // None means `yarn --version` has not been run yet.
+            version: Option<YarnVersion>,
+            expected: bool,
+        }
+        let cases = vec![
+            Case {
+                title: "no lifecycle hooks",
+                scripts: vec!["build", "prepare", "postinstall"],
+                version: None,
+                expected: false,
+            },
+            Case {
+                title: "lifecycle hooks with yarn v1",
+                scripts: vec!["build", "prebuild"],
+                version: Some(YarnVersion::V1),
+                expected: true,
+            },
+            Case {
+                title: "lifecycle hooks with yarn v2 or later",
+                scripts: vec!["build", "postbuild"],
+                version: Some(YarnVersion::V2OrLater),
+                expected: false,
+            },
+        ];
+
+        for case in cases {
+            let scripts: Vec<(String, String, u32)> =
+                case.scripts.iter().map(|s| (s.to_string(), String::new(), 1)).collect();
+            let yarn_version = LazyYarnVersion::new(PathBuf::new());
+            if let Some(v) = case.version {
+                let _ = yarn_version.version.set(Some(v));
+            }
+            assert_eq!(case.expected, yarn_version.run_hooks(&scripts), "\nFailed: 🚨{:?}🚨\n", case.title,);
+            // yarn is not run when there are no lifecycle hooks.
+            assert_eq!(
+                case.version.is_some(),
+                yarn_version.version.get().is_some(),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+}
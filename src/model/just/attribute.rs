@@ -0,0 +1,303 @@
+use std::path::PathBuf;
+
+const ATTRIBUTE_PRIVATE: &str = "private";
+const ATTRIBUTE_CONFIRM: &str = "confirm";
+const ATTRIBUTE_NO_CD: &str = "no-cd";
+const ATTRIBUTE_WORKING_DIRECTORY: &str = "working-directory";
+
+const SETTING_DOTENV_LOAD: &str = "dotenv-load";
+const SETTING_SHELL: &str = "shell";
+const SETTING_WORKING_DIRECTORY: &str = "working-directory";
+
+/// RecipeAttributes represents the attributes of a recipe which affect how and where the recipe is executed.
+/// ref: https://just.systems/man/en/attributes.html
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RecipeAttributes {
+    pub private: bool,
+    /// The message of `[confirm]` or `[confirm("message")]`.
+    pub confirm: Option<String>,
+    pub no_cd: bool,
+    pub working_directory: Option<PathBuf>,
+}
+
+impl RecipeAttributes {
+    /// new collects the attributes of the recipe node.
+    /// Multiple attributes can be declared in one bracket like `[group('misc'), private]`, so an identifier always
+    /// starts a new attribute and the following `argument: string` belongs to it.
+    pub fn new(recipe: tree_sitter::Node, source_code: &str, recipe_name: &str) -> RecipeAttributes {
+        // (name, argument)
+        let mut attributes: Vec<(String, Option<String>)> = vec![];
+        for attribute in recipe.named_children(&mut recipe.walk()) {
+            if attribute.kind() != "attribute" {
+                continue;
+            }
+            for child in attribute.named_children(&mut attribute.walk()) {
+                match child.kind() {
+                    "identifier" => attributes.push((source_code[child.byte_range()].to_string(), None)),
+                    "string" => {
+                        if let Some((_, argument)) = attributes.last_mut()
+                            && argument.is_none()
+                        {
+                            *argument = Some(unquote(&source_code[child.byte_range()]));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut result = RecipeAttributes::default();
+        for (name, argument) in attributes {
+            match name.as_str() {
+                ATTRIBUTE_PRIVATE => result.private = true,
+                // The default message is the same as the one just shows.
+                ATTRIBUTE_CONFIRM => {
+                    result.confirm = Some(argument.unwrap_or_else(|| format!("Run recipe `{}`?", recipe_name)))
+                }
+                ATTRIBUTE_NO_CD => result.no_cd = true,
+                ATTRIBUTE_WORKING_DIRECTORY => result.working_directory = argument.map(PathBuf::from),
+                _ => {}
+            }
+        }
+        result
+    }
+}
+
+/// Settings represents the `set` directives in a justfile which affect how recipes are executed.
+/// ref: https://just.systems/man/en/settings.html
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Settings {
+    // None if it is not set. It is distinguished from `set dotenv-load := false` not to be overridden by
+    // the imported justfiles.
+    pub dotenv_load: Option<bool>,
+    pub shell: Option<Vec<String>>,
+    pub working_directory: Option<PathBuf>,
+}
+
+impl Settings {
+    /// apply updates the settings with the `setting` node.
+    /// Its format is like `(setting left: (identifier) right: (string))`.
+    /// `set shell := [...]` does not have `left` because `shell` is a keyword of the grammar.
+    pub fn apply(&mut self, setting: tree_sitter::Node, source_code: &str) {
+        let name = match setting.child_by_field_name("left") {
+            Some(left) => source_code[left.byte_range()].to_string(),
+            None => {
+                if setting.children(&mut setting.walk()).any(|c| c.kind() == SETTING_SHELL) {
+                    SETTING_SHELL.to_string()
+                } else {
+                    return;
+                }
+            }
+        };
+
+        let mut strings = vec![];
+        let mut boolean = None;
+        for child in setting.named_children(&mut setting.walk()) {
+            match child.kind() {
+                "string" => strings.push(unquote(&source_code[child.byte_range()])),
+                "boolean" => boolean = Some(&source_code[child.byte_range()] == "true"),
+                _ => {}
+            }
+        }
+
+        match name.as_str() {
+            // `set dotenv-load` is the same as `set dotenv-load := true`.
+            SETTING_DOTENV_LOAD => self.dotenv_load = Some(boolean.unwrap_or(true)),
+            SETTING_SHELL => self.shell = Some(strings),
+            SETTING_WORKING_DIRECTORY => self.working_directory = strings.first().map(PathBuf::from),
+            _ => {}
+        }
+    }
+
+    /// merge fills the settings which are not set in `self` with the ones in `other`.
+    /// This is used for the settings in the files imported by `import` directive.
+    pub fn merge(self, other: Settings) -> Settings {
+        Settings {
+            dotenv_load: self.dotenv_load.or(other.dotenv_load),
+            shell: self.shell.or(other.shell),
+            working_directory: self.working_directory.or(other.working_directory),
+        }
+    }
+}
+
+/// unquote removes surrounding quotes of just's string literal. e.g. `'foo'`, `"foo"`, `'''foo'''`
+pub fn unquote(s: &str) -> String {
+    s.trim_matches(|c| c == '\'' || c == '"').to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tree_sitter::Parser;
+
+    fn parse(source_code: &str) -> tree_sitter::Tree {
+        let mut parser = Parser::new();
+        parser.set_language(&tree_sitter_just::language()).unwrap();
+        parser.parse(source_code, None).unwrap()
+    }
+
+    #[test]
+    fn recipe_attributes_new_test() {
+        struct Case {
+            title: &'static str,
+            source_code: &'static str,
+            expect: RecipeAttributes,
+        }
+        let cases = vec![
+            Case {
+                title: "no attributes",
+                source_code: "build:\n  echo private\n",
+                expect: RecipeAttributes::default(),
+            },
+            Case {
+                title: "multiple attributes in one bracket",
+                source_code: "[group('private'), private]\nbuild:\n  echo build\n",
+                expect: RecipeAttributes {
+                    private: true,
+                    ..RecipeAttributes::default()
+                },
+            },
+            Case {
+                title: "confirm without message",
+                source_code: "[confirm]\ndeploy:\n  echo deploy\n",
+                expect: RecipeAttributes {
+                    confirm: Some("Run recipe `deploy`?".to_string()),
+                    ..RecipeAttributes::default()
+                },
+            },
+            Case {
+                title: "confirm with message, no-cd and working-directory",
+                source_code: r#"[confirm("Are you sure?")]
+[no-cd]
+[working-directory: 'sub dir']
+deploy:
+  echo deploy
+"#,
+                expect: RecipeAttributes {
+                    private: false,
+                    confirm: Some("Are you sure?".to_string()),
+                    no_cd: true,
+                    working_directory: Some(PathBuf::from("sub dir")),
+                },
+            },
+        ];
+
+        for case in cases {
+            let tree = parse(case.source_code);
+            let recipe = tree
+                .root_node()
+                .named_children(&mut tree.walk())
+                .find(|n| n.kind() == "recipe")
+                .unwrap();
+            let name = recipe
+                .named_children(&mut recipe.walk())
+                .find(|n| n.kind() == "recipe_header")
+                .and_then(|h| h.child_by_field_name("name"))
+                .map(|n| case.source_code[n.byte_range()].to_string())
+                .unwrap();
+            assert_eq!(
+                case.expect,
+                RecipeAttributes::new(recipe, case.source_code, &name),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn settings_apply_test() {
+        struct Case {
+            title: &'static str,
+            source_code: &'static str,
+            expect: Settings,
+        }
+        let cases = vec![
+            Case {
+                title: "no settings",
+                source_code: "build:\n  echo build\n",
+                expect: Settings::default(),
+            },
+            Case {
+                title: "all supported settings",
+                source_code: r#"set dotenv-load
+set shell := ["bash", "-cu"]
+set working-directory := 'sub'
+set positional-arguments
+"#,
+                expect: Settings {
+                    dotenv_load: Some(true),
+                    shell: Some(vec!["bash".to_string(), "-cu".to_string()]),
+                    working_directory: Some(PathBuf::from("sub")),
+                },
+            },
+            Case {
+                title: "dotenv-load is disabled explicitly",
+                source_code: "set dotenv-load := false\n",
+                expect: Settings {
+                    dotenv_load: Some(false),
+                    ..Settings::default()
+                },
+            },
+        ];
+
+        for case in cases {
+            let tree = parse(case.source_code);
+            let mut settings = Settings::default();
+            for node in tree.root_node().named_children(&mut tree.walk()) {
+                if node.kind() == "setting" {
+                    settings.apply(node, case.source_code);
+                }
+            }
+            assert_eq!(case.expect, settings, "\nFailed: 🚨{:?}🚨\n", case.title);
+        }
+    }
+
+    #[test]
+    fn settings_merge_test() {
+        struct Case {
+            title: &'static str,
+            settings: Settings,
+            imported: Settings,
+            expect: Settings,
+        }
+        let cases = vec![
+            Case {
+                title: "the imported settings are used if they are not set",
+                settings: Settings::default(),
+                imported: Settings {
+                    dotenv_load: Some(true),
+                    shell: Some(vec!["bash".to_string()]),
+                    working_directory: Some(PathBuf::from("sub")),
+                },
+                expect: Settings {
+                    dotenv_load: Some(true),
+                    shell: Some(vec!["bash".to_string()]),
+                    working_directory: Some(PathBuf::from("sub")),
+                },
+            },
+            Case {
+                title: "the settings set explicitly are not overridden",
+                settings: Settings {
+                    dotenv_load: Some(false),
+                    shell: Some(vec!["zsh".to_string()]),
+                    working_directory: None,
+                },
+                imported: Settings {
+                    dotenv_load: Some(true),
+                    shell: Some(vec!["bash".to_string()]),
+                    working_directory: None,
+                },
+                expect: Settings {
+                    dotenv_load: Some(false),
+                    shell: Some(vec!["zsh".to_string()]),
+                    working_directory: None,
+                },
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(case.expect, case.settings.merge(case.imported), "\nFailed: 🚨{:?}🚨\n", case.title);
+        }
+    }
+}
@@ -1,12 +1,14 @@
+use super::attribute::{RecipeAttributes, Settings, unquote};
 use crate::model::{
     command::{self, CommandWithPreview},
     file_util,
     runner_type::RunnerType,
 };
-use anyhow::{Result, anyhow, bail};
+use anyhow::{Result, anyhow};
 use std::{
+    collections::HashMap,
     fs::{self},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process,
 };
 use tree_sitter::Parser;
@@ -15,6 +17,8 @@ const JUSTFILE_EXTENSION: &str = "just";
 const JUSTFILE_NAME_MOD_JUST: &str = "mod.just";
 const JUSTFILE_NAME_JUSTFILE: &str = "justfile";
 const JUSTFILE_NAME_DOT_JUSTFILE: &str = ".justfile";
+// Flag to skip the confirmation of `[confirm]` recipes. It is passed after the user confirmed in the TUI.
+const FLAG_YES: &str = "--yes";
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Just {
@@ -24,6 +28,10 @@ pub struct Just {
     // ref: https://github.com/casey/just#modules1190
     modules: Vec<Module>,
     commands: Vec<command::CommandWithPreview>,
+    settings: Settings,
+    // Attributes of the recipes keyed by recipe name.
+    // Recipes which don't have any attributes affecting the execution are not included.
+    attributes: HashMap<String, RecipeAttributes>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -39,16 +47,16 @@ struct PossiblePaths {
 }
 
 impl Just {
-    pub fn new(current_dir: PathBuf) -> Result<Just> {
-        let justfile_path = match Just::find_justfile(current_dir.clone()) {
-            Some(path) => path,
-            None => bail!("justfile not found"),
+    // find returns None if there is no justfile in `current_dir` and its ancestors.
+    pub fn find(current_dir: PathBuf) -> Result<Option<Just>> {
+        let Some(justfile_path) = Just::find_justfile(current_dir.clone()) else {
+            return Ok(None);
         };
         let source_code = fs::read_to_string(&justfile_path)?;
 
         match Just::parse_justfile(current_dir, justfile_path.clone(), source_code) {
-            Some(c) => Ok(c),
-            None => Err(anyhow!("failed to parse justfile")),
+            Some(c) => Ok(Some(c)),
+            None => Err(anyhow!("failed to parse {}", justfile_path.display())),
         }
     }
 
@@ -76,36 +84,131 @@ impl Just {
         self.path.clone()
     }
 
-    pub fn command_to_run(&self, command: &command::CommandForExec) -> Result<String, anyhow::Error> {
-        Ok(format!("just {}", command.args))
+    // source_files returns the justfile and the files of the modules declared by `mod` directive.
+    // The files imported by `import` directive are found from the commands defined in them.
+    pub fn source_files(&self) -> Vec<PathBuf> {
+        let mut result = vec![self.path.clone()];
+        for module in &self.modules {
+            result.extend(module.content.source_files());
+        }
+        result
     }
 
-    pub fn execute(&self, command: &command::CommandForExec) -> Result<(), anyhow::Error> {
-        let child = process::Command::new("just")
-            .stdin(process::Stdio::inherit())
-            .args(command.args.split_whitespace())
-            .spawn();
+    pub fn command_to_run(
+        &self,
+        command: &command::CommandForExec,
+        current_dir: &Path,
+    ) -> Result<String, anyhow::Error> {
+        let details = self.execution_details(command, current_dir);
+        if details.is_empty() {
+            Ok(format!("just {}", command.args_to_string()))
+        } else {
+            Ok(format!("just {} ({})", command.args_to_string(), details.join(", ")))
+        }
+    }
 
-        match child {
-            Ok(mut child) => match child.wait() {
-                Ok(_) => Ok(()),
-                Err(e) => Err(anyhow!("failed to run: {}", e)),
-            },
-            Err(e) => Err(anyhow!("failed to spawn: {}", e)),
+    // confirmation_message returns the message of `[confirm]` attribute if the recipe has it.
+    // just shows its own prompt only after the TUI is closed, so fzf-make asks for confirmation in the TUI instead.
+    pub fn confirmation_message(&self, command: &command::CommandForExec) -> Option<String> {
+        self.find_recipe(command).and_then(|(_, attributes)| attributes.confirm)
+    }
+
+    // confirmed returns the command which skips the confirmation by just because the user has already confirmed it.
+    pub fn confirmed(&self, command: &command::CommandForExec) -> command::CommandForExec {
+        command::CommandForExec {
+            runner_type: command.runner_type.clone(),
+            args: [vec![FLAG_YES.to_string()], command.args.clone()].concat(),
         }
     }
 
+    // execution_details describes where and how the recipe will be executed.
+    // Only the things which differ from the default behavior of just are described.
+    // `current_dir` is the directory where just is launched, which is used by `[no-cd]` recipes.
+    pub fn execution_details(&self, command: &command::CommandForExec, current_dir: &Path) -> Vec<String> {
+        let Some((justfile, attributes)) = self.find_recipe(command) else {
+            return vec![];
+        };
+
+        let mut result = vec![];
+        // Recipes are executed in the directory of the justfile by default.
+        let justfile_dir = justfile.path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let working_directory = if attributes.no_cd {
+            Some(current_dir.to_path_buf())
+        } else {
+            attributes
+                .working_directory
+                .or(justfile.settings.working_directory.clone())
+                .map(|dir| justfile_dir.join(dir))
+        };
+        if let Some(dir) = working_directory {
+            result.push(format!("working directory: {}", dir.display()));
+        }
+        if let Some(shell) = &justfile.settings.shell {
+            result.push(format!("shell: {}", shell.join(" ")));
+        }
+        if justfile.settings.dotenv_load == Some(true) {
+            result.push("dotenv-load: enabled".to_string());
+        }
+        result
+    }
+
+    // find_recipe returns the justfile which defines the recipe and the attributes of the recipe.
+    // The recipe in modules is specified like `backend::start`.
+    fn find_recipe(&self, command: &command::CommandForExec) -> Option<(&Just, RecipeAttributes)> {
+        let recipe_path = command.args.iter().find(|arg| !arg.starts_with('-'))?;
+        self.find_recipe_by_path(recipe_path)
+    }
+
+    fn find_recipe_by_path(&self, recipe_path: &str) -> Option<(&Just, RecipeAttributes)> {
+        match recipe_path.split_once("::") {
+            Some((mod_name, rest)) => self
+                .modules
+                .iter()
+                .find(|m| m.mod_name == mod_name)
+                .and_then(|m| m.content.find_recipe_by_path(rest)),
+            None => self
+                .commands
+                .iter()
+                .any(|c| c.args == recipe_path)
+                .then(|| (self, self.attributes.get(recipe_path).cloned().unwrap_or_default())),
+        }
+    }
+
+    // process_command builds the process to run the command. It is spawned or exec-ed by Runner.
+    pub fn process_command(&self, command: &command::CommandForExec) -> process::Command {
+        let mut process_command = process::Command::new("just");
+        process_command.stdin(process::Stdio::inherit()).args(&command.args);
+        process_command
+    }
+
     fn find_justfile(current_dir: PathBuf) -> Option<PathBuf> {
         file_util::find_file_in_ancestors(current_dir, vec!["justfile", ".justfile"])
     }
 
     fn parse_justfile(current_dir: PathBuf, justfile_path: PathBuf, source_code: String) -> Option<Just> {
+        let mut visited = vec![fs::canonicalize(&justfile_path).unwrap_or(justfile_path.clone())];
+        let just = Just::parse_justfile_internal(current_dir, justfile_path, source_code, &mut visited);
+
+        if just.commands.is_empty() { None } else { Some(just) }
+    }
+
+    // parse_justfile_internal parses the justfile and the files imported by `import` directive.
+    // `visited` holds the canonicalized paths of the justfiles which are already parsed to avoid infinite loop
+    // caused by circular imports.
+    fn parse_justfile_internal(
+        current_dir: PathBuf,
+        justfile_path: PathBuf,
+        source_code: String,
+        visited: &mut Vec<PathBuf>,
+    ) -> Just {
         let mut parser = Parser::new();
         parser.set_language(&tree_sitter_just::language()).unwrap();
         let tree = parser.parse(&source_code, None).unwrap();
         // source_file
         // ├── shebang
         // │   └── language
+        // ├── import
+        // │   └── string
         // ├── module
         //     └── name: identifier
         //     └── string
@@ -120,92 +223,144 @@ impl Just {
         //         └── argument: string
         let mut commands = vec![];
         let mut modules = vec![];
+        let mut settings = Settings::default();
+        let mut attributes = HashMap::new();
+        let mut imported_settings = vec![];
 
         // At first, it seemed that it is more readable if we can use `Node#children_by_field_name` instead of `Node#children`.
         // But the elements wanted to be extracted here do not have names.
         // So we had no choice but to use `Node#children`.
-        'recipe: for recipes_and_its_siblings in tree.root_node().named_children(&mut tree.walk()) {
-            if recipes_and_its_siblings.kind() == "module" {
-                // Parse `mod` directive.
-                // Its format is like `(module name: (identifier) (string))`.
-                let mut mod_name = String::new();
-                let mut mod_path: Option<PathBuf> = None;
-                if mod_name.is_empty() {
+        for recipes_and_its_siblings in tree.root_node().named_children(&mut tree.walk()) {
+            match recipes_and_its_siblings.kind() {
+                "module" => {
+                    // Parse `mod` directive.
+                    // Its format is like `(module name: (identifier) (string))`.
+                    let mut mod_name = String::new();
+                    let mut mod_path: Option<PathBuf> = None;
                     for recipe_child in recipes_and_its_siblings.named_children(&mut tree.walk()) {
                         match recipe_child.kind() {
                             "identifier" => {
                                 mod_name = source_code[recipe_child.byte_range()].to_string();
                             }
                             "string" => {
-                                let raw_path = &source_code[recipe_child.byte_range()];
-                                // Remove surrounding quotes
-                                mod_path =
-                                    Some(PathBuf::from(raw_path.trim_matches(|c| c == '\'' || c == '"').to_string()));
+                                mod_path = Some(PathBuf::from(unquote(&source_code[recipe_child.byte_range()])));
                             }
                             _ => {}
                         }
                     }
-                }
 
-                // Retrieve the justfiles for the modules recursively.
-                if let Some(path) = Just::get_mod_file_path(current_dir.clone(), mod_name.clone(), mod_path) {
-                    // Ensure the path is absolute
-                    let absolute_path = fs::canonicalize(&path).unwrap_or(path);
-                    if let Ok(mod_source_code) = fs::read_to_string(&absolute_path)
-                        && let Some(parsed) = Just::parse_justfile(current_dir.clone(), absolute_path, mod_source_code)
-                    {
-                        modules.push(Module {
-                            mod_name: mod_name.clone(),
-                            content: parsed,
-                        });
+                    // Retrieve the justfiles for the modules recursively.
+                    if let Some(path) = Just::get_mod_file_path(current_dir.clone(), mod_name.clone(), mod_path) {
+                        // Ensure the path is absolute
+                        let absolute_path = fs::canonicalize(&path).unwrap_or(path);
+                        if let Ok(mod_source_code) = fs::read_to_string(&absolute_path)
+                            && let Some(parsed) =
+                                Just::parse_justfile(current_dir.clone(), absolute_path, mod_source_code)
+                        {
+                            modules.push(Module {
+                                mod_name: mod_name.clone(),
+                                content: parsed,
+                            });
+                        }
                     }
                 }
-            }
-
-            // Retrieve recipe names.
-            if recipes_and_its_siblings.kind() == "recipe" {
-                let mut should_skip = false;
-                recipes_and_its_siblings.children(&mut tree.walk()).for_each(|attr| {
-                    let attr_name = &source_code[attr.byte_range()];
-                    if attr_name.contains("private") {
-                        should_skip = true;
-                    }
-                });
-                if should_skip {
-                    continue;
+                "import" => {
+                    // Parse `import` directive.
+                    // Its format is like `(import (string))`. `import?` has an anonymous `?` node in addition.
+                    // Recipes in the imported file belong to the same namespace as the importing file,
+                    // so they are merged into the importing justfile instead of being treated as a module.
+                    let Some(import_path) = recipes_and_its_siblings
+                        .named_children(&mut tree.walk())
+                        .find(|n| n.kind() == "string")
+                        .map(|n| PathBuf::from(unquote(&source_code[n.byte_range()])))
+                    else {
+                        continue;
+                    };
+                    // A missing file is just skipped regardless of whether the import is optional(`import?`) or not,
+                    // because just itself will report the error when the recipe is executed.
+                    let Some(imported) =
+                        Just::parse_imported_justfile(&current_dir, &justfile_path, import_path, visited)
+                    else {
+                        continue;
+                    };
+                    commands.extend(imported.commands);
+                    modules.extend(imported.modules);
+                    attributes.extend(imported.attributes);
+                    imported_settings.push(imported.settings);
                 }
-                for recipe_child in recipes_and_its_siblings.named_children(&mut tree.walk()) {
-                    if recipe_child.kind() == "recipe_header" {
-                        // `recipe_name` has format like: `fmt:`
-                        let recipe_name = &source_code[recipe_child.byte_range()];
-                        let trimmed = recipe_name.split(':').collect::<Vec<&str>>();
-                        if let Some(r) = trimmed.first() {
-                            // If recipe includes an argument, it will be like `run arg:`.
-                            // So we need to split it by space and take the first element.
-                            let command_name = r.split_whitespace().next().unwrap_or("").to_string();
-
-                            commands.push(CommandWithPreview::new(
-                                RunnerType::Just,
-                                command_name,
-                                justfile_path.clone(),
-                                recipe_child.start_position().row as u32 + 1,
-                            ))
-                        };
-                        continue 'recipe;
+                "setting" => settings.apply(recipes_and_its_siblings, &source_code),
+                "recipe" => {
+                    let Some(recipe_header) = recipes_and_its_siblings
+                        .named_children(&mut tree.walk())
+                        .find(|n| n.kind() == "recipe_header")
+                    else {
+                        continue;
                     };
+                    let Some(recipe_name) = recipe_header
+                        .child_by_field_name("name")
+                        .map(|n| source_code[n.byte_range()].to_string())
+                    else {
+                        continue;
+                    };
+                    // Recipes whose name starts with `_` are private as well as ones with `[private]` attribute.
+                    // ref: https://just.systems/man/en/private-recipes.html
+                    let recipe_attributes = RecipeAttributes::new(recipes_and_its_siblings, &source_code, &recipe_name);
+                    if recipe_attributes.private || recipe_name.starts_with('_') {
+                        continue;
+                    }
+                    if recipe_attributes != RecipeAttributes::default() {
+                        attributes.insert(recipe_name.clone(), recipe_attributes);
+                    }
+
+                    commands.push(CommandWithPreview::new(
+                        RunnerType::Just,
+                        recipe_name,
+                        justfile_path.clone(),
+                        recipe_header.start_position().row as u32 + 1,
+                    ));
                 }
+                _ => {}
             }
         }
 
-        if commands.is_empty() {
-            None
+        // Settings in the importing file take precedence over the ones in the imported files.
+        for imported in imported_settings {
+            settings = settings.merge(imported);
+        }
+
+        Just {
+            path: justfile_path,
+            modules,
+            commands,
+            settings,
+            attributes,
+        }
+    }
+
+    // parse_imported_justfile resolves `import_path` relative to the directory of the importing justfile and parses it.
+    // It returns None if the file does not exist or it has been already parsed(e.g. circular import).
+    fn parse_imported_justfile(
+        current_dir: &Path,
+        importing_justfile_path: &Path,
+        import_path: PathBuf,
+        visited: &mut Vec<PathBuf>,
+    ) -> Option<Just> {
+        let path = if import_path.is_absolute() {
+            import_path
         } else {
-            Some(Just {
-                path: justfile_path,
-                modules,
-                commands,
-            })
+            importing_justfile_path
+                .parent()
+                .map(|dir| dir.join(&import_path))
+                .unwrap_or(import_path)
+        };
+        let absolute_path = fs::canonicalize(&path).ok()?;
+        if visited.contains(&absolute_path) {
+            return None;
         }
+        visited.push(absolute_path.clone());
+
+        let source_code = fs::read_to_string(&absolute_path).ok()?;
+        Some(Just::parse_justfile_internal(current_dir.to_path_buf(), absolute_path, source_code, visited))
     }
 
     fn calc_possible_justfile_path_from_mod_info(
@@ -318,6 +473,7 @@ impl Just {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::model::command::CommandMetadata;
     use pretty_assertions::assert_eq;
     use uuid::Uuid;
 
@@ -417,6 +573,8 @@ clippy:
         "#,
                 expected: Some(Just {
                     path: PathBuf::from("justfile"),
+                    settings: Settings::default(),
+                    attributes: HashMap::new(),
                     modules: vec![],
                     commands: vec![
                         CommandWithPreview {
@@ -424,30 +582,35 @@ clippy:
                             args: "test".to_string(),
                             file_path: PathBuf::from("justfile"),
                             line_number: 4,
+                            metadata: CommandMetadata::default(),
                         },
                         CommandWithPreview {
                             runner_type: RunnerType::Just,
                             args: "run".to_string(),
                             file_path: PathBuf::from("justfile"),
                             line_number: 8,
+                            metadata: CommandMetadata::default(),
                         },
                         CommandWithPreview {
                             runner_type: RunnerType::Just,
                             args: "build".to_string(),
                             file_path: PathBuf::from("justfile"),
                             line_number: 12,
+                            metadata: CommandMetadata::default(),
                         },
                         CommandWithPreview {
                             runner_type: RunnerType::Just,
                             args: "fmt".to_string(),
                             file_path: PathBuf::from("justfile"),
                             line_number: 16,
+                            metadata: CommandMetadata::default(),
                         },
                         CommandWithPreview {
                             runner_type: RunnerType::Just,
                             args: "clippy".to_string(),
                             file_path: PathBuf::from("justfile"),
                             line_number: 26,
+                            metadata: CommandMetadata::default(),
                         },
                     ],
                 }),
@@ -466,6 +629,8 @@ build:
         "#,
                 expected: Some(Just {
                     path: PathBuf::from("justfile"),
+                    settings: Settings::default(),
+                    attributes: HashMap::new(),
                     modules: vec![],
                     commands: vec![
                         CommandWithPreview {
@@ -473,16 +638,70 @@ build:
                             args: "run".to_string(),
                             file_path: PathBuf::from("justfile"),
                             line_number: 4,
+                            metadata: CommandMetadata::default(),
                         },
                         CommandWithPreview {
                             runner_type: RunnerType::Just,
                             args: "build".to_string(),
                             file_path: PathBuf::from("justfile"),
                             line_number: 8,
+                            metadata: CommandMetadata::default(),
+                        },
+                    ],
+                }),
+            },
+            Case {
+                name: "justfile with private recipes and a recipe mentioning private in its body",
+                source_code: r#"
+[private]
+hidden:
+  echo hidden
+
+[group('misc'), private]
+hidden-grouped:
+  echo hidden
+
+_helper:
+  echo helper
+
+build:
+  echo "build private packages"
+
+[group('private')]
+grouped:
+  echo grouped
+        "#,
+                expected: Some(Just {
+                    path: PathBuf::from("justfile"),
+                    settings: Settings::default(),
+                    attributes: HashMap::new(),
+                    modules: vec![],
+                    commands: vec![
+                        CommandWithPreview {
+                            runner_type: RunnerType::Just,
+                            args: "build".to_string(),
+                            file_path: PathBuf::from("justfile"),
+                            line_number: 13,
+                            metadata: CommandMetadata::default(),
+                        },
+                        CommandWithPreview {
+                            runner_type: RunnerType::Just,
+                            args: "grouped".to_string(),
+                            file_path: PathBuf::from("justfile"),
+                            line_number: 17,
+                            metadata: CommandMetadata::default(),
                         },
                     ],
                 }),
             },
+            Case {
+                name: "justfile with only private recipes",
+                source_code: r#"
+_helper:
+  echo helper
+        "#,
+                expected: None,
+            },
         ];
 
         for case in cases {
@@ -540,6 +759,8 @@ clippy:
         "#,
                 expected: Some(Just {
                     path: PathBuf::from("justfile"),
+                    settings: Settings::default(),
+                    attributes: HashMap::new(),
                     modules: vec![],
                     commands: vec![
                         CommandWithPreview {
@@ -547,30 +768,35 @@ clippy:
                             args: "test".to_string(),
                             file_path: PathBuf::from("justfile"),
                             line_number: 4,
+                            metadata: CommandMetadata::default(),
                         },
                         CommandWithPreview {
                             runner_type: RunnerType::Just,
                             args: "run".to_string(),
                             file_path: PathBuf::from("justfile"),
                             line_number: 8,
+                            metadata: CommandMetadata::default(),
                         },
                         CommandWithPreview {
                             runner_type: RunnerType::Just,
                             args: "build".to_string(),
                             file_path: PathBuf::from("justfile"),
                             line_number: 12,
+                            metadata: CommandMetadata::default(),
                         },
                         CommandWithPreview {
                             runner_type: RunnerType::Just,
                             args: "fmt".to_string(),
                             file_path: PathBuf::from("justfile"),
                             line_number: 16,
+                            metadata: CommandMetadata::default(),
                         },
                         CommandWithPreview {
                             runner_type: RunnerType::Just,
                             args: "clippy".to_string(),
                             file_path: PathBuf::from("justfile"),
                             line_number: 26,
+                            metadata: CommandMetadata::default(),
                         },
                     ],
                 }),
@@ -589,6 +815,8 @@ build:
         "#,
                 expected: Some(Just {
                     path: PathBuf::from("justfile"),
+                    settings: Settings::default(),
+                    attributes: HashMap::new(),
                     modules: vec![],
                     commands: vec![
                         CommandWithPreview {
@@ -596,12 +824,14 @@ build:
                             args: "run".to_string(),
                             file_path: PathBuf::from("justfile"),
                             line_number: 4,
+                            metadata: CommandMetadata::default(),
                         },
                         CommandWithPreview {
                             runner_type: RunnerType::Just,
                             args: "build".to_string(),
                             file_path: PathBuf::from("justfile"),
                             line_number: 8,
+                            metadata: CommandMetadata::default(),
                         },
                     ],
                 }),
@@ -618,6 +848,113 @@ build:
         }
     }
 
+    #[test]
+    fn test_parse_justfile_with_imports() {
+        // See test_data/just/import for the layout.
+        // - common.just imports nested/deep.just, which imports ../cycle.just (resolved relative to nested/deep.just).
+        // - cycle.just imports the root justfile again, which must not cause an infinite loop.
+        // - `import?` to a missing file is ignored.
+        let just = Just::find(PathBuf::from("test_data/just/import")).unwrap().unwrap();
+        let import_dir = fs::canonicalize("test_data/just/import").unwrap();
+
+        let result: Vec<(String, PathBuf)> = just
+            .to_commands()
+            .into_iter()
+            .map(|c| (c.args, fs::canonicalize(c.file_path).unwrap()))
+            .collect();
+        assert_eq!(
+            result,
+            vec![
+                ("cycle".to_string(), import_dir.join("cycle.just")),
+                ("deep".to_string(), import_dir.join("nested/deep.just")),
+                ("lint".to_string(), import_dir.join("common.just")),
+                ("build".to_string(), import_dir.join("justfile")),
+                ("test".to_string(), import_dir.join("justfile")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recipe_attributes_and_settings() {
+        // See test_data/just/attributes for the justfile.
+        let just = Just::find(PathBuf::from("test_data/just/attributes")).unwrap().unwrap();
+        let justfile_dir = just.path().parent().unwrap().to_path_buf();
+        let command = |args: &str| command::CommandForExec {
+            runner_type: RunnerType::Just,
+            args: args.split_whitespace().map(|a| a.to_string()).collect(),
+        };
+
+        struct Case {
+            title: &'static str,
+            args: &'static str,
+            expect_confirmation_message: Option<String>,
+            expect_execution_details: Vec<String>,
+        }
+        let shell_and_dotenv = vec!["shell: bash -cu".to_string(), "dotenv-load: enabled".to_string()];
+        let cases = vec![
+            Case {
+                title: "confirm with message",
+                args: "deploy",
+                expect_confirmation_message: Some("Deploy to production?".to_string()),
+                expect_execution_details: shell_and_dotenv.clone(),
+            },
+            Case {
+                title: "confirm without message and working-directory",
+                args: "clean --force",
+                expect_confirmation_message: Some("Run recipe `clean`?".to_string()),
+                expect_execution_details: [
+                    vec![format!("working directory: {}", justfile_dir.join("sub").display())],
+                    shell_and_dotenv.clone(),
+                ]
+                .concat(),
+            },
+            Case {
+                title: "no-cd",
+                args: "fmt",
+                expect_confirmation_message: None,
+                expect_execution_details: [
+                    vec!["working directory: /tmp/launched".to_string()],
+                    shell_and_dotenv.clone(),
+                ]
+                .concat(),
+            },
+            Case {
+                title: "no attributes",
+                args: "test",
+                expect_confirmation_message: None,
+                expect_execution_details: shell_and_dotenv.clone(),
+            },
+            Case {
+                title: "recipe which does not exist",
+                args: "unknown",
+                expect_confirmation_message: None,
+                expect_execution_details: vec![],
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(
+                case.expect_confirmation_message,
+                just.confirmation_message(&command(case.args)),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+            assert_eq!(
+                case.expect_execution_details,
+                just.execution_details(&command(case.args), Path::new("/tmp/launched")),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+
+        assert_eq!(command("--yes deploy"), just.confirmed(&command("deploy")));
+        assert_eq!(
+            "just test (shell: bash -cu, dotenv-load: enabled)",
+            just.command_to_run(&command("test"), Path::new("/tmp/launched"))
+                .unwrap()
+        );
+    }
+
     #[test]
     fn test_calc_justfile_path_from_mod_info() {
         struct Case {
@@ -885,6 +1222,8 @@ build:
         {
             let just = Just {
                 path: PathBuf::from("justfile"),
+                settings: Settings::default(),
+                attributes: HashMap::new(),
                 modules: vec![],
                 commands: vec![
                     CommandWithPreview::new(RunnerType::Just, "test".to_string(), PathBuf::from("justfile"), 1),
@@ -902,10 +1241,14 @@ build:
         {
             let just = Just {
                 path: PathBuf::from("justfile"),
+                settings: Settings::default(),
+                attributes: HashMap::new(),
                 modules: vec![Module {
                     mod_name: "backend".to_string(),
                     content: Just {
                         path: PathBuf::from("backend.just"),
+                        settings: Settings::default(),
+                        attributes: HashMap::new(),
                         modules: vec![],
                         commands: vec![
                             CommandWithPreview::new(
@@ -945,11 +1288,15 @@ build:
         {
             let just = Just {
                 path: PathBuf::from("justfile"),
+                settings: Settings::default(),
+                attributes: HashMap::new(),
                 modules: vec![
                     Module {
                         mod_name: "backend".to_string(),
                         content: Just {
                             path: PathBuf::from("backend.just"),
+                            settings: Settings::default(),
+                            attributes: HashMap::new(),
                             modules: vec![],
                             commands: vec![CommandWithPreview::new(
                                 RunnerType::Just,
@@ -963,6 +1310,8 @@ build:
                         mod_name: "frontend".to_string(),
                         content: Just {
                             path: PathBuf::from("frontend.just"),
+                            settings: Settings::default(),
+                            attributes: HashMap::new(),
                             modules: vec![],
                             commands: vec![CommandWithPreview::new(
                                 RunnerType::Just,
@@ -992,14 +1341,20 @@ build:
         {
             let just = Just {
                 path: PathBuf::from("justfile"),
+                settings: Settings::default(),
+                attributes: HashMap::new(),
                 modules: vec![Module {
                     mod_name: "backend".to_string(),
                     content: Just {
                         path: PathBuf::from("backend.just"),
+                        settings: Settings::default(),
+                        attributes: HashMap::new(),
                         modules: vec![Module {
                             mod_name: "api".to_string(),
                             content: Just {
                                 path: PathBuf::from("backend/api.just"),
+                                settings: Settings::default(),
+                                attributes: HashMap::new(),
                                 modules: vec![],
                                 commands: vec![CommandWithPreview::new(
                                     RunnerType::Just,
@@ -1 +1,2 @@
+mod attribute;
 pub(crate) mod just_main;
@@ -1,6 +1,6 @@
 use super::target::*;
 use crate::model::{command, file_util};
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use regex::Regex;
 use std::{
     fs,
@@ -21,14 +21,17 @@ impl Make {
     /// command as an argument. However, if it is an associated function, it can be called
     /// from anywhere, so it is better to make it a method to limit the context.
     pub fn command_to_run(&self, command: &command::CommandForExec) -> Result<String> {
-        Ok(format!("make {}", command.args))
+        Ok(format!("make {}", command.args_to_string()))
     }
 
-    pub fn new(current_dir: PathBuf) -> Result<Make> {
-        let Some(makefile_name) = Make::specify_makefile_name(current_dir, ".".to_string()) else {
-            return Err(anyhow!("makefile not found.\n"));
+    // find returns None if there is no makefile in `current_dir`.
+    pub fn find(current_dir: PathBuf) -> Result<Option<Make>> {
+        let Some(makefile_name) =
+            Make::specify_makefile_name(current_dir.clone(), current_dir.to_string_lossy().to_string())
+        else {
+            return Ok(None);
         };
-        Make::new_internal(Path::new(&makefile_name).to_path_buf())
+        Make::new_internal(makefile_name, &current_dir).map(Some)
     }
 
     pub fn to_commands(&self) -> Vec<command::CommandWithPreview> {
@@ -41,30 +44,32 @@ impl Make {
         result
     }
 
-    pub fn execute(&self, command: &command::CommandForExec) -> Result<()> {
-        let child = process::Command::new("make")
-            .stdin(process::Stdio::inherit())
-            .args(command.args.split_whitespace())
-            .spawn();
-
-        match child {
-            Ok(mut child) => match child.wait() {
-                Ok(_) => Ok(()),
-                Err(e) => Err(anyhow!("failed to run: {}", e)),
-            },
-            Err(e) => Err(anyhow!("failed to spawn: {}", e)),
+    // source_files returns the makefile and the files included by it.
+    pub fn source_files(&self) -> Vec<PathBuf> {
+        let mut result = vec![self.path.clone()];
+        for include_file in &self.include_files {
+            result.extend(include_file.source_files());
         }
+        result
+    }
+
+    // process_command builds the process to run the command. It is spawned or exec-ed by Runner.
+    pub fn process_command(&self, command: &command::CommandForExec) -> process::Command {
+        let mut process_command = process::Command::new("make");
+        process_command.stdin(process::Stdio::inherit()).args(&command.args);
+        process_command
     }
 
     // I gave up writing tests using temp_dir because it was too difficult (it was necessary to change the implementation to some extent).
     // It is not difficult to ensure that it works with manual tests, so I will not do it for now.
-    fn new_internal(path: PathBuf) -> Result<Make> {
+    // The included files are resolved relative to `current_dir` since make is run there.
+    fn new_internal(path: PathBuf, current_dir: &Path) -> Result<Make> {
         // If the file path does not exist, the make command cannot be executed in the first place,
         // so it is not handled here.
         let file_content = file_util::path_to_content(path.clone())?;
         let include_files = content_to_include_file_paths(file_content.clone())
             .iter()
-            .map(|included_file_path| Make::new_internal(included_file_path.clone()))
+            .map(|included_file_path| Make::new_internal(current_dir.join(included_file_path), current_dir))
             .filter_map(Result::ok)
             .collect();
 
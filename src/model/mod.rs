@@ -1,9 +1,11 @@
 pub(super) mod command;
+pub(super) mod custom;
 pub(crate) mod file_util;
 pub(crate) mod histories;
 pub(super) mod js_package_manager;
 pub(super) mod just;
 pub(super) mod make;
+pub(super) mod project_config;
 pub(super) mod runner;
 pub(super) mod runner_type;
 pub(super) mod task;
@@ -0,0 +1,274 @@
+use super::{
+    command,
+    custom::custom_main::{Custom, CustomCommand},
+    file_util,
+};
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+};
+
+pub const PROJECT_CONFIG_FILE_NAME: &str = ".fzf-make.toml";
+
+/// ProjectConfig is the config in `.fzf-make.toml` to curate the commands listed in the project.
+/// Commands are specified in the form of `<runner> <command>`(e.g. `make build`).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ProjectConfig {
+    // The commands listed at the top in this order.
+    pin: Vec<String>,
+    // The patterns of the commands not to be listed.
+    hide: Vec<glob::Pattern>,
+    // The names and descriptions shown instead of the original ones.
+    commands: HashMap<String, CommandConfig>,
+    custom: Option<Custom>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct CommandConfig {
+    name: Option<String>,
+    description: Option<String>,
+}
+
+/// toml representation of ProjectConfig.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct ProjectConfigToml {
+    pin: Vec<String>,
+    hide: Vec<String>,
+    commands: HashMap<String, CommandConfig>,
+    custom_commands: Vec<CustomCommandToml>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct CustomCommandToml {
+    // Spanned is used to get the line number where the command is defined.
+    name: toml::Spanned<String>,
+    run: String,
+    cwd: Option<PathBuf>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    description: Option<String>,
+}
+
+impl ProjectConfig {
+    // find finds `.fzf-make.toml` in the ancestors of `current_dir` and parses it.
+    // It returns None if there is no `.fzf-make.toml`.
+    pub fn find(current_dir: PathBuf) -> Result<Option<Self>> {
+        let Some(path) = file_util::find_file_in_ancestors(current_dir, vec![PROJECT_CONFIG_FILE_NAME]) else {
+            return Ok(None);
+        };
+        let content = fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        Self::parse(path.clone(), &content)
+            .with_context(|| format!("failed to parse {}", path.display()))
+            .map(Some)
+    }
+
+    fn parse(path: PathBuf, content: &str) -> Result<Self> {
+        let config: ProjectConfigToml = toml::from_str(content)?;
+
+        let hide = config
+            .hide
+            .iter()
+            .map(|p| glob::Pattern::new(p).map_err(|e| anyhow!("invalid pattern in `hide`: {}: {}", p, e)))
+            .collect::<Result<Vec<_>>>()?;
+        let custom_commands: Vec<CustomCommand> = config
+            .custom_commands
+            .into_iter()
+            .map(|c| CustomCommand {
+                line_number: line_number(content, c.name.span().start),
+                name: c.name.into_inner(),
+                run: c.run,
+                cwd: c.cwd,
+                env: c.env,
+                description: c.description,
+            })
+            .collect();
+
+        Ok(Self {
+            pin: config.pin,
+            hide,
+            commands: config.commands,
+            custom: (!custom_commands.is_empty()).then(|| Custom::new(path, custom_commands)),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn new_for_test(content: &str) -> Self {
+        Self::parse(PathBuf::from(PROJECT_CONFIG_FILE_NAME), content).unwrap()
+    }
+
+    // custom_runner returns the runner of the commands defined in `custom-commands`.
+    pub fn custom_runner(&self) -> Option<Custom> {
+        self.custom.clone()
+    }
+
+    // apply hides, renames and describes the commands and moves the pinned commands to the top.
+    pub fn apply(&self, commands: Vec<command::CommandWithPreview>) -> Vec<command::CommandWithPreview> {
+        let mut commands: Vec<command::CommandWithPreview> = commands
+            .into_iter()
+            .filter(|c| {
+                let command = c.to_string();
+                !self.hide.iter().any(|p| p.matches(&command))
+            })
+            .map(|c| {
+                let command = c.to_string();
+                let mut metadata = c.metadata.clone();
+                if let Some(config) = self.commands.get(&command) {
+                    metadata.display_name = config.name.clone().or(metadata.display_name);
+                    metadata.description = config.description.clone().or(metadata.description);
+                }
+                metadata.pinned = self.pin.contains(&command);
+                c.with_metadata(metadata)
+            })
+            .collect();
+        commands.sort_by_key(|c| {
+            let command = c.to_string();
+            self.pin.iter().position(|p| *p == command).unwrap_or(self.pin.len())
+        });
+        commands
+    }
+}
+
+// line_number returns the 1-based line number of the byte offset in `content`.
+fn line_number(content: &str, offset: usize) -> u32 {
+    content[..offset.min(content.len())].matches('\n').count() as u32 + 1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::runner_type;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_test() {
+        let content = r#"pin = ["make build"]
+hide = ["make _*"]
+
+[commands."make release"]
+name = "release build"
+
+[[custom-commands]]
+name = "deploy"
+run = "./deploy.sh"
+cwd = "infra"
+env = { STAGE = "staging" }
+description = "Deploy to staging"
+"#;
+        let path = PathBuf::from("/code/app/.fzf-make.toml");
+        let expected = ProjectConfig {
+            pin: vec!["make build".to_string()],
+            hide: vec![glob::Pattern::new("make _*").unwrap()],
+            commands: HashMap::from([(
+                "make release".to_string(),
+                CommandConfig {
+                    name: Some("release build".to_string()),
+                    description: None,
+                },
+            )]),
+            custom: Some(Custom::new(
+                path.clone(),
+                vec![CustomCommand {
+                    name: "deploy".to_string(),
+                    run: "./deploy.sh".to_string(),
+                    cwd: Some(PathBuf::from("infra")),
+                    env: BTreeMap::from([("STAGE".to_string(), "staging".to_string())]),
+                    description: Some("Deploy to staging".to_string()),
+                    line_number: 8,
+                }],
+            )),
+        };
+        assert_eq!(expected, ProjectConfig::parse(path, content).unwrap());
+    }
+
+    #[test]
+    fn parse_error_test() {
+        struct Case {
+            title: &'static str,
+            content: &'static str,
+            expected: &'static str,
+        }
+        let cases = vec![
+            Case {
+                title: "unknown key",
+                content: "pin = []\npinned = []\n",
+                expected: "TOML parse error at line 2, column 1\n  |\n2 | pinned = []\n  | ^^^^^^\nunknown field `pinned`, expected one of `pin`, `hide`, `commands`, `custom-commands`\n",
+            },
+            Case {
+                title: "custom command without run",
+                content: "[[custom-commands]]\nname = \"deploy\"\n",
+                expected: "TOML parse error at line 1, column 1\n  |\n1 | [[custom-commands]]\n  | ^^^^^^^^^^^^^^^^^^^\nmissing field `run`\n",
+            },
+            Case {
+                title: "invalid pattern",
+                content: "hide = [\"make [\"]\n",
+                expected: "invalid pattern in `hide`: make [: Pattern syntax error near position 5: invalid range pattern",
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(
+                Some(case.expected.to_string()),
+                ProjectConfig::parse(PathBuf::from(".fzf-make.toml"), case.content)
+                    .err()
+                    .map(|e| e.to_string()),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn apply_test() {
+        let command = |args: &str| {
+            command::CommandWithPreview::new(
+                runner_type::RunnerType::Make,
+                args.to_string(),
+                PathBuf::from("Makefile"),
+                1,
+            )
+        };
+        let config = ProjectConfig::parse(
+            PathBuf::from(".fzf-make.toml"),
+            r#"pin = ["make test", "make build"]
+hide = ["make _*"]
+
+[commands."make release"]
+name = "release build"
+description = "Build with optimizations"
+"#,
+        )
+        .unwrap();
+
+        let result: Vec<(String, Option<String>, Option<String>, bool)> = config
+            .apply(vec![
+                command("release"),
+                command("_internal"),
+                command("build"),
+                command("lint"),
+                command("test"),
+            ])
+            .into_iter()
+            .map(|c| (c.args, c.metadata.display_name, c.metadata.description, c.metadata.pinned))
+            .collect();
+        assert_eq!(
+            vec![
+                ("test".to_string(), None, None, true),
+                ("build".to_string(), None, None, true),
+                (
+                    "release".to_string(),
+                    Some("release build".to_string()),
+                    Some("Build with optimizations".to_string()),
+                    false
+                ),
+                ("lint".to_string(), None, None, false),
+            ],
+            result
+        );
+    }
+}
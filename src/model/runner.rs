@@ -1,10 +1,13 @@
 use super::{
-    command, js_package_manager::js_package_manager_main::JsPackageManager, just::just_main::Just,
-    make::make_main::Make, task::task_main::Task,
+    command, custom::custom_main::Custom, js_package_manager::js_package_manager_main::JsPackageManager,
+    just::just_main::Just, make::make_main::Make, task::task_main::Task,
 };
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use colored::Colorize;
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Runner {
@@ -12,6 +15,7 @@ pub enum Runner {
     JsPackageManager(JsPackageManager),
     Just(Just),
     Task(Task),
+    Custom(Custom),
 }
 
 impl Runner {
@@ -21,6 +25,7 @@ impl Runner {
             Runner::JsPackageManager(js) => js.to_commands(),
             Runner::Just(just) => just.to_commands(),
             Runner::Task(task) => task.to_commands(),
+            Runner::Custom(custom) => custom.to_commands(),
         }
     }
 
@@ -30,15 +35,33 @@ impl Runner {
             Runner::JsPackageManager(js) => js.path(),
             Runner::Just(just) => just.path(),
             Runner::Task(task) => task.path(),
+            Runner::Custom(custom) => custom.path(),
         }
     }
 
-    pub fn show_command(&self, command: &command::CommandForExec) {
+    // source_files returns the files which the commands are read from(e.g. the makefile and the included ones).
+    // The paths may be relative to the current directory or directories(e.g. the directory where task is run).
+    pub fn source_files(&self) -> Vec<PathBuf> {
+        let mut files = match self {
+            Runner::MakeCommand(make) => make.source_files(),
+            Runner::Just(just) => just.source_files(),
+            Runner::JsPackageManager(_) | Runner::Task(_) | Runner::Custom(_) => vec![self.path()],
+        };
+        // The commands may be defined in the files not listed above(e.g. package.json in the workspaces or
+        // Taskfiles included by `includes`).
+        files.extend(self.list_commands().into_iter().map(|c| c.file_path));
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    pub fn show_command(&self, command: &command::CommandForExec, current_dir: &Path) {
         let command_or_error_message = match self {
             Runner::MakeCommand(make) => make.command_to_run(command),
             Runner::JsPackageManager(js) => js.command_to_run(command),
-            Runner::Just(just) => just.command_to_run(command),
+            Runner::Just(just) => just.command_to_run(command, current_dir),
             Runner::Task(task) => task.command_to_run(command),
+            Runner::Custom(custom) => custom.command_to_run(command),
         };
 
         println!(
@@ -49,12 +72,71 @@ impl Runner {
         );
     }
 
-    pub fn execute(&self, command: &command::CommandForExec) -> Result<()> {
+    // confirmation_message returns the message to ask the user before executing the command if it is required.
+    pub fn confirmation_message(&self, command: &command::CommandForExec) -> Option<String> {
         match self {
-            Runner::MakeCommand(make) => make.execute(command),
-            Runner::JsPackageManager(js) => js.execute(command),
-            Runner::Just(just) => just.execute(command),
-            Runner::Task(task) => task.execute(command),
+            Runner::Just(just) => just.confirmation_message(command),
+            Runner::MakeCommand(_) | Runner::JsPackageManager(_) | Runner::Task(_) | Runner::Custom(_) => None,
         }
     }
+
+    // confirmed returns the command to execute after the user confirmed it in the TUI.
+    pub fn confirmed(&self, command: &command::CommandForExec) -> command::CommandForExec {
+        match self {
+            Runner::Just(just) => just.confirmed(command),
+            Runner::MakeCommand(_) | Runner::JsPackageManager(_) | Runner::Task(_) | Runner::Custom(_) => {
+                command.clone()
+            }
+        }
+    }
+
+    // execution_details describes how the command will be executed(working directory, shell, etc.).
+    pub fn execution_details(&self, command: &command::CommandForExec, current_dir: &Path) -> Vec<String> {
+        match self {
+            Runner::Just(just) => just.execution_details(command, current_dir),
+            Runner::Task(task) => task.execution_details(command),
+            Runner::Custom(custom) => custom.execution_details(command),
+            Runner::MakeCommand(_) | Runner::JsPackageManager(_) => vec![],
+        }
+    }
+
+    // required_vars returns the variables which should be asked to the user before executing the command.
+    pub fn required_vars(&self, command: &command::CommandForExec) -> Vec<String> {
+        match self {
+            Runner::Task(task) => task.required_vars(command),
+            Runner::MakeCommand(_) | Runner::JsPackageManager(_) | Runner::Just(_) | Runner::Custom(_) => vec![],
+        }
+    }
+
+    // process_command builds the process to run the command. It is spawned by execute or exec-ed by exec.
+    // It fails if the command can not be run(e.g. the custom command was removed from `.fzf-make.toml`),
+    // so that nothing is run and stored in the history.
+    pub fn process_command(&self, command: &command::CommandForExec) -> Result<process::Command> {
+        match self {
+            Runner::MakeCommand(make) => Ok(make.process_command(command)),
+            Runner::JsPackageManager(js) => Ok(js.process_command(command)),
+            Runner::Just(just) => Ok(just.process_command(command)),
+            Runner::Task(task) => Ok(task.process_command(command)),
+            Runner::Custom(custom) => custom.process_command(command),
+        }
+    }
+}
+
+// execute spawns the command, waits for it and returns the exit status of it.
+pub fn execute(mut process_command: process::Command) -> Result<process::ExitStatus> {
+    match process_command.spawn() {
+        Ok(mut child) => match child.wait() {
+            Ok(status) => Ok(status),
+            Err(e) => Err(anyhow!("failed to run: {}", e)),
+        },
+        Err(e) => Err(anyhow!("failed to spawn: {}", e)),
+    }
+}
+
+// exec replaces the fzf-make process with the command(execvp(3)).
+// Since it does not return on success, it returns only the error.
+#[cfg(unix)]
+pub fn exec(mut process_command: process::Command) -> anyhow::Error {
+    use std::os::unix::process::CommandExt;
+    anyhow!("failed to exec: {}", process_command.exec())
 }
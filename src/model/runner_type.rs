@@ -11,6 +11,8 @@ pub enum RunnerType {
     JsPackageManager(JsPackageManager),
     Just,
     Task,
+    // The commands defined in `.fzf-make.toml`.
+    Custom,
 }
 
 #[derive(Hash, PartialEq, Debug, Clone, Serialize, Deserialize, Eq)]
@@ -39,6 +41,7 @@ impl RunnerType {
             RunnerType::Just => "yaml",
             RunnerType::JsPackageManager(_) => "json",
             RunnerType::Task => "yaml",
+            RunnerType::Custom => "toml",
         }
     }
 }
@@ -54,6 +57,7 @@ impl From<runner::Runner> for RunnerType {
             },
             runner::Runner::Just(_) => RunnerType::Just,
             runner::Runner::Task(_) => RunnerType::Task,
+            runner::Runner::Custom(_) => RunnerType::Custom,
         }
     }
 }
@@ -69,6 +73,7 @@ impl fmt::Display for RunnerType {
             },
             RunnerType::Just => "just",
             RunnerType::Task => "task",
+            RunnerType::Custom => "custom",
         };
         write!(f, "{}", name)
     }
@@ -94,6 +99,7 @@ impl<'de> Deserialize<'de> for RunnerType {
             "yarn" => Ok(RunnerType::JsPackageManager(JsPackageManager::Yarn)),
             "just" => Ok(RunnerType::Just),
             "task" => Ok(RunnerType::Task),
+            "custom" => Ok(RunnerType::Custom),
             _ => Err(de::Error::custom(format!("Unknown runner type: {}", s))),
         }
     }
@@ -112,6 +118,7 @@ impl Serialize for RunnerType {
             RunnerType::JsPackageManager(JsPackageManager::Yarn) => serializer.serialize_str("yarn"),
             RunnerType::Just => serializer.serialize_str("just"),
             RunnerType::Task => serializer.serialize_str("task"),
+            RunnerType::Custom => serializer.serialize_str("custom"),
         }
     }
 }
@@ -4,20 +4,37 @@ use crate::model::{
 };
 use anyhow::{Result, anyhow};
 use std::{
-    path::PathBuf,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
     process::{self},
 };
 
+// ref: https://taskfile.dev/reference/cli#exit-codes
+const TASKFILE_NOT_FOUND_EXIT_CODE: i32 = 100;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Task {
     path: PathBuf,
     commands: Vec<command::CommandWithPreview>,
+    // Variables declared in `requires.vars` keyed by task name.
+    // ref: https://taskfile.dev/usage/#ensuring-required-variables-are-set
+    required_vars: HashMap<String, Vec<String>>,
 }
 
 impl Task {
-    pub fn new(cwd: PathBuf) -> Result<Task> {
-        let commands = Self::get_available_commands()?;
-        Ok(Task { path: cwd, commands })
+    // find returns None if `task` is not installed or there is no Taskfile.
+    pub fn find(cwd: PathBuf) -> Result<Option<Task>> {
+        let Some(tasks) = Self::get_available_tasks(&cwd)? else {
+            return Ok(None);
+        };
+        let required_vars = Self::collect_required_vars(&tasks);
+        let commands = tasks.into_iter().map(|task| task.into()).collect();
+        Ok(Some(Task {
+            path: cwd,
+            commands,
+            required_vars,
+        }))
     }
 
     pub fn to_commands(&self) -> Vec<command::CommandWithPreview> {
@@ -29,46 +46,179 @@ impl Task {
     }
 
     pub fn command_to_run(&self, command: &command::CommandForExec) -> Result<String, anyhow::Error> {
-        Ok(format!("task {}", command.args))
+        Ok(format!("task {}", command.args_to_string()))
     }
 
-    pub fn execute(&self, command: &command::CommandForExec) -> Result<(), anyhow::Error> {
-        let child = process::Command::new("task")
-            .stdin(process::Stdio::inherit())
-            .args(command.args.split_whitespace())
-            .spawn();
-
-        match child {
-            Ok(mut child) => match child.wait() {
-                Ok(_) => Ok(()),
-                Err(e) => Err(anyhow!("failed to run: {}", e)),
-            },
-            Err(e) => Err(anyhow!("failed to spawn: {}", e)),
+    pub fn execution_details(&self, command: &command::CommandForExec) -> Vec<String> {
+        let task_name = self.resolve_alias(command.args.first().map(|a| a.as_str()).unwrap_or_default());
+        match self.required_vars.get(task_name) {
+            Some(vars) => vec![format!("required vars: {}", vars.join(", "))],
+            None => vec![],
         }
     }
 
-    // get_available_commands executes `task --list-all --json` and parse the result from it.
-    fn get_available_commands() -> Result<Vec<command::CommandWithPreview>, anyhow::Error> {
-        if process::Command::new("task").arg("--version").output().is_err() {
-            return Err(anyhow!("command not found: task"));
-        }
+    // required_vars returns the variables which are required by the task but not passed as `VAR=value` yet.
+    pub fn required_vars(&self, command: &command::CommandForExec) -> Vec<String> {
+        let mut args = command.args.iter();
+        let Some(task_name) = args.next() else {
+            return vec![];
+        };
+        let passed_vars: Vec<&str> = args
+            .map(|arg| arg.as_str())
+            .filter_map(|arg| arg.split_once('=').map(|(name, _)| name))
+            .collect();
 
-        let output = process::Command::new("task").arg("--list-all").arg("--json").output()?;
-        let output_json = String::from_utf8(output.stdout)?;
-        let tasks = Self::parse_task_json(&output_json)?;
+        self.required_vars
+            .get(self.resolve_alias(task_name))
+            .map(|vars| {
+                vars.iter()
+                    .filter(|var| !passed_vars.contains(&var.as_str()))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 
-        let output = tasks
+    // resolve_alias returns the task name if `name` is an alias of a task because the task can be called by its alias.
+    fn resolve_alias<'a>(&'a self, name: &'a str) -> &'a str {
+        self.commands
             .iter()
-            .map(|task| {
+            .find(|c| c.metadata.aliases.iter().any(|alias| alias == name))
+            .map(|c| c.args.as_str())
+            .unwrap_or(name)
+    }
+
+    #[cfg(test)]
+    pub fn new_for_test() -> Task {
+        use std::env;
+
+        Task {
+            path: env::current_dir().unwrap(),
+            commands: vec![
                 command::CommandWithPreview::new(
                     runner_type::RunnerType::Task,
-                    task.task.clone(),
-                    task.location.taskfile.clone(),
-                    task.location.line,
+                    "build".to_string(),
+                    PathBuf::from("Taskfile.yml"),
+                    2,
+                ),
+                command::CommandWithPreview::new(
+                    runner_type::RunnerType::Task,
+                    "deploy".to_string(),
+                    PathBuf::from("Taskfile.yml"),
+                    6,
                 )
-            })
-            .collect();
-        Ok(output)
+                .with_metadata(command::CommandMetadata {
+                    aliases: vec!["ship".to_string()],
+                    ..command::CommandMetadata::default()
+                }),
+            ],
+            required_vars: HashMap::from([("deploy".to_string(), vec!["ENV".to_string(), "REGION".to_string()])]),
+        }
+    }
+
+    // process_command builds the process to run the command. It is spawned or exec-ed by Runner.
+    pub fn process_command(&self, command: &command::CommandForExec) -> process::Command {
+        let mut process_command = process::Command::new("task");
+        process_command.stdin(process::Stdio::inherit()).args(&command.args);
+        process_command
+    }
+
+    // get_available_tasks executes `task --list-all --json` and parse the result from it.
+    // It returns None if `task` is not installed or there is no Taskfile.
+    fn get_available_tasks(cwd: &Path) -> Result<Option<Vec<TaskListJson>>, anyhow::Error> {
+        if process::Command::new("task").arg("--version").output().is_err() {
+            return Ok(None);
+        }
+
+        let output = process::Command::new("task")
+            .arg("--list-all")
+            .arg("--json")
+            .current_dir(cwd)
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            // Older versions of task exit with 1 instead of the dedicated exit code.
+            if output.status.code() == Some(TASKFILE_NOT_FOUND_EXIT_CODE) || stderr.contains("No Taskfile found") {
+                return Ok(None);
+            }
+            return Err(anyhow!("`task --list-all --json` failed: {}", stderr.trim()));
+        }
+        let output_json = String::from_utf8(output.stdout)?;
+        Self::parse_task_json(&output_json).map(Some)
+    }
+
+    // collect_required_vars reads `requires.vars` of each task from its Taskfile
+    // because `task --list-all --json` does not output them.
+    fn collect_required_vars(tasks: &[TaskListJson]) -> HashMap<String, Vec<String>> {
+        // Taskfile path => (task name in the Taskfile => required vars)
+        let mut taskfiles: HashMap<PathBuf, HashMap<String, Vec<String>>> = HashMap::new();
+        let mut result = HashMap::new();
+
+        for task in tasks {
+            let required_vars_in_taskfile = taskfiles
+                .entry(task.location.taskfile.clone())
+                .or_insert_with(|| Self::read_required_vars(&task.location.taskfile));
+
+            // Tasks in included Taskfiles are prefixed with the namespace like `nested:deploy`.
+            // So the longest task name which matches the suffix is used.
+            let vars = required_vars_in_taskfile
+                .iter()
+                .filter(|(name, _)| task.task == **name || task.task.ends_with(&format!(":{}", name)))
+                .max_by_key(|(name, _)| name.len())
+                .map(|(_, vars)| vars.clone());
+            if let Some(vars) = vars
+                && !vars.is_empty()
+            {
+                result.insert(task.task.clone(), vars);
+            }
+        }
+        result
+    }
+
+    fn read_required_vars(taskfile: &Path) -> HashMap<String, Vec<String>> {
+        fs::read_to_string(taskfile)
+            .map(|content| Self::parse_required_vars(&content))
+            .unwrap_or_default()
+    }
+
+    fn parse_required_vars(taskfile_content: &str) -> HashMap<String, Vec<String>> {
+        /* Example taskfile_content:
+        tasks:
+          deploy:
+            requires:
+              vars:
+                - ENV
+                - name: REGION
+                  enum: [us, eu]
+        */
+        let Ok(taskfile) = serde_yaml_ng::from_str::<serde_yaml_ng::Value>(taskfile_content) else {
+            return HashMap::new();
+        };
+        let Some(tasks) = taskfile.get("tasks").and_then(|t| t.as_mapping()) else {
+            return HashMap::new();
+        };
+
+        let mut result = HashMap::new();
+        for (name, definition) in tasks {
+            let (Some(name), Some(vars)) = (
+                name.as_str(),
+                definition
+                    .get("requires")
+                    .and_then(|r| r.get("vars"))
+                    .and_then(|v| v.as_sequence()),
+            ) else {
+                continue;
+            };
+            let vars: Vec<String> = vars
+                .iter()
+                .filter_map(|var| match var {
+                    serde_yaml_ng::Value::String(name) => Some(name.clone()),
+                    _ => var.get("name").and_then(|n| n.as_str()).map(|n| n.to_string()),
+                })
+                .collect();
+            result.insert(name.to_string(), vars);
+        }
+        result
     }
 
     fn parse_task_json(json_str: &str) -> Result<Vec<TaskListJson>, anyhow::Error> {
@@ -125,8 +275,41 @@ impl Task {
 #[derive(serde::Deserialize, Debug, Clone, PartialEq)]
 struct TaskListJson {
     task: String,
+    #[serde(default)]
+    desc: String,
+    #[serde(default)]
+    summary: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    up_to_date: bool,
     location: Location,
 }
+
+impl From<TaskListJson> for command::CommandWithPreview {
+    fn from(task: TaskListJson) -> command::CommandWithPreview {
+        let non_empty = |s: String| {
+            if s.trim().is_empty() {
+                None
+            } else {
+                Some(s.trim().to_string())
+            }
+        };
+        command::CommandWithPreview::new(
+            runner_type::RunnerType::Task,
+            task.task,
+            task.location.taskfile,
+            task.location.line,
+        )
+        .with_metadata(command::CommandMetadata {
+            description: non_empty(task.desc),
+            summary: non_empty(task.summary),
+            aliases: task.aliases,
+            is_up_to_date: task.up_to_date,
+            ..command::CommandMetadata::default()
+        })
+    }
+}
 #[derive(serde::Deserialize, Debug, Clone, PartialEq)]
 struct Location {
     line: u32,
@@ -162,6 +345,10 @@ mod test {
                 }"#,
                 expected_result: Ok(vec![TaskListJson {
                     task: "deploy".to_string(),
+                    desc: String::new(),
+                    summary: String::new(),
+                    aliases: vec![],
+                    up_to_date: false,
                     location: Location {
                         line: 9,
                         taskfile: PathBuf::from("/Users/test/Taskfile.yml"),
@@ -191,6 +378,10 @@ mod test {
                 expected_result: Ok(vec![
                     TaskListJson {
                         task: "deploy".to_string(),
+                        desc: String::new(),
+                        summary: String::new(),
+                        aliases: vec![],
+                        up_to_date: false,
                         location: Location {
                             line: 9,
                             taskfile: PathBuf::from("/Users/test/Taskfile.yml"),
@@ -198,6 +389,10 @@ mod test {
                     },
                     TaskListJson {
                         task: "setup".to_string(),
+                        desc: String::new(),
+                        summary: String::new(),
+                        aliases: vec![],
+                        up_to_date: false,
                         location: Location {
                             line: 4,
                             taskfile: PathBuf::from("/Users/test/nested/Taskfile.yml"),
@@ -205,6 +400,37 @@ mod test {
                     },
                 ]),
             },
+            Case {
+                title: "Should parse desc, summary, aliases and up_to_date",
+                input: r#"{
+                    "tasks": [
+                        {
+                            "name": "deploy",
+                            "task": "deploy",
+                            "desc": "Deploy the service",
+                            "summary": "Deploy the service\nto the production",
+                            "aliases": ["d"],
+                            "up_to_date": true,
+                            "location": {
+                                "line": 9,
+                                "column": 3,
+                                "taskfile": "/Users/test/Taskfile.yml"
+                            }
+                        }
+                    ]
+                }"#,
+                expected_result: Ok(vec![TaskListJson {
+                    task: "deploy".to_string(),
+                    desc: "Deploy the service".to_string(),
+                    summary: "Deploy the service\nto the production".to_string(),
+                    aliases: vec!["d".to_string()],
+                    up_to_date: true,
+                    location: Location {
+                        line: 9,
+                        taskfile: PathBuf::from("/Users/test/Taskfile.yml"),
+                    },
+                }]),
+            },
             Case {
                 title: "Should parse empty tasks array",
                 input: r#"{"tasks": []}"#,
@@ -237,4 +463,70 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn collect_required_vars_test() {
+        let task = |name: &str, taskfile: &str| TaskListJson {
+            task: name.to_string(),
+            desc: String::new(),
+            summary: String::new(),
+            aliases: vec![],
+            up_to_date: false,
+            location: Location {
+                line: 1,
+                taskfile: PathBuf::from(taskfile),
+            },
+        };
+        let tasks = vec![
+            task("build", "test_data/task/Taskfile.yml"),
+            task("nested:setup", "test_data/task/nested/Taskfile.yml"),
+            task("nested:deploy", "test_data/task/nested/Taskfile.yml"),
+            task("missing", "test_data/task/not_exist/Taskfile.yml"),
+        ];
+
+        assert_eq!(
+            Task::collect_required_vars(&tasks),
+            HashMap::from([("nested:deploy".to_string(), vec!["ENV".to_string(), "REGION".to_string()])])
+        );
+    }
+
+    #[test]
+    fn required_vars_test() {
+        struct Case {
+            title: &'static str,
+            args: &'static str,
+            expected: Vec<String>,
+        }
+        let cases = vec![
+            Case {
+                title: "task without required vars",
+                args: "build",
+                expected: vec![],
+            },
+            Case {
+                title: "task with required vars",
+                args: "deploy",
+                expected: vec!["ENV".to_string(), "REGION".to_string()],
+            },
+            Case {
+                title: "task called by its alias",
+                args: "ship",
+                expected: vec!["ENV".to_string(), "REGION".to_string()],
+            },
+            Case {
+                title: "vars which are already passed are excluded",
+                args: "deploy ENV=prod",
+                expected: vec!["REGION".to_string()],
+            },
+        ];
+
+        let task = Task::new_for_test();
+        for case in cases {
+            let command = command::CommandForExec {
+                runner_type: runner_type::RunnerType::Task,
+                args: case.args.split_whitespace().map(|a| a.to_string()).collect(),
+            };
+            assert_eq!(case.expected, task.required_vars(&command), "\nFailed: 🚨{:?}🚨\n", case.title);
+        }
+    }
 }
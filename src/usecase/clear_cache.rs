@@ -0,0 +1,31 @@
+use crate::{
+    file::{app_dir, command_cache},
+    usecase::usecase_main::{Options, Usecase},
+};
+use anyhow::{Result, anyhow};
+use futures::{FutureExt, future::BoxFuture};
+use std::process;
+
+pub struct ClearCache;
+
+impl ClearCache {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Usecase for ClearCache {
+    fn command_str(&self) -> Vec<&'static str> {
+        vec!["--clear-cache", "clear-cache"]
+    }
+
+    fn run(&self, _: Options) -> BoxFuture<'_, Result<Option<process::ExitStatus>>> {
+        async {
+            let app_dir = app_dir::AppDir::from_env().ok_or_else(|| anyhow!("failed to get the home directory"))?;
+            let dir = command_cache::clear(&app_dir.cache)?;
+            println!("Cleared the cache in {}", dir.display());
+            Ok(None)
+        }
+        .boxed()
+    }
+}
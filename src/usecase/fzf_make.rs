@@ -1,7 +1,11 @@
 use super::tui::config;
-use crate::usecase::{tui::app, usecase_main::Usecase};
+use crate::usecase::{
+    tui::app,
+    usecase_main::{Options, Usecase},
+};
 use anyhow::Result;
 use futures::{FutureExt, future::BoxFuture};
+use std::process;
 
 pub struct FzfMake;
 
@@ -16,7 +20,7 @@ impl Usecase for FzfMake {
         vec![]
     }
 
-    fn run(&self) -> BoxFuture<'_, Result<()>> {
-        async { app::main(config::Config::default()).await }.boxed()
+    fn run(&self, options: Options) -> BoxFuture<'_, Result<Option<process::ExitStatus>>> {
+        async { app::main(config::Config::load(options)?).await }.boxed()
     }
 }
@@ -0,0 +1,23 @@
+use super::tui::{app, config};
+use crate::usecase::usecase_main::{Options, Usecase};
+use anyhow::Result;
+use futures::{FutureExt, future::BoxFuture};
+use std::process;
+
+pub struct GlobalHistory;
+
+impl GlobalHistory {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Usecase for GlobalHistory {
+    fn command_str(&self) -> Vec<&'static str> {
+        vec!["--global-history", "-g", "global-history"]
+    }
+
+    fn run(&self, options: Options) -> BoxFuture<'_, Result<Option<process::ExitStatus>>> {
+        async { app::main(config::Config::load(options)?.global_history()).await }.boxed()
+    }
+}
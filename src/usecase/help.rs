@@ -1,6 +1,7 @@
-use crate::usecase::usecase_main::Usecase;
+use crate::usecase::usecase_main::{Options, Usecase};
 use anyhow::Result;
 use futures::{FutureExt, future::BoxFuture};
+use std::process;
 
 pub struct Help;
 
@@ -15,10 +16,10 @@ impl Usecase for Help {
         vec!["--help", "help"]
     }
 
-    fn run(&self) -> BoxFuture<'_, Result<()>> {
+    fn run(&self, _: Options) -> BoxFuture<'_, Result<Option<process::ExitStatus>>> {
         async {
             println!("{}", get_help());
-            Ok(())
+            Ok(None)
         }
         .boxed()
     }
@@ -37,10 +38,20 @@ SUBCOMMANDS:
         Execute the last executed command.
     history, --history, -h
         Launch fzf-make with the history pane focused.
+    global-history, --global-history, -g
+        Launch fzf-make with the history of all directories focused.
+    clear-cache, --clear-cache
+        Remove the cached commands of all directories.
     help, --help
         Prints help message.
     version, --version, -v
         Prints version information.
+
+OPTIONS:
+    --history-file <PATH>
+        Use the given history file. It can be also specified with `FZF_MAKE_HISTORY_FILE`.
+    --no-cache
+        Neither read nor write the cached commands. The commands are listed by the runners every time.
     "#
     .to_string()
 }
@@ -1,7 +1,8 @@
 use super::tui::{app, config};
-use crate::usecase::usecase_main::Usecase;
+use crate::usecase::usecase_main::{Options, Usecase};
 use anyhow::Result;
 use futures::{FutureExt, future::BoxFuture};
+use std::process;
 
 pub struct History;
 
@@ -16,7 +17,7 @@ impl Usecase for History {
         vec!["--history", "-h", "history"]
     }
 
-    fn run(&self) -> BoxFuture<'_, Result<()>> {
-        async { app::main(config::Config::new(true)).await }.boxed()
+    fn run(&self, options: Options) -> BoxFuture<'_, Result<Option<process::ExitStatus>>> {
+        async { app::main(config::Config::load(options)?.focus_history()).await }.boxed()
     }
 }
@@ -1,7 +1,8 @@
 use super::help;
-use crate::usecase::usecase_main::Usecase;
+use crate::usecase::usecase_main::{Options, Usecase};
 use anyhow::Result;
 use futures::{FutureExt, future::BoxFuture};
+use std::process;
 
 pub struct InvalidArg;
 
@@ -16,11 +17,11 @@ impl Usecase for InvalidArg {
         vec![]
     }
 
-    fn run(&self) -> BoxFuture<'_, Result<()>> {
+    fn run(&self, _: Options) -> BoxFuture<'_, Result<Option<process::ExitStatus>>> {
         async {
             println!("{}", get_message());
             println!("{}", help::get_help());
-            Ok(())
+            Ok(None)
         }
         .boxed()
     }
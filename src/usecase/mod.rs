@@ -1,4 +1,6 @@
+pub(super) mod clear_cache;
 pub(super) mod fzf_make;
+pub(super) mod global_history;
 pub(super) mod help;
 pub(super) mod history;
 pub(super) mod invalid_arg;
@@ -1,10 +1,15 @@
 use super::tui::{
-    app::{AppState, Model},
+    app::{self, AppState, Model},
     config,
 };
-use crate::{model::command, usecase::usecase_main::Usecase};
+use crate::{
+    model::command,
+    usecase::usecase_main::{Options, Usecase},
+};
 use anyhow::{Result, anyhow};
+use colored::Colorize;
 use futures::{FutureExt, future::BoxFuture};
+use std::process;
 
 pub struct Repeat;
 
@@ -19,21 +24,42 @@ impl Usecase for Repeat {
         vec!["--repeat", "-r", "repeat"]
     }
 
-    fn run(&self) -> BoxFuture<'_, Result<()>> {
+    fn run(&self, options: Options) -> BoxFuture<'_, Result<Option<process::ExitStatus>>> {
         async move {
-            match Model::new(config::Config::default()) {
+            let config = config::Config::load(options)?;
+            match Model::new(&config) {
                 Err(e) => Err(e),
                 Ok(model) => match model.app_state {
-                    AppState::SelectCommand(state) => match state.get_latest_command() {
-                        Some(c) => match state.get_runner(&c.runner_type) {
-                            Some(runner) => {
-                                runner.show_command(&command::CommandForExec::from(c.clone()));
-                                runner.execute(&command::CommandForExec::from(c.clone()))
+                    AppState::SelectCommand(mut state) => {
+                        // There is no notification area in repeat, so the warnings(e.g. the failure of storing the
+                        // history last time) are printed.
+                        for warning in &state.warnings {
+                            eprintln!("{}", format!("⚠️ {}", warning).yellow());
+                        }
+                        match state.get_latest_command().cloned() {
+                            Some(c) => {
+                                // The command is executed in the directory where it was executed last time.
+                                state.switch_project(state.history_command_dir(&c));
+                                // The runners are detected in the background.
+                                state.wait_for_runners().await;
+                                match state.get_runner(&c.runner_type) {
+                                    Some(runner) => {
+                                        let command = command::CommandForExec::from(c);
+                                        app::execute_command(
+                                            &runner,
+                                            &command,
+                                            command.clone(),
+                                            state.switched_dir.clone(),
+                                            &config,
+                                        )
+                                        .map(Some)
+                                    }
+                                    None => Err(anyhow!("runner not found.")),
+                                }
                             }
-                            None => Err(anyhow!("runner not found.")),
-                        },
-                        None => Err(anyhow!("fzf-make has not been executed in this path yet.")),
-                    },
+                            None => Err(anyhow!("fzf-make has not been executed in this path yet.")),
+                        }
+                    }
                     _ => Err(anyhow!("Invalid state")),
                 },
             }
@@ -1,40 +1,39 @@
-use super::{config, ui::ui};
+use super::{config, discovery, keymap, narrow_down, preview, theme, ui::ui};
 use crate::{
     error::any_to_string,
-    file::toml,
+    file::{command_cache, toml},
     model::{
         command::{self},
         histories::{self},
         js_package_manager::js_package_manager_main as js,
-        just::just_main::Just,
-        make::make_main::Make,
+        project_config,
         runner::{self, Runner},
         runner_type,
-        task::task_main::Task,
     },
 };
 use anyhow::{Result, anyhow, bail};
 use arboard::Clipboard;
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    event::{DisableMouseCapture, EnableMouseCapture, KeyCode, KeyEvent, KeyEventKind},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use futures::FutureExt;
-use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
 use ratatui::{
     Terminal,
     backend::{Backend, CrosstermBackend},
     widgets::ListState,
 };
 use std::{
+    cell::RefCell,
     collections::HashMap,
     env,
     io::{self, Stderr},
     panic::AssertUnwindSafe,
     path::PathBuf,
+    process,
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tokio::task;
 use tui_textarea::TextArea;
@@ -81,7 +80,7 @@ pub struct Model<'a> {
 }
 
 impl Model<'_> {
-    pub fn new(config: config::Config) -> Result<Self> {
+    pub fn new(config: &config::Config) -> Result<Self> {
         match SelectCommandState::new(config) {
             Ok(s) => Ok(Model {
                 app_state: AppState::SelectCommand(Box::new(s)),
@@ -90,61 +89,85 @@ impl Model<'_> {
         }
     }
 
+    // handle_key_input converts the key to the message through the keymap of the current context.
     fn handle_key_input(&self, key: KeyEvent) -> Option<Message> {
-        if let AppState::SelectCommand(s) = &self.app_state {
-            let is_ctrl_pressed = key.modifiers.contains(KeyModifiers::CONTROL);
-
-            // When additional arguments popup is opened
-            if let Some(additional_arguments_popup_state) = &s.additional_arguments_popup_state {
-                match key.code {
-                    KeyCode::Esc => Some(Message::CloseAdditionalArgumentsWindow),
-                    KeyCode::Tab => None, // no-op: same as Main pane.
-                    KeyCode::Enter => {
-                        Some(Message::ExecuteCommand(additional_arguments_popup_state.append_arguments()))
-                    }
-                    _ => Some(Message::AdditionalArgumentsKeyInput(key)),
-                }
-            } else {
-                // When additional arguments popup is not opened
-                match s.current_pane {
-                    CurrentPane::Main => match (key.code, is_ctrl_pressed) {
-                        (KeyCode::Tab, _) => Some(Message::MoveToNextPane),
-                        (KeyCode::Esc, _) | (KeyCode::Char('c'), true) => Some(Message::Quit),
-                        (KeyCode::Down, _) | (KeyCode::Char('n'), true) => Some(Message::NextCommand),
-                        (KeyCode::Up, _) | (KeyCode::Char('p'), true) => Some(Message::PreviousCommand),
-                        (KeyCode::Char('o'), true) => Some(Message::OpenAdditionalArgumentsWindow),
-                        (KeyCode::Char('y'), true) => Some(Message::CopyCommandToClipboard),
-                        (KeyCode::Enter, _) => match s.get_selected_command() {
-                            Some(c) => Some(Message::ExecuteCommand(c)),
-                            None => Some(Message::NoCommandSelected),
-                        },
-                        _ => Some(Message::SearchTextAreaKeyInput(key)),
-                    },
-                    CurrentPane::History => match (key.code, is_ctrl_pressed) {
-                        (KeyCode::Tab, _) => Some(Message::MoveToNextPane),
-                        (KeyCode::Esc, _) | (KeyCode::Char('c'), true) | (KeyCode::Char('q'), _) => Some(Message::Quit),
-                        (KeyCode::Down, _) | (KeyCode::Char('n'), true) => Some(Message::NextHistory),
-                        (KeyCode::Up, _) | (KeyCode::Char('p'), true) => Some(Message::PreviousHistory),
-                        (KeyCode::Char('o'), true) => Some(Message::OpenAdditionalArgumentsWindow),
-                        (KeyCode::Char('y'), true) => Some(Message::CopyCommandToClipboard),
-                        (KeyCode::Enter, _) | (KeyCode::Char(' '), _) => match s.get_selected_command() {
-                            Some(c) => Some(Message::ExecuteCommand(c)),
-                            None => Some(Message::NoCommandSelected),
-                        },
-                        _ => None,
-                    },
-                }
-            }
-        } else {
-            None
+        let AppState::SelectCommand(s) = &self.app_state else {
+            return None;
+        };
+        let keymap = &s.keymap;
+
+        // When confirm popup is opened
+        // Only `y` executes the command. Submit(e.g. Enter) cancels it since the default answer is No(`[y/N]`).
+        if s.is_confirm_popup_opened() {
+            return match (keymap.popup.action(&key), key.code) {
+                (_, KeyCode::Char('y') | KeyCode::Char('Y')) => Some(Message::ConfirmExecution),
+                (Some(keymap::Action::Close | keymap::Action::Submit), _)
+                | (_, KeyCode::Char('n') | KeyCode::Char('N')) => Some(Message::CancelExecution),
+                _ => None,
+            };
+        }
+
+        // When variables popup is opened
+        if s.is_vars_popup_opened() {
+            return match keymap.popup.action(&key) {
+                Some(keymap::Action::Close) => Some(Message::CloseVarsWindow),
+                Some(keymap::Action::Submit) => Some(Message::SubmitVars),
+                Some(keymap::Action::Next) => Some(Message::NextVar),
+                Some(keymap::Action::Previous) => Some(Message::PreviousVar),
+                _ => Some(Message::VarsKeyInput(key)),
+            };
+        }
+
+        // When additional arguments popup is opened
+        if s.is_additional_arguments_popup_opened() {
+            return match keymap.popup.action(&key) {
+                Some(keymap::Action::Close) => Some(Message::CloseAdditionalArgumentsWindow),
+                Some(keymap::Action::Submit) => Some(Message::SubmitAdditionalArguments),
+                // no-op: there is only one text area.
+                Some(keymap::Action::Next) | Some(keymap::Action::Previous) => None,
+                _ => Some(Message::AdditionalArgumentsKeyInput(key)),
+            };
         }
-    }
 
-    // returns available commands in cwd from history file
-    fn get_histories(current_working_directory: PathBuf) -> Vec<histories::HistoryCommand> {
-        let histories = toml::Histories::into(toml::Histories::get_history());
+        match s.current_pane {
+            CurrentPane::Main => match keymap.main.action(&key) {
+                Some(keymap::Action::NextPane) => Some(Message::MoveToNextPane),
+                Some(keymap::Action::Quit) => Some(Message::Quit),
+                Some(keymap::Action::Next) => Some(Message::NextCommand),
+                Some(keymap::Action::Previous) => Some(Message::PreviousCommand),
+                Some(keymap::Action::AdditionalArguments) => Some(Message::OpenAdditionalArgumentsWindow),
+                Some(keymap::Action::CopyCommand) => Some(Message::CopyCommandToClipboard),
+                Some(keymap::Action::Execute) => match s.get_selected_command() {
+                    Some(c) => Some(Message::ExecuteCommand(c)),
+                    None => Some(Message::NoCommandSelected),
+                },
+                _ => Some(Message::SearchTextAreaKeyInput(key)),
+            },
+            CurrentPane::History => match keymap.history.action(&key) {
+                Some(keymap::Action::NextPane) => Some(Message::MoveToNextPane),
+                Some(keymap::Action::Quit) => Some(Message::Quit),
+                Some(keymap::Action::Next) => Some(Message::NextHistory),
+                Some(keymap::Action::Previous) => Some(Message::PreviousHistory),
+                Some(keymap::Action::ToggleHistoryView) => Some(Message::ToggleHistoryView),
+                Some(keymap::Action::AdditionalArguments) => Some(Message::OpenAdditionalArgumentsWindow),
+                Some(keymap::Action::CopyCommand) => Some(Message::CopyCommandToClipboard),
+                // The command is executed in the directory where it was executed before.
+                Some(keymap::Action::Execute) => match (s.get_selected_command(), s.selected_history_dir()) {
+                    (Some(c), Some(dir)) => Some(Message::ExecuteCommandInDir(dir, c)),
+                    (Some(c), None) => Some(Message::ExecuteCommand(c)),
+                    (None, _) => Some(Message::NoCommandSelected),
+                },
+                _ => None,
+            },
+        }
+    }
 
-        for history in histories.histories {
+    // returns available commands in cwd from the histories read from history file
+    fn get_histories(
+        histories: &histories::Histories,
+        current_working_directory: PathBuf,
+    ) -> Vec<histories::HistoryCommand> {
+        for history in &histories.histories {
             if history.path != current_working_directory {
                 continue;
             }
@@ -152,14 +175,14 @@ impl Model<'_> {
             // Originally, it was filtering out commands in history that no longer exist.
             // But due to the development of the feature to pass arguments to commands,
             // checking the existence of commands was removed.
-            return history.commands;
+            return history.commands.clone();
         }
 
         vec![]
     }
 
-    fn transition_to_execute_command_state(&mut self, runner: runner::Runner, command: command::CommandForExec) {
-        self.app_state = AppState::ExecuteCommand(ExecuteCommandState::new(runner, command));
+    fn transition_to_execute_command_state(&mut self, state: ExecuteCommandState) {
+        self.app_state = AppState::ExecuteCommand(state);
     }
 
     fn transition_to_should_quit_state(&mut self, quit_result: Result<()>) {
@@ -176,22 +199,51 @@ impl Model<'_> {
         }
     }
 
+    // receive_detected_runners adds the runners detected in the background.
+    // It quits with the error if no runner is found after all runners are detected.
+    // The command selected before its runner is detected is executed once the runner is detected.
+    fn receive_detected_runners(&mut self) {
+        let AppState::SelectCommand(s) = &mut self.app_state else {
+            return;
+        };
+        let is_done = s.receive_detected_runners();
+        // The global history can be used even if there is no task runner in the current directory.
+        if is_done && s.runners.is_empty() && !s.history_view.is_global() && s.waiting_command.is_none() {
+            self.transition_to_should_quit_state(Err(anyhow!(NO_RUNNER_FOUND)));
+            return;
+        }
+
+        let Some(command) = s
+            .waiting_command
+            .take_if(|c| is_done || c.runner_type.to_runner(&s.runners).is_some())
+        else {
+            return;
+        };
+        if command.runner_type.to_runner(&s.runners).is_some() {
+            update(self, Some(Message::ExecuteCommand(command)));
+        } else {
+            s.warnings = vec![s.not_available_warning(&command)];
+        }
+    }
+
+    fn is_detecting_runners(&self) -> bool {
+        matches!(&self.app_state, AppState::SelectCommand(s) if s.detecting_runners.is_some())
+    }
+
     fn is_command_selected(&self) -> bool {
         matches!(self.app_state, AppState::ExecuteCommand(_))
     }
 
-    fn command_to_execute(&self) -> Option<(runner::Runner, command::CommandForExec)> {
+    fn command_to_execute(&self) -> Option<ExecuteCommandState> {
         match &self.app_state {
-            AppState::ExecuteCommand(command) => {
-                let command = command.clone();
-                Some((command.executor, command.command))
-            }
+            AppState::ExecuteCommand(command) => Some(command.clone()),
             _ => None,
         }
     }
 }
 
-pub async fn main(config: config::Config) -> Result<()> {
+// main returns the exit status of the executed command. It returns None if no command was executed.
+pub async fn main(config: config::Config) -> Result<Option<process::ExitStatus>> {
     enable_raw_mode()?;
     let mut stderr = io::stderr();
     execute!(stderr, EnterAlternateScreen, EnableMouseCapture)?;
@@ -199,12 +251,12 @@ pub async fn main(config: config::Config) -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     let result = AssertUnwindSafe(async {
-        match Model::new(config) {
+        match Model::new(&config) {
             Ok(mut m) => {
-                match run(&mut terminal, &mut m).await {
+                match run(&mut terminal, &mut m, config.get_check_update()).await {
                     // If async closure will be stabilized, use map instead of match
                     Ok(command) => match command {
-                        Some((runner, command)) => Ok(Some((runner, command))),
+                        Some(command) => Ok(Some(command)),
                         None => Ok(None), // If no command selected, show nothing.
                     },
                     Err(e) => Err(e),
@@ -220,12 +272,11 @@ pub async fn main(config: config::Config) -> Result<()> {
 
     match result {
         // some kind of command was selected
-        Ok(Ok(Some((runner, command)))) => {
-            runner.show_command(&command);
-            runner.execute(&command)
+        Ok(Ok(Some(state))) => {
+            execute_command(&state.executor, &state.command, state.history_command, state.dir, &config).map(Some)
         }
-        Ok(Ok(None)) => Ok(()), // no command was selected
-        Ok(Err(e)) => Err(e),   // Model::new or run returned Err
+        Ok(Ok(None)) => Ok(None), // no command was selected
+        Ok(Err(e)) => Err(e),     // Model::new or run returned Err
         Err(e) => {
             // Since panic content is printed by the panic hook defined in main.rs once,
             // it occurs before terminal is shutdown, so we should print it here again.
@@ -247,15 +298,133 @@ pub async fn main(config: config::Config) -> Result<()> {
     }
 }
 
+// execute_command executes the command in the given mode and stores `history_command` in the history.
+// In exec mode, the history is written before the execution because the process is replaced with the command.
+// Otherwise, it is written after the command exits with its exit code and duration.
+// The history is stored for the directory decided by the history scope with the directory where it is executed.
+// The command is executed in `dir` if it is given, otherwise in the current directory.
+pub fn execute_command(
+    runner: &runner::Runner,
+    command: &command::CommandForExec,
+    history_command: command::CommandForExec,
+    dir: Option<PathBuf>,
+    config: &config::Config,
+) -> Result<process::ExitStatus> {
+    let mut process_command = runner.process_command(command)?;
+    let current_dir = match dir {
+        Some(dir) => dir,
+        None => env::current_dir()?,
+    };
+    // Some runners decide the directory by themselves(e.g. the custom commands run in `cwd`).
+    if process_command.get_current_dir().is_none() {
+        process_command.current_dir(&current_dir);
+    }
+    runner.show_command(command, &current_dir);
+    let history_dir = config.get_history_scope().key(&current_dir);
+    let executed_at = SystemTime::now();
+    let history_command = histories::HistoryCommand {
+        dir: Some(current_dir),
+        ..histories::HistoryCommand::from(history_command)
+    };
+    let execution = |duration: Option<Duration>, status: Option<&process::ExitStatus>| {
+        histories::Execution::new(
+            executed_at
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            duration.map(|d| d.as_millis() as u64),
+            status.map(|s| command::exit_code(*s)),
+        )
+    };
+
+    match config.get_execution_mode() {
+        #[cfg(unix)]
+        config::ExecutionMode::Exec => {
+            store_history(config, history_dir, history_command.with_execution(execution(None, None)));
+            Err(runner::exec(process_command))
+        }
+        _ => {
+            // SIGINT is handled(ignored) while waiting for the command as shells do.
+            // Otherwise fzf-make is also killed by Ctrl-C and the history can not be written.
+            #[cfg(unix)]
+            let _sigint = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt());
+
+            let result = runner::execute(process_command);
+            let duration = executed_at.elapsed().ok();
+            store_history(
+                config,
+                history_dir,
+                history_command.with_execution(execution(duration, result.as_ref().ok())),
+            );
+            result
+        }
+    }
+}
+
+// store_history appends the command to the history file.
+// Since the TUI has already been closed here, the error is saved to be shown in the notification area on the next
+// launch. It is printed only if it can not be saved either.
+fn store_history(config: &config::Config, current_dir: PathBuf, command: histories::HistoryCommand) {
+    let path = match config.get_history_file() {
+        Ok(path) => path,
+        Err(e) => return print_history_error(&e),
+    };
+    let result =
+        toml::update_history_file(&path, |histories| histories.append(current_dir, command, config.get_history_size()));
+    if let Err(e) = result
+        && toml::write_history_error(&path, &e).is_err()
+    {
+        print_history_error(&e);
+    }
+}
+
+fn print_history_error(e: &anyhow::Error) {
+    use colored::Colorize;
+    eprintln!("{}", format!("⚠️ Failed to store the history: {:#}", e).yellow());
+}
+
+const NO_RUNNER_FOUND: &str = "No task runner found.\nRun following command to see usage.\nopen \"https://github.com/kyu08/fzf-make?tab=readme-ov-file#-usage\"";
+
+// find_project_config returns the project config(`.fzf-make.toml`) and the warning if it is broken.
+fn find_project_config(current_dir: PathBuf) -> (project_config::ProjectConfig, Vec<String>) {
+    match project_config::ProjectConfig::find(current_dir) {
+        Ok(c) => (c.unwrap_or_default(), vec![]),
+        Err(e) => (project_config::ProjectConfig::default(), vec![format!("{:#}", e)]),
+    }
+}
+
+// read_cached_commands returns the cached commands of the runners enabled by `runners_config`.
+// The error of reading the cache is added to `warnings`.
+fn read_cached_commands(
+    command_cache: Option<&command_cache::CommandCache>,
+    runners_config: &config::RunnersConfig,
+    warnings: &mut Vec<String>,
+) -> Vec<(runner_type::RunnerType, Vec<command::CommandWithPreview>)> {
+    match command_cache.map(|c| c.read()) {
+        Some(Ok(cached)) => cached
+            .into_iter()
+            .filter(|(runner_type, _)| runners_config.is_enabled(runner_type))
+            .collect(),
+        Some(Err(e)) => {
+            warnings.push(format!("Failed to read the cache: {:#}", e));
+            vec![]
+        }
+        None => vec![],
+    }
+}
+
 const VERSION_KEY: &str = "version";
 async fn run<'a, B: Backend>(
     terminal: &mut Terminal<B>,
     model: &'a mut Model<'a>,
-) -> Result<Option<(runner::Runner, command::CommandForExec)>> {
+    check_update: bool,
+) -> Result<Option<ExecuteCommandState>> {
     let shared_version_hash_map = Arc::new(Mutex::new(HashMap::new()));
 
-    let cloned_hash_map = shared_version_hash_map.clone();
-    tokio::spawn(get_latest_version(cloned_hash_map));
+    if check_update {
+        let cloned_hash_map = shared_version_hash_map.clone();
+        tokio::spawn(get_latest_version(cloned_hash_map));
+    }
 
     loop {
         if let AppState::SelectCommand(s) = &mut model.app_state
@@ -264,6 +433,10 @@ async fn run<'a, B: Backend>(
         {
             s.latest_version = Some(new_version.to_string());
         }
+        model.receive_detected_runners();
+        if let Some(Err(e)) = model.should_quit() {
+            return Err(anyhow!("{e}"));
+        }
 
         if let Err(e) = terminal.draw(|f| ui(f, model)) {
             return Err(anyhow!(e));
@@ -315,6 +488,7 @@ async fn get_latest_version(share_clone: Arc<Mutex<HashMap<String, String>>>) {
     };
 }
 
+#[derive(Debug, PartialEq)]
 enum Message {
     SearchTextAreaKeyInput(KeyEvent),
     ExecuteCommand(command::CommandForExec),
@@ -323,19 +497,37 @@ enum Message {
     MoveToNextPane,
     NextHistory,
     PreviousHistory,
+    ToggleHistoryView,
+    ExecuteCommandInDir(PathBuf, command::CommandForExec),
     NoCommandSelected,
     Quit,
     // Additional arguments
     OpenAdditionalArgumentsWindow,
     CloseAdditionalArgumentsWindow,
     AdditionalArgumentsKeyInput(KeyEvent),
+    SubmitAdditionalArguments,
     // Copy command to clipboard
     CopyCommandToClipboard,
+    // Confirmation before executing the command(e.g. just's `[confirm]` attribute)
+    ConfirmExecution,
+    CancelExecution,
+    // Variables required by the command(e.g. task's `requires.vars`)
+    CloseVarsWindow,
+    SubmitVars,
+    NextVar,
+    PreviousVar,
+    VarsKeyInput(KeyEvent),
 }
 
 // TODO: make this method Model's method
 fn handle_event(model: &Model) -> io::Result<Option<Message>> {
-    if crossterm::event::poll(std::time::Duration::from_millis(500))? {
+    // The detected runners are shown soon while detecting them.
+    let timeout = if model.is_detecting_runners() {
+        Duration::from_millis(50)
+    } else {
+        Duration::from_millis(500)
+    };
+    if crossterm::event::poll(timeout)? {
         match crossterm::event::read()? {
             crossterm::event::Event::Key(key) if key.kind == KeyEventKind::Press => Ok(model.handle_key_input(key)),
             _ => Ok(None),
@@ -352,9 +544,73 @@ fn update(model: &mut Model, message: Option<Message>) {
         match message {
             Some(Message::SearchTextAreaKeyInput(key_event)) => s.handle_key_input(key_event),
             Some(Message::ExecuteCommand(command)) => {
-                s.store_history(command.clone());
-                if let Some(r) = command.runner_type.to_runner(&s.runners) {
-                    model.transition_to_execute_command_state(r, command);
+                let runner = command.runner_type.to_runner(&s.runners);
+                let required_vars = runner.as_ref().map(|r| r.required_vars(&command)).unwrap_or_default();
+                if runner.is_none() && s.detecting_runners.is_some() {
+                    // e.g. the cached command is selected before its runner is detected.
+                    s.waiting_command = Some(command);
+                } else if runner.is_none() {
+                    s.warnings = vec![s.not_available_warning(&command)];
+                } else if !required_vars.is_empty() {
+                    s.open_vars_popup(required_vars, command);
+                } else if let Some(message) = runner.as_ref().and_then(|r| r.confirmation_message(&command)) {
+                    s.open_confirm_popup(message, command);
+                } else if let Some(r) = runner {
+                    let state = ExecuteCommandState {
+                        dir: s.switched_dir.clone(),
+                        ..ExecuteCommandState::new(r, command)
+                    };
+                    model.transition_to_execute_command_state(state);
+                }
+            }
+            Some(Message::ConfirmExecution) => {
+                if let Some(confirm_popup_state) = s.confirm_popup_state.take()
+                    && let Some(r) = confirm_popup_state.command.runner_type.to_runner(&s.runners)
+                {
+                    let command = r.confirmed(&confirm_popup_state.command);
+                    // The command is stored without the flag to skip confirmation so that
+                    // the confirmation is asked again when it is executed from the history.
+                    let state = ExecuteCommandState {
+                        history_command: confirm_popup_state.command,
+                        dir: s.switched_dir.clone(),
+                        ..ExecuteCommandState::new(r, command)
+                    };
+                    model.transition_to_execute_command_state(state);
+                }
+            }
+            Some(Message::CancelExecution) => s.close_confirm_popup(),
+            Some(Message::CloseVarsWindow) => s.close_vars_popup(),
+            Some(Message::SubmitVars) => {
+                if let Some(ref mut vars_popup_state) = s.vars_popup_state {
+                    match vars_popup_state.first_empty_var() {
+                        // The popup is kept opened so that the user can fill in the variable.
+                        // Passing `VAR=` would bypass the check of `requires.vars` by task.
+                        Some(index) => {
+                            vars_popup_state.selected = index;
+                            vars_popup_state.error = Some(format!("{} is required", vars_popup_state.vars[index].0));
+                        }
+                        None => {
+                            let command = vars_popup_state.command_with_vars();
+                            // The variables are passed as arguments, so the popup is not opened again.
+                            s.vars_popup_state = None;
+                            update(model, Some(Message::ExecuteCommand(command)));
+                        }
+                    }
+                }
+            }
+            Some(Message::NextVar) => {
+                if let Some(ref mut vars_popup_state) = s.vars_popup_state {
+                    vars_popup_state.next_var();
+                }
+            }
+            Some(Message::PreviousVar) => {
+                if let Some(ref mut vars_popup_state) = s.vars_popup_state {
+                    vars_popup_state.previous_var();
+                }
+            }
+            Some(Message::VarsKeyInput(key_event)) => {
+                if let Some(ref mut vars_popup_state) = s.vars_popup_state {
+                    vars_popup_state.input(key_event);
                 }
             }
             Some(Message::NextCommand) => s.next_command(),
@@ -362,6 +618,11 @@ fn update(model: &mut Model, message: Option<Message>) {
             Some(Message::MoveToNextPane) => s.move_to_next_pane(),
             Some(Message::NextHistory) => s.next_history(),
             Some(Message::PreviousHistory) => s.previous_history(),
+            Some(Message::ToggleHistoryView) => s.toggle_history_view(),
+            Some(Message::ExecuteCommandInDir(dir, command)) => {
+                s.switch_project(dir);
+                update(model, Some(Message::ExecuteCommand(command)));
+            }
             Some(Message::NoCommandSelected) => {
                 model.transition_to_should_quit_state(Err(anyhow!("No command selected")))
             }
@@ -369,6 +630,18 @@ fn update(model: &mut Model, message: Option<Message>) {
             Some(Message::OpenAdditionalArgumentsWindow) => s.open_additional_arguments_popup(),
             Some(Message::CloseAdditionalArgumentsWindow) => s.close_additional_arguments_popup(),
             Some(Message::AdditionalArgumentsKeyInput(key_event)) => s.handle_additional_arguments_key_input(key_event),
+            Some(Message::SubmitAdditionalArguments) => {
+                if let Some(ref mut additional_arguments_popup_state) = s.additional_arguments_popup_state {
+                    match additional_arguments_popup_state.append_arguments() {
+                        Ok(command) => match additional_arguments_popup_state.dir.clone() {
+                            Some(dir) => update(model, Some(Message::ExecuteCommandInDir(dir, command))),
+                            None => update(model, Some(Message::ExecuteCommand(command))),
+                        },
+                        // The popup is kept opened so that the user can fix the arguments.
+                        Err(e) => additional_arguments_popup_state.error = Some(e),
+                    }
+                }
+            }
             Some(Message::CopyCommandToClipboard) => s.copy_command_to_clipboard(),
             None => {}
         }
@@ -377,19 +650,48 @@ fn update(model: &mut Model, message: Option<Message>) {
 
 #[derive(Debug)]
 pub struct SelectCommandState<'a> {
-    pub current_dir: PathBuf,
     pub current_pane: CurrentPane,
     pub runners: Vec<runner::Runner>,
     pub search_text_area: TextArea_<'a>,
     pub commands_list_state: ListState,
+    // The commands in the history sorted in MRU order. Use get_history to get them sorted by history_ranking.
     pub history: Vec<histories::HistoryCommand>,
     pub history_list_state: ListState,
+    pub history_ranking: histories::Ranking,
+    pub history_scope: histories::Scope,
+    pub history_view: HistoryView,
+    // The histories of all directories. They are shown when history_view is HistoryView::Global.
+    pub global_history: histories::Histories,
+    pub runners_config: config::RunnersConfig,
+    // The config in `.fzf-make.toml` to pin, hide and rename the commands.
+    pub project_config: project_config::ProjectConfig,
+    pub preview: config::PreviewConfig,
+    pub keymap: keymap::Keymap,
+    pub theme: theme::Theme,
     pub additional_arguments_popup_state: Option<AdditionalWindowState<'a>>,
+    pub confirm_popup_state: Option<ConfirmPopupState>,
+    pub vars_popup_state: Option<VarsPopupState<'a>>,
     pub latest_version: Option<String>,
     // The first type variable of Result is intentionally defined as String because we need to get command from
     // the preview pane or the history pane.
     // In the history pane, we don't have file_path and line_number info.
     pub copy_command_state: Option<Result<String, String>>,
+    // Warnings found while detecting runners(e.g. conflicting lockfiles). They are shown in the notification area.
+    pub warnings: Vec<String>,
+    // The cache of narrow_down_commands. RefCell is used since the commands are narrowed down while rendering.
+    narrow_down_cache: RefCell<narrow_down::Cache>,
+    // The cache of the highlighted lines in the preview window.
+    pub preview_cache: preview::Cache,
+    // The runners being detected in the background. They are added to `runners` as they are detected.
+    pub detecting_runners: Option<discovery::Detecting>,
+    // The cache of the commands in the current directory. It is None if the cache is disabled by `--no-cache`.
+    command_cache: Option<command_cache::CommandCache>,
+    // The cached commands of the runners not detected yet. They are listed until the runners are detected.
+    pub cached_commands: Vec<(runner_type::RunnerType, Vec<command::CommandWithPreview>)>,
+    // The command selected before its runner is detected. It is executed once the runner is detected.
+    pub waiting_command: Option<command::CommandForExec>,
+    // The directory switched to by switch_project. None means the current directory.
+    pub switched_dir: Option<PathBuf>,
 }
 
 impl PartialEq for SelectCommandState<'_> {
@@ -398,7 +700,12 @@ impl PartialEq for SelectCommandState<'_> {
             && self.search_text_area == other.search_text_area
             && self.commands_list_state == other.commands_list_state
             && self.history == other.history
-            && self.history_list_state == other.history_list_state;
+            && self.history_list_state == other.history_list_state
+            && self.history_view == other.history_view
+            && self.global_history == other.global_history
+            && self.additional_arguments_popup_state == other.additional_arguments_popup_state
+            && self.confirm_popup_state == other.confirm_popup_state
+            && self.vars_popup_state == other.vars_popup_state;
         if !other_than_runners {
             return false; // Early return for performance
         }
@@ -418,7 +725,7 @@ impl PartialEq for SelectCommandState<'_> {
 }
 
 impl SelectCommandState<'_> {
-    pub fn new(config: config::Config) -> Result<Self> {
+    pub fn new(config: &config::Config) -> Result<Self> {
         let current_dir = match env::current_dir() {
             Ok(d) => d,
             Err(e) => bail!("Failed to get current directory: {}", e),
@@ -430,44 +737,83 @@ impl SelectCommandState<'_> {
             CurrentPane::Main
         };
 
-        let runners = {
-            let mut runners = vec![];
+        let history_view = if config.get_global_history() {
+            HistoryView::Global
+        } else {
+            HistoryView::Project
+        };
 
-            if let Ok(f) = Make::new(current_dir.clone()) {
-                runners.push(Runner::MakeCommand(f));
-            };
-            if let Some(js_package_manager) = js::get_js_package_manager_runner(current_dir.clone()) {
-                runners.push(Runner::JsPackageManager(js_package_manager));
-            };
-            if let Ok(just) = Just::new(current_dir.clone()) {
-                runners.push(Runner::Just(just));
-            };
-            if let Ok(task) = Task::new(current_dir.clone()) {
-                runners.push(Runner::Task(task));
-            };
-            runners
+        // The runners are detected in the background not to block opening the TUI.
+        // Only the custom commands in `.fzf-make.toml` are available at first.
+        let (project_config, mut warnings) = find_project_config(current_dir.clone());
+        let runners = config
+            .get_runners()
+            .apply(project_config.custom_runner().map(Runner::Custom).into_iter().collect());
+        // The commands listed last time are shown until the runners are detected again.
+        let command_cache = config
+            .get_cache_dir()
+            .map(|dir| command_cache::CommandCache::new(&dir, current_dir.clone()));
+        let cached_commands = read_cached_commands(command_cache.as_ref(), &config.get_runners(), &mut warnings);
+        // The history is stored after the TUI is closed, so the error of storing it is shown on the next launch.
+        let (all_histories, history_error) = match config.get_history_file() {
+            Ok(path) => (toml::read_history_file(&path), toml::take_history_error(&path)),
+            Err(e) => (Err(e), None),
+        };
+        let all_histories = match all_histories {
+            Ok(h) => h.into(),
+            Err(e) => {
+                warnings.push(format!("Failed to read the history: {:#}", e));
+                histories::Histories { histories: vec![] }
+            }
         };
+        if let Some(e) = history_error {
+            warnings.push(format!("Failed to store the history last time: {}", e));
+        }
 
-        if runners.is_empty() {
-            Err(anyhow!(
-                "No task runner found.\nRun following command to see usage.\nopen \"https://github.com/kyu08/fzf-make?tab=readme-ov-file#-usage\""
-            ))
-        } else {
-            Ok(SelectCommandState {
-                current_dir: current_dir.clone(),
-                current_pane,
-                runners: runners.clone(),
-                search_text_area: TextArea_(TextArea::default()),
-                commands_list_state: ListState::with_selected(ListState::default(), Some(0)),
-                history: Model::get_histories(current_dir),
-                history_list_state: ListState::with_selected(ListState::default(), Some(0)),
-                additional_arguments_popup_state: None,
-                latest_version: None,
-                copy_command_state: None,
-            })
+        Ok(SelectCommandState {
+            current_pane,
+            runners: runners.clone(),
+            search_text_area: TextArea_(TextArea::default()),
+            commands_list_state: ListState::with_selected(ListState::default(), Some(0)),
+            history: Model::get_histories(&all_histories, config.get_history_scope().key(&current_dir)),
+            history_list_state: ListState::with_selected(ListState::default(), Some(0)),
+            history_ranking: config.get_history_ranking(),
+            history_scope: config.get_history_scope(),
+            history_view,
+            global_history: all_histories,
+            runners_config: config.get_runners(),
+            project_config,
+            preview: config.get_preview(),
+            keymap: config.get_keymap(),
+            theme: config.get_theme()?,
+            additional_arguments_popup_state: None,
+            confirm_popup_state: None,
+            vars_popup_state: None,
+            latest_version: None,
+            copy_command_state: None,
+            warnings,
+            narrow_down_cache: RefCell::new(narrow_down::Cache::default()),
+            preview_cache: preview::Cache::default(),
+            detecting_runners: Some(discovery::Detecting::start(current_dir, &config.get_runners())),
+            command_cache,
+            cached_commands,
+            waiting_command: None,
+            switched_dir: None,
+        })
+    }
+
+    // current_dir returns the directory of the project shown in the TUI.
+    pub fn current_dir(&self) -> PathBuf {
+        match &self.switched_dir {
+            Some(dir) => dir.clone(),
+            None => env::current_dir().unwrap_or_default(),
         }
     }
 
+    fn not_available_warning(&self, command: &command::CommandForExec) -> String {
+        format!("{} is not available in {}", command.runner_type, self.current_dir().display())
+    }
+
     pub fn get_selected_command(&self) -> Option<command::CommandForExec> {
         match self.current_pane {
             CurrentPane::Main => self.selected_command().map(|c| c.into()),
@@ -484,12 +830,16 @@ impl SelectCommandState<'_> {
 
     fn selected_command(&self) -> Option<command::CommandWithPreview> {
         match self.commands_list_state.selected() {
-            Some(i) => self.narrow_down_commands().get(i).cloned(),
+            Some(i) => self.narrow_down_commands_with_indices().get(i).map(|(c, _)| c.clone()),
             None => None,
         }
     }
 
     fn selected_history(&self) -> Option<histories::HistoryCommand> {
+        if self.history_view.is_global() {
+            return self.selected_global_history().map(|c| c.command);
+        }
+
         let history = self.get_history();
         if history.is_empty() {
             return None;
@@ -501,69 +851,134 @@ impl SelectCommandState<'_> {
         }
     }
 
-    pub fn narrow_down_commands(&self) -> Vec<command::CommandWithPreview> {
-        let commands = {
-            let mut commands: Vec<command::CommandWithPreview> = Vec::new();
-            for runner in &self.runners {
-                commands = [commands, runner.list_commands()].concat();
-            }
-            commands
-        };
+    // selected_history_dir returns the directory where the selected command in the history pane was executed.
+    // It returns None if the history pane is not focused.
+    fn selected_history_dir(&self) -> Option<PathBuf> {
+        if !self.current_pane.is_history() {
+            return None;
+        }
+        match self.history_view {
+            HistoryView::Project => self.selected_history().map(|c| self.history_command_dir(&c)),
+            HistoryView::Global => self.selected_global_history().map(|c| c.dir()),
+        }
+    }
 
-        if self.search_text_area.0.is_empty() {
-            return commands;
+    // history_command_dir returns the directory where the command in the history of the current directory was
+    // executed. The directory is not stored if it is the directory of the history(the one decided by history_scope),
+    // which can differ from the current directory(e.g. the command executed in the root of the project is
+    // repeated in its subdirectory).
+    pub fn history_command_dir(&self, command: &histories::HistoryCommand) -> PathBuf {
+        match &command.dir {
+            Some(dir) => dir.clone(),
+            None => self.history_scope.key(&self.current_dir()),
         }
+    }
 
-        // Store the commands in a temporary map in the form of map[command.to_string()]Command
-        let mut temporary_command_map: HashMap<String, command::CommandWithPreview> = HashMap::new();
-        for command in &commands {
-            temporary_command_map.insert(command.to_string(), command.clone());
+    fn selected_global_history(&self) -> Option<histories::GlobalHistoryCommand> {
+        match self.history_list_state.selected() {
+            Some(i) => self.get_global_history().get(i).cloned(),
+            None => None,
         }
+    }
 
-        // filter the commands using fuzzy finder based on the user input
-        let filtered_list: Vec<String> = {
-            let matcher = SkimMatcherV2::default();
-            let mut list: Vec<(i64, String)> = commands
-                .into_iter()
-                .filter_map(|command| {
-                    let mut key_input = self.search_text_area.0.lines().join("");
-                    key_input.retain(|c| !c.is_whitespace());
-                    matcher
-                        .fuzzy_indices(&command.to_string(), key_input.as_str())
-                        .map(|(score, _)| (score, command.to_string()))
-                })
-                .collect();
+    #[cfg(test)]
+    fn narrow_down_commands(&self) -> Vec<command::CommandWithPreview> {
+        self.narrow_down_commands_with_indices()
+            .iter()
+            .map(|(command, _)| command.clone())
+            .collect()
+    }
+
+    // narrow_down_commands_with_indices returns the commands matching the query with the indices of the matched
+    // characters in `command.to_string()` to highlight them. The result is cached until the query is changed.
+    pub fn narrow_down_commands_with_indices(&self) -> narrow_down::Commands {
+        let query = self.search_text_area.0.lines().join(" ");
+        self.narrow_down_cache.borrow_mut().narrow_down(
+            &query,
+            || {
+                let mut commands: Vec<(runner_type::RunnerType, Vec<command::CommandWithPreview>)> = self
+                    .runners
+                    .iter()
+                    .map(|r| (runner_type::RunnerType::from(r.clone()), r.list_commands()))
+                    .chain(self.cached_commands.iter().cloned())
+                    .collect();
+                // The cached commands are listed in the same position as the ones of the detected runner.
+                commands.sort_by_key(|(runner_type, _)| {
+                    (self.runners_config.priority(runner_type), discovery::default_order(runner_type))
+                });
+                self.project_config
+                    .apply(commands.into_iter().flat_map(|(_, commands)| commands).collect())
+            },
+            || {
+                self.history_ranking
+                    .scores(&self.history, histories::now())
+                    .into_iter()
+                    .map(|(c, score)| (format!("{} {}", c.runner_type, c.args.join(" ")), score))
+                    .collect()
+            },
+        )
+    }
 
-            list.sort_by(|(score1, _), (score2, _)| score1.cmp(score2));
-            list.reverse();
+    pub fn get_history(&self) -> Vec<histories::HistoryCommand> {
+        self.history_ranking.rank(self.history.clone(), histories::now())
+    }
 
-            list.into_iter().map(|(_, command)| command).collect()
-        };
+    pub fn get_global_history(&self) -> Vec<histories::GlobalHistoryCommand> {
+        self.global_history
+            .global_commands(self.history_ranking, histories::now())
+    }
 
-        let mut result: Vec<command::CommandWithPreview> = Vec::new();
-        // Get the filtered values from the temporary map
-        for c in filtered_list {
-            if let Some(command) = temporary_command_map.get(&c) {
-                result.push(command.clone());
-            }
+    // history_len returns the number of the commands shown in the history pane.
+    fn history_len(&self) -> usize {
+        match self.history_view {
+            HistoryView::Project => self.get_history().len(),
+            HistoryView::Global => self.get_global_history().len(),
         }
+    }
 
-        result
+    fn toggle_history_view(&mut self) {
+        self.history_view = match self.history_view {
+            HistoryView::Project => HistoryView::Global,
+            HistoryView::Global => HistoryView::Project,
+        };
+        self.history_list_state.select(Some(0));
     }
 
-    pub fn get_history(&self) -> Vec<histories::HistoryCommand> {
-        self.history.clone()
+    // switch_project switches to the project in `dir` to execute the command in the history there.
+    // The runners and the history are replaced with the ones of `dir` so that the command is executed
+    // with the runner in `dir` and stored in the history of `dir`. The runners are detected in the background
+    // as on launch, and the working directory of fzf-make itself is not changed.
+    pub fn switch_project(&mut self, dir: PathBuf) {
+        if self.current_dir() == dir {
+            return;
+        }
+
+        let (project_config, mut warnings) = find_project_config(dir.clone());
+        self.runners = self
+            .runners_config
+            .apply(project_config.custom_runner().map(Runner::Custom).into_iter().collect());
+        self.project_config = project_config;
+        // The runners detected in the previous directory are dropped with its receiver.
+        self.detecting_runners = Some(discovery::Detecting::start(dir.clone(), &self.runners_config));
+        self.command_cache = self.command_cache.as_ref().map(|c| c.for_project(dir.clone()));
+        self.cached_commands = read_cached_commands(self.command_cache.as_ref(), &self.runners_config, &mut warnings);
+        self.warnings = warnings;
+        self.waiting_command = None;
+        self.history = Model::get_histories(&self.global_history, self.history_scope.key(&dir));
+        self.switched_dir = Some(dir);
+        self.narrow_down_cache.get_mut().invalidate();
+        self.commands_list_state.select(Some(0));
     }
 
     fn next_command(&mut self) {
-        if self.narrow_down_commands().is_empty() {
+        if self.narrow_down_commands_with_indices().is_empty() {
             self.commands_list_state.select(None);
             return;
         }
 
         let i = match self.commands_list_state.selected() {
             Some(i) => {
-                if self.narrow_down_commands().len() - 1 <= i {
+                if self.narrow_down_commands_with_indices().len() - 1 <= i {
                     0
                 } else {
                     i + 1
@@ -575,7 +990,7 @@ impl SelectCommandState<'_> {
     }
 
     fn previous_command(&mut self) {
-        if self.narrow_down_commands().is_empty() {
+        if self.narrow_down_commands_with_indices().is_empty() {
             self.commands_list_state.select(None);
             return;
         }
@@ -583,7 +998,7 @@ impl SelectCommandState<'_> {
         let i = match self.commands_list_state.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.narrow_down_commands().len() - 1
+                    self.narrow_down_commands_with_indices().len() - 1
                 } else {
                     i - 1
                 }
@@ -594,15 +1009,15 @@ impl SelectCommandState<'_> {
     }
 
     fn next_history(&mut self) {
-        let history_list = self.get_history();
-        if history_list.is_empty() {
+        let history_list_len = self.history_len();
+        if history_list_len == 0 {
             self.history_list_state.select(None);
             return;
         };
 
         let i = match self.history_list_state.selected() {
             Some(i) => {
-                if history_list.len() - 1 <= i {
+                if history_list_len - 1 <= i {
                     0
                 } else {
                     i + 1
@@ -614,7 +1029,7 @@ impl SelectCommandState<'_> {
     }
 
     fn previous_history(&mut self) {
-        let history_list_len = self.get_history().len();
+        let history_list_len = self.history_len();
         match history_list_len {
             0 => {
                 self.history_list_state.select(None);
@@ -649,7 +1064,10 @@ impl SelectCommandState<'_> {
         if let Some(command) = self.get_selected_command()
             && self.additional_arguments_popup_state.is_none()
         {
-            self.additional_arguments_popup_state = Some(AdditionalWindowState::new(command));
+            self.additional_arguments_popup_state = Some(AdditionalWindowState {
+                dir: self.selected_history_dir(),
+                ..AdditionalWindowState::new(command)
+            });
         }
     }
 
@@ -662,9 +1080,30 @@ impl SelectCommandState<'_> {
     fn handle_additional_arguments_key_input(&mut self, key_event: KeyEvent) {
         if let Some(ref mut additional_window) = self.additional_arguments_popup_state {
             additional_window.arguments_text_area.0.input(key_event);
+            additional_window.error = None;
         }
     }
 
+    fn open_confirm_popup(&mut self, message: String, command: command::CommandForExec) {
+        // The additional arguments popup is replaced with the confirm popup when the command is executed from it.
+        self.additional_arguments_popup_state = None;
+        self.confirm_popup_state = Some(ConfirmPopupState { message, command });
+    }
+
+    fn close_confirm_popup(&mut self) {
+        self.confirm_popup_state = None;
+    }
+
+    fn open_vars_popup(&mut self, vars: Vec<String>, command: command::CommandForExec) {
+        // The additional arguments popup is replaced with the variables popup when the command is executed from it.
+        self.additional_arguments_popup_state = None;
+        self.vars_popup_state = Some(VarsPopupState::new(vars, command));
+    }
+
+    fn close_vars_popup(&mut self) {
+        self.vars_popup_state = None;
+    }
+
     fn copy_command_to_clipboard(&mut self) {
         let command = match self.get_selected_command() {
             Some(c) => c.to_string(),
@@ -686,22 +1125,64 @@ impl SelectCommandState<'_> {
         }
     }
 
-    fn store_history(&self, command: command::CommandForExec) {
-        // NOTE: self.get_selected_command should be called before self.append_history.
-        // Because self.histories_list_state.selected keeps the selected index of the history list
-        // before update.
-        if let Some((dir, file_name)) = toml::history_file_path() {
-            let all_histories = toml::Histories::get_history()
-                .into()
-                .append(self.current_dir.clone(), command);
-
-            // TODO: handle error
-            let _ = toml::create_or_update_history_file(dir, file_name, all_histories);
+    // receive_detected_runners adds the runners detected since the last call and shows their errors
+    // in the notification area. The cached commands are replaced with the ones of the detected runners and
+    // the cache is updated when all runners have been detected. It returns true when all runners have been detected.
+    fn receive_detected_runners(&mut self) -> bool {
+        let Some(detecting) = &mut self.detecting_runners else {
+            return false;
         };
+        let detected = detecting.receive();
+        let is_done = detecting.is_done();
+        self.add_detected_runners(detected, is_done);
+        is_done
+    }
+
+    // add_detected_runners adds the detected runners. The cache is updated once all runners are detected.
+    fn add_detected_runners(&mut self, detected: Vec<discovery::Detected>, is_done: bool) {
+        if !detected.is_empty() {
+            let mut runners = self.runners.clone();
+            for d in detected {
+                runners.extend(d.runner);
+                self.warnings.extend(d.warnings);
+            }
+            discovery::sort(&mut runners);
+            self.runners = self.runners_config.apply(runners);
+            let runner_types: Vec<runner_type::RunnerType> = self
+                .runners
+                .iter()
+                .map(|r| runner_type::RunnerType::from(r.clone()))
+                .collect();
+            self.cached_commands
+                .retain(|(runner_type, _)| !runner_types.contains(runner_type));
+            self.narrow_down_cache.get_mut().invalidate();
+        }
+        if is_done {
+            self.detecting_runners = None;
+            // The cached commands of the runners which are not found anymore are removed.
+            if !self.cached_commands.is_empty() {
+                self.cached_commands = vec![];
+                self.narrow_down_cache.get_mut().invalidate();
+            }
+            if let Some(command_cache) = &self.command_cache
+                && let Err(e) = command_cache.write(&self.runners)
+            {
+                self.warnings.push(format!("Failed to write the cache: {:#}", e));
+            }
+        }
+    }
+
+    // wait_for_runners waits until all runners are detected. It is used when the runners are needed
+    // without the TUI(e.g. `fzf-make repeat`).
+    pub async fn wait_for_runners(&mut self) {
+        if let Some(detecting) = &mut self.detecting_runners {
+            let detected = detecting.wait().await;
+            self.add_detected_runners(detected, true);
+        }
     }
 
     fn reset_selection(&mut self) {
-        if self.narrow_down_commands().is_empty() {
+        if self.narrow_down_commands_with_indices().is_empty() {
             self.commands_list_state.select(None);
         }
         self.commands_list_state.select(Some(0));
@@ -717,6 +1198,9 @@ impl SelectCommandState<'_> {
                 (runner_type::RunnerType::Make, runner::Runner::MakeCommand(_)) => {
                     return Some(runner.clone());
                 }
+                (runner_type::RunnerType::Custom, runner::Runner::Custom(_)) => {
+                    return Some(runner.clone());
+                }
                 (
                     runner_type::RunnerType::JsPackageManager(runner_type_js),
                     runner::Runner::JsPackageManager(runner_js),
@@ -751,57 +1235,147 @@ impl SelectCommandState<'_> {
         self.additional_arguments_popup_state.is_some()
     }
 
+    pub fn is_confirm_popup_opened(&self) -> bool {
+        self.confirm_popup_state.is_some()
+    }
+
+    pub fn is_vars_popup_opened(&self) -> bool {
+        self.vars_popup_state.is_some()
+    }
+
+    pub fn is_popup_opened(&self) -> bool {
+        self.is_additional_arguments_popup_opened() || self.is_confirm_popup_opened() || self.is_vars_popup_opened()
+    }
+
     #[cfg(test)]
     fn new_for_test() -> Self {
-        use crate::model::runner_type;
+        use crate::model::{make::make_main::Make, runner_type};
 
         SelectCommandState {
-            current_dir: env::current_dir().unwrap(),
             current_pane: CurrentPane::Main,
             runners: vec![runner::Runner::MakeCommand(Make::new_for_test())],
             search_text_area: TextArea_(TextArea::default()),
             commands_list_state: ListState::with_selected(ListState::default(), Some(0)),
             history: vec![
-                histories::HistoryCommand {
-                    runner_type: runner_type::RunnerType::Make,
-                    args: "history0".to_string(),
-                },
-                histories::HistoryCommand {
-                    runner_type: runner_type::RunnerType::Make,
-                    args: "history1".to_string(),
-                },
-                histories::HistoryCommand {
-                    runner_type: runner_type::RunnerType::Make,
-                    args: "history2".to_string(),
-                },
+                histories::HistoryCommand::new(runner_type::RunnerType::Make, vec!["history0".to_string()]),
+                histories::HistoryCommand::new(runner_type::RunnerType::Make, vec!["history1".to_string()]),
+                histories::HistoryCommand::new(runner_type::RunnerType::Make, vec!["history2".to_string()]),
             ],
             history_list_state: ListState::with_selected(ListState::default(), Some(0)),
+            history_ranking: histories::Ranking::default(),
+            history_scope: histories::Scope::default(),
+            history_view: HistoryView::Project,
+            global_history: histories::Histories { histories: vec![] },
+            runners_config: config::RunnersConfig::default(),
+            project_config: project_config::ProjectConfig::default(),
+            preview: config::PreviewConfig::default(),
+            keymap: keymap::Keymap::default(),
+            theme: theme::Theme::default(),
             additional_arguments_popup_state: None,
+            confirm_popup_state: None,
+            vars_popup_state: None,
             latest_version: None,
             copy_command_state: None,
+            warnings: vec![],
+            narrow_down_cache: RefCell::new(narrow_down::Cache::default()),
+            preview_cache: preview::Cache::default(),
+            detecting_runners: None,
+            command_cache: None,
+            cached_commands: vec![],
+            waiting_command: None,
+            switched_dir: None,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct AdditionalWindowState<'a> {
     pub arguments_text_area: TextArea_<'a>,
+    // The error which occurred while parsing the arguments(e.g. unbalanced quotes).
+    pub error: Option<String>,
     command: command::CommandForExec,
+    // The directory where the command is executed. None means the current directory.
+    dir: Option<PathBuf>,
 }
 
 impl AdditionalWindowState<'_> {
     pub fn new(command: command::CommandForExec) -> Self {
         Self {
             arguments_text_area: TextArea_(TextArea::default()),
+            error: None,
+            command,
+            dir: None,
+        }
+    }
+
+    // append_arguments parses the input following POSIX shell rules and appends them to the command.
+    pub fn append_arguments(&self) -> Result<command::CommandForExec, String> {
+        self.command
+            .append_shell_words(&self.arguments_text_area.0.lines().join(" "))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfirmPopupState {
+    pub message: String,
+    // The command to be executed after the user confirmed.
+    pub command: command::CommandForExec,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VarsPopupState<'a> {
+    // (variable name, input)
+    pub vars: Vec<(String, TextArea_<'a>)>,
+    pub selected: usize,
+    // The error shown when the variables are submitted without some of them(e.g. `ENV is required`).
+    pub error: Option<String>,
+    // The command to be executed with the variables.
+    command: command::CommandForExec,
+}
+
+impl VarsPopupState<'_> {
+    pub fn new(vars: Vec<String>, command: command::CommandForExec) -> Self {
+        Self {
+            vars: vars
+                .into_iter()
+                .map(|var| (var, TextArea_(TextArea::default())))
+                .collect(),
+            selected: 0,
+            error: None,
             command,
         }
     }
 
-    pub fn append_arguments(&self) -> command::CommandForExec {
+    fn next_var(&mut self) {
+        self.selected = (self.selected + 1) % self.vars.len();
+    }
+
+    fn previous_var(&mut self) {
+        self.selected = (self.selected + self.vars.len() - 1) % self.vars.len();
+    }
+
+    fn input(&mut self, key_event: KeyEvent) {
+        if let Some((_, text_area)) = self.vars.get_mut(self.selected) {
+            text_area.0.input(key_event);
+            self.error = None;
+        }
+    }
+
+    // first_empty_var returns the index of the first variable which is not filled in.
+    fn first_empty_var(&self) -> Option<usize> {
+        self.vars
+            .iter()
+            .position(|(_, text_area)| text_area.0.lines().join(" ").trim().is_empty())
+    }
+
+    // command_with_vars returns the command with the variables passed as `VAR=value`.
+    pub fn command_with_vars(&self) -> command::CommandForExec {
         let mut new_command = self.command.clone();
-        let arguments = self.arguments_text_area.0.lines().join(" ").trim().to_string();
-        if !arguments.is_empty() {
-            new_command.args.push_str(&format!(" {}", arguments));
+        for (var, text_area) in &self.vars {
+            // The value is passed as one argument, so it can contain whitespaces.
+            new_command
+                .args
+                .push(format!("{}={}", var, text_area.0.lines().join(" ").trim()));
         }
         new_command
     }
@@ -811,13 +1385,22 @@ impl AdditionalWindowState<'_> {
 pub struct ExecuteCommandState {
     /// It is possible to have one concrete type like Command struct here.
     /// But from the perspective of simpleness of code base, this field has trait object.
-    executor: runner::Runner,
+    executor: Box<runner::Runner>,
     command: command::CommandForExec,
+    // The command stored in the history. It can differ from `command`(e.g. without the flag to skip confirmation).
+    history_command: command::CommandForExec,
+    // The directory where the command is executed. None means the current directory.
+    dir: Option<PathBuf>,
 }
 
 impl ExecuteCommandState {
     fn new(executor: runner::Runner, command: command::CommandForExec) -> Self {
-        ExecuteCommandState { executor, command }
+        ExecuteCommandState {
+            executor: Box::new(executor),
+            history_command: command.clone(),
+            command,
+            dir: None,
+        }
     }
 }
 
@@ -837,6 +1420,21 @@ impl CurrentPane {
     }
 }
 
+/// HistoryView decides which commands are shown in the history pane.
+#[derive(Clone, PartialEq, Debug)]
+pub enum HistoryView {
+    /// The commands executed in the current directory.
+    Project,
+    /// The commands executed in all directories.
+    Global,
+}
+
+impl HistoryView {
+    pub fn is_global(&self) -> bool {
+        matches!(self, HistoryView::Global)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct TextArea_<'a>(pub TextArea<'a>);
 
@@ -850,10 +1448,68 @@ impl PartialEq for TextArea_<'_> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::model::runner_type;
+    use crate::model::{just::just_main::Just, make::make_main::Make, runner_type, task::task_main::Task};
     use pretty_assertions::assert_eq;
     use std::env;
 
+    // See test_data/just/attributes for the justfile.
+    fn just() -> Just {
+        Just::find(PathBuf::from("test_data/just/attributes")).unwrap().unwrap()
+    }
+
+    fn just_command(args: &str) -> command::CommandForExec {
+        command::CommandForExec {
+            runner_type: runner_type::RunnerType::Just,
+            args: args.split_whitespace().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    fn select_command_state_with_just<'a>() -> SelectCommandState<'a> {
+        SelectCommandState {
+            runners: vec![runner::Runner::Just(just())],
+            ..SelectCommandState::new_for_test()
+        }
+    }
+
+    fn task_command(args: &str) -> command::CommandForExec {
+        command::CommandForExec {
+            runner_type: runner_type::RunnerType::Task,
+            args: args.split_whitespace().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    fn select_command_state_with_task<'a>() -> SelectCommandState<'a> {
+        SelectCommandState {
+            runners: vec![runner::Runner::Task(Task::new_for_test())],
+            ..SelectCommandState::new_for_test()
+        }
+    }
+
+    fn global_history() -> histories::Histories {
+        histories::Histories {
+            histories: vec![
+                histories::History {
+                    path: PathBuf::from("/code/app"),
+                    commands: vec![histories::HistoryCommand::new(
+                        runner_type::RunnerType::Make,
+                        vec!["build".to_string()],
+                    )],
+                },
+                histories::History {
+                    path: PathBuf::from("/code/lib"),
+                    commands: vec![histories::HistoryCommand::new(
+                        runner_type::RunnerType::Make,
+                        vec!["test".to_string()],
+                    )],
+                },
+            ],
+        }
+    }
+
+    fn text_area<'a>(text: &str) -> TextArea_<'a> {
+        TextArea_(TextArea::from(vec![text.to_string()]))
+    }
+
     #[test]
     fn update_test() {
         struct Case<'a> {
@@ -1026,14 +1682,14 @@ mod test {
                 },
                 message: Some(Message::ExecuteCommand(command::CommandForExec {
                     runner_type: runner_type::RunnerType::Make,
-                    args: "target0".to_string(),
+                    args: vec!["target0".to_string()],
                 })),
                 expect_model: Model {
                     app_state: AppState::ExecuteCommand(ExecuteCommandState::new(
                         runner::Runner::MakeCommand(Make::new_for_test()),
                         command::CommandForExec {
                             runner_type: runner_type::RunnerType::Make,
-                            args: "target0".to_string(),
+                            args: vec!["target0".to_string()],
                         },
                     )),
                 },
@@ -1049,50 +1705,146 @@ mod test {
                 },
                 message: Some(Message::ExecuteCommand(command::CommandForExec {
                     runner_type: runner_type::RunnerType::Make,
-                    args: "history1".to_string(),
+                    args: vec!["history1".to_string()],
                 })),
                 expect_model: Model {
                     app_state: AppState::ExecuteCommand(ExecuteCommandState::new(
                         runner::Runner::MakeCommand(Make::new_for_test()),
                         command::CommandForExec {
                             runner_type: runner_type::RunnerType::Make,
-                            args: "history1".to_string(),
+                            args: vec!["history1".to_string()],
                         },
                     )),
                 },
             },
             Case {
-                title: "Selecting position should be reset if some kind of char
-                    was inputted when the command located not in top of the commands",
+                title: "ToggleHistoryView(Project -> Global)",
                 model: Model {
                     app_state: AppState::SelectCommand(Box::new(SelectCommandState {
-                        commands_list_state: ListState::with_selected(ListState::default(), Some(1)),
+                        current_pane: CurrentPane::History,
+                        history_list_state: ListState::with_selected(ListState::default(), Some(2)),
                         ..SelectCommandState::new_for_test()
                     })),
                 },
-                message: Some(Message::SearchTextAreaKeyInput(KeyEvent::from(KeyCode::Char('a')))),
+                message: Some(Message::ToggleHistoryView),
                 expect_model: Model {
                     app_state: AppState::SelectCommand(Box::new(SelectCommandState {
-                        commands_list_state: ListState::with_selected(ListState::default(), Some(0)),
-                        search_text_area: {
-                            let mut text_area = TextArea::default();
-                            text_area.input(KeyEvent::from(KeyCode::Char('a')));
-                            TextArea_(text_area)
-                        },
+                        current_pane: CurrentPane::History,
+                        history_view: HistoryView::Global,
+                        history_list_state: ListState::with_selected(ListState::default(), Some(0)),
                         ..SelectCommandState::new_for_test()
                     })),
                 },
             },
             Case {
-                title: "NextCommand when there is no commands to select, panic should not occur",
-                model: {
-                    let mut m = Model {
-                        app_state: AppState::SelectCommand(Box::new(SelectCommandState {
-                            commands_list_state: ListState::with_selected(ListState::default(), None),
-                            ..SelectCommandState::new_for_test()
-                        })),
-                    };
-                    update(
+                title: "NextHistory(Global)",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        current_pane: CurrentPane::History,
+                        history_view: HistoryView::Global,
+                        global_history: global_history(),
+                        history_list_state: ListState::with_selected(ListState::default(), Some(1)),
+                        ..SelectCommandState::new_for_test()
+                    })),
+                },
+                message: Some(Message::NextHistory),
+                expect_model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        current_pane: CurrentPane::History,
+                        history_view: HistoryView::Global,
+                        global_history: global_history(),
+                        // The global history has only 2 commands, so it returns to the beginning.
+                        history_list_state: ListState::with_selected(ListState::default(), Some(0)),
+                        ..SelectCommandState::new_for_test()
+                    })),
+                },
+            },
+            Case {
+                title: "ExecuteCommandInDir(the current directory)",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        current_pane: CurrentPane::History,
+                        history_view: HistoryView::Global,
+                        ..SelectCommandState::new_for_test()
+                    })),
+                },
+                message: Some(Message::ExecuteCommandInDir(
+                    env::current_dir().unwrap(),
+                    command::CommandForExec {
+                        runner_type: runner_type::RunnerType::Make,
+                        args: vec!["target0".to_string()],
+                    },
+                )),
+                expect_model: Model {
+                    app_state: AppState::ExecuteCommand(ExecuteCommandState::new(
+                        runner::Runner::MakeCommand(Make::new_for_test()),
+                        command::CommandForExec {
+                            runner_type: runner_type::RunnerType::Make,
+                            args: vec!["target0".to_string()],
+                        },
+                    )),
+                },
+            },
+            Case {
+                title: "ExecuteCommandInDir(the runner is not available)",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        current_pane: CurrentPane::History,
+                        history_view: HistoryView::Global,
+                        ..SelectCommandState::new_for_test()
+                    })),
+                },
+                message: Some(Message::ExecuteCommandInDir(
+                    env::current_dir().unwrap(),
+                    command::CommandForExec {
+                        runner_type: runner_type::RunnerType::Task,
+                        args: vec!["build".to_string()],
+                    },
+                )),
+                expect_model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        current_pane: CurrentPane::History,
+                        history_view: HistoryView::Global,
+                        warnings: vec![format!(
+                            "task is not available in {}",
+                            env::current_dir().unwrap().display()
+                        )],
+                        ..SelectCommandState::new_for_test()
+                    })),
+                },
+            },
+            Case {
+                title: "Selecting position should be reset if some kind of char
+                    was inputted when the command located not in top of the commands",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        commands_list_state: ListState::with_selected(ListState::default(), Some(1)),
+                        ..SelectCommandState::new_for_test()
+                    })),
+                },
+                message: Some(Message::SearchTextAreaKeyInput(KeyEvent::from(KeyCode::Char('a')))),
+                expect_model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        commands_list_state: ListState::with_selected(ListState::default(), Some(0)),
+                        search_text_area: {
+                            let mut text_area = TextArea::default();
+                            text_area.input(KeyEvent::from(KeyCode::Char('a')));
+                            TextArea_(text_area)
+                        },
+                        ..SelectCommandState::new_for_test()
+                    })),
+                },
+            },
+            Case {
+                title: "NextCommand when there is no commands to select, panic should not occur",
+                model: {
+                    let mut m = Model {
+                        app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                            commands_list_state: ListState::with_selected(ListState::default(), None),
+                            ..SelectCommandState::new_for_test()
+                        })),
+                    };
+                    update(
                         // There should not be commands because init_model has ["target0", "target1", "target2"] as command.
                         &mut m,
                         Some(Message::SearchTextAreaKeyInput(KeyEvent::from(KeyCode::Char('w')))),
@@ -1216,17 +1968,477 @@ mod test {
                     })),
                 },
             },
+            Case {
+                title: "SubmitAdditionalArguments passes quoted arguments as one argument",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        additional_arguments_popup_state: Some(AdditionalWindowState {
+                            arguments_text_area: text_area(r#"--set "a b""#),
+                            ..AdditionalWindowState::new(just_command("test"))
+                        }),
+                        ..select_command_state_with_just()
+                    })),
+                },
+                message: Some(Message::SubmitAdditionalArguments),
+                expect_model: Model {
+                    app_state: AppState::ExecuteCommand(ExecuteCommandState::new(
+                        runner::Runner::Just(just()),
+                        command::CommandForExec {
+                            runner_type: runner_type::RunnerType::Just,
+                            args: vec!["test".to_string(), "--set".to_string(), "a b".to_string()],
+                        },
+                    )),
+                },
+            },
+            Case {
+                title: "OpenAdditionalArgumentsWindow keeps the directory of the command in the global history",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        current_pane: CurrentPane::History,
+                        history_view: HistoryView::Global,
+                        global_history: global_history(),
+                        ..SelectCommandState::new_for_test()
+                    })),
+                },
+                message: Some(Message::OpenAdditionalArgumentsWindow),
+                expect_model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        current_pane: CurrentPane::History,
+                        history_view: HistoryView::Global,
+                        global_history: global_history(),
+                        additional_arguments_popup_state: Some(AdditionalWindowState {
+                            dir: Some(PathBuf::from("/code/app")),
+                            ..AdditionalWindowState::new(command::CommandForExec {
+                                runner_type: runner_type::RunnerType::Make,
+                                args: vec!["build".to_string()],
+                            })
+                        }),
+                        ..SelectCommandState::new_for_test()
+                    })),
+                },
+            },
+            Case {
+                title: "SubmitAdditionalArguments executes the command in the directory of the popup",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        additional_arguments_popup_state: Some(AdditionalWindowState {
+                            arguments_text_area: text_area("--verbose"),
+                            dir: Some(env::current_dir().unwrap()),
+                            ..AdditionalWindowState::new(just_command("test"))
+                        }),
+                        ..select_command_state_with_just()
+                    })),
+                },
+                message: Some(Message::SubmitAdditionalArguments),
+                expect_model: Model {
+                    app_state: AppState::ExecuteCommand(ExecuteCommandState::new(
+                        runner::Runner::Just(just()),
+                        command::CommandForExec {
+                            runner_type: runner_type::RunnerType::Just,
+                            args: vec!["test".to_string(), "--verbose".to_string()],
+                        },
+                    )),
+                },
+            },
+            Case {
+                title: "SubmitAdditionalArguments shows the error in the popup when the quotes are unbalanced",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        additional_arguments_popup_state: Some(AdditionalWindowState {
+                            arguments_text_area: text_area(r#"--set "a b"#),
+                            ..AdditionalWindowState::new(just_command("test"))
+                        }),
+                        ..select_command_state_with_just()
+                    })),
+                },
+                message: Some(Message::SubmitAdditionalArguments),
+                expect_model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        additional_arguments_popup_state: Some(AdditionalWindowState {
+                            arguments_text_area: text_area(r#"--set "a b"#),
+                            error: Some("failed to parse arguments: missing closing quote".to_string()),
+                            ..AdditionalWindowState::new(just_command("test"))
+                        }),
+                        ..select_command_state_with_just()
+                    })),
+                },
+            },
+            Case {
+                title: "ExecuteCommand opens the confirm popup for a recipe which requires confirmation",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        additional_arguments_popup_state: Some(AdditionalWindowState::new(just_command("deploy"))),
+                        ..select_command_state_with_just()
+                    })),
+                },
+                message: Some(Message::ExecuteCommand(just_command("deploy"))),
+                expect_model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        confirm_popup_state: Some(ConfirmPopupState {
+                            message: "Deploy to production?".to_string(),
+                            command: just_command("deploy"),
+                        }),
+                        ..select_command_state_with_just()
+                    })),
+                },
+            },
+            Case {
+                title: "ExecuteCommand executes a recipe which does not require confirmation immediately",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(select_command_state_with_just())),
+                },
+                message: Some(Message::ExecuteCommand(just_command("test"))),
+                expect_model: Model {
+                    app_state: AppState::ExecuteCommand(ExecuteCommandState::new(
+                        runner::Runner::Just(just()),
+                        just_command("test"),
+                    )),
+                },
+            },
+            Case {
+                title: "ConfirmExecution",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        confirm_popup_state: Some(ConfirmPopupState {
+                            message: "Deploy to production?".to_string(),
+                            command: just_command("deploy"),
+                        }),
+                        ..select_command_state_with_just()
+                    })),
+                },
+                message: Some(Message::ConfirmExecution),
+                expect_model: Model {
+                    app_state: AppState::ExecuteCommand(ExecuteCommandState {
+                        history_command: just_command("deploy"),
+                        ..ExecuteCommandState::new(runner::Runner::Just(just()), just_command("--yes deploy"))
+                    }),
+                },
+            },
+            Case {
+                title: "CancelExecution",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        confirm_popup_state: Some(ConfirmPopupState {
+                            message: "Deploy to production?".to_string(),
+                            command: just_command("deploy"),
+                        }),
+                        ..select_command_state_with_just()
+                    })),
+                },
+                message: Some(Message::CancelExecution),
+                expect_model: Model {
+                    app_state: AppState::SelectCommand(Box::new(select_command_state_with_just())),
+                },
+            },
+            Case {
+                title: "ExecuteCommand opens the variables popup for a task which requires variables",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(select_command_state_with_task())),
+                },
+                message: Some(Message::ExecuteCommand(task_command("deploy"))),
+                expect_model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        vars_popup_state: Some(VarsPopupState::new(
+                            vec!["ENV".to_string(), "REGION".to_string()],
+                            task_command("deploy"),
+                        )),
+                        ..select_command_state_with_task()
+                    })),
+                },
+            },
+            Case {
+                title: "NextVar",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        vars_popup_state: Some(VarsPopupState::new(
+                            vec!["ENV".to_string(), "REGION".to_string()],
+                            task_command("deploy"),
+                        )),
+                        ..select_command_state_with_task()
+                    })),
+                },
+                message: Some(Message::NextVar),
+                expect_model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        vars_popup_state: Some(VarsPopupState {
+                            selected: 1,
+                            ..VarsPopupState::new(vec!["ENV".to_string(), "REGION".to_string()], task_command("deploy"))
+                        }),
+                        ..select_command_state_with_task()
+                    })),
+                },
+            },
+            Case {
+                title: "PreviousVar(0 -> 1)",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        vars_popup_state: Some(VarsPopupState::new(
+                            vec!["ENV".to_string(), "REGION".to_string()],
+                            task_command("deploy"),
+                        )),
+                        ..select_command_state_with_task()
+                    })),
+                },
+                message: Some(Message::PreviousVar),
+                expect_model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        vars_popup_state: Some(VarsPopupState {
+                            selected: 1,
+                            ..VarsPopupState::new(vec!["ENV".to_string(), "REGION".to_string()], task_command("deploy"))
+                        }),
+                        ..select_command_state_with_task()
+                    })),
+                },
+            },
+            Case {
+                title: "VarsKeyInput",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        vars_popup_state: Some(VarsPopupState {
+                            selected: 1,
+                            ..VarsPopupState::new(vec!["ENV".to_string(), "REGION".to_string()], task_command("deploy"))
+                        }),
+                        ..select_command_state_with_task()
+                    })),
+                },
+                message: Some(Message::VarsKeyInput(KeyEvent::from(KeyCode::Char('u')))),
+                expect_model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        vars_popup_state: Some(VarsPopupState {
+                            vars: vec![
+                                ("ENV".to_string(), text_area("")),
+                                ("REGION".to_string(), text_area("u")),
+                            ],
+                            selected: 1,
+                            error: None,
+                            command: task_command("deploy"),
+                        }),
+                        ..select_command_state_with_task()
+                    })),
+                },
+            },
+            Case {
+                title: "SubmitVars",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        vars_popup_state: Some(VarsPopupState {
+                            vars: vec![
+                                ("ENV".to_string(), text_area("prod")),
+                                ("REGION".to_string(), text_area("us")),
+                            ],
+                            selected: 1,
+                            error: None,
+                            command: task_command("deploy"),
+                        }),
+                        ..select_command_state_with_task()
+                    })),
+                },
+                message: Some(Message::SubmitVars),
+                expect_model: Model {
+                    app_state: AppState::ExecuteCommand(ExecuteCommandState::new(
+                        runner::Runner::Task(Task::new_for_test()),
+                        task_command("deploy ENV=prod REGION=us"),
+                    )),
+                },
+            },
+            Case {
+                title: "SubmitVars with an empty variable",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        vars_popup_state: Some(VarsPopupState {
+                            vars: vec![
+                                ("ENV".to_string(), text_area("prod")),
+                                ("REGION".to_string(), text_area(" ")),
+                            ],
+                            selected: 0,
+                            error: None,
+                            command: task_command("deploy"),
+                        }),
+                        ..select_command_state_with_task()
+                    })),
+                },
+                message: Some(Message::SubmitVars),
+                expect_model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        vars_popup_state: Some(VarsPopupState {
+                            vars: vec![
+                                ("ENV".to_string(), text_area("prod")),
+                                ("REGION".to_string(), text_area(" ")),
+                            ],
+                            selected: 1,
+                            error: Some("REGION is required".to_string()),
+                            command: task_command("deploy"),
+                        }),
+                        ..select_command_state_with_task()
+                    })),
+                },
+            },
+            Case {
+                title: "CloseVarsWindow",
+                model: Model {
+                    app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                        vars_popup_state: Some(VarsPopupState::new(
+                            vec!["ENV".to_string(), "REGION".to_string()],
+                            task_command("deploy"),
+                        )),
+                        ..select_command_state_with_task()
+                    })),
+                },
+                message: Some(Message::CloseVarsWindow),
+                expect_model: Model {
+                    app_state: AppState::SelectCommand(Box::new(select_command_state_with_task())),
+                },
+            },
         ];
 
-        // NOTE: When running tests, you need to set FZF_MAKE_IS_TESTING=true. Otherwise, the developer's history file will be overwritten.
-        unsafe { env::set_var("FZF_MAKE_IS_TESTING", "true") };
-
         for mut case in cases {
             update(&mut case.model, case.message);
             assert_eq!(case.expect_model, case.model, "\nFailed: 🚨{:?}🚨\n", case.title,);
         }
     }
 
+    #[test]
+    fn narrow_down_commands_test() {
+        struct Case {
+            title: &'static str,
+            query: &'static str,
+            expected: Vec<&'static str>,
+        }
+        let cases = vec![
+            Case {
+                title: "empty query returns all commands",
+                query: "",
+                expected: vec!["build", "deploy"],
+            },
+            Case {
+                title: "commands can be searched by name",
+                query: "bui",
+                expected: vec!["build"],
+            },
+            Case {
+                title: "commands can be searched by alias",
+                query: "ship",
+                expected: vec!["deploy"],
+            },
+            Case {
+                title: "negation",
+                query: "!build",
+                expected: vec!["deploy"],
+            },
+            Case {
+                title: "runner filter and prefix",
+                query: "@task ^dep",
+                expected: vec!["deploy"],
+            },
+            Case {
+                title: "OR",
+                query: "^build | ^de",
+                expected: vec!["build", "deploy"],
+            },
+            Case {
+                title: "runner filter which does not match",
+                query: "@make",
+                expected: vec![],
+            },
+        ];
+
+        for case in cases {
+            let state = SelectCommandState {
+                search_text_area: text_area(case.query),
+                ..select_command_state_with_task()
+            };
+            let result: Vec<String> = state.narrow_down_commands().into_iter().map(|c| c.args).collect();
+            assert_eq!(case.expected, result, "\nFailed: 🚨{:?}🚨\n", case.title);
+        }
+    }
+
+    #[test]
+    fn narrow_down_commands_tie_breaker_test() {
+        struct Case {
+            title: &'static str,
+            history_ranking: histories::Ranking,
+            expected: Vec<&'static str>,
+        }
+        let cases = vec![
+            Case {
+                title: "frecency: the frequently used command is ranked higher",
+                history_ranking: histories::Ranking::Frecency,
+                expected: vec!["build", "deploy"],
+            },
+            Case {
+                title: "mru: the recently used command is ranked higher",
+                history_ranking: histories::Ranking::Mru,
+                expected: vec!["deploy", "build"],
+            },
+        ];
+
+        let now = histories::now();
+        let history_command = |args: &str, frecency: f64| histories::HistoryCommand {
+            execution: Some(histories::Execution {
+                frecency,
+                ..histories::Execution::new(now, None, Some(0))
+            }),
+            ..histories::HistoryCommand::new(runner_type::RunnerType::Task, vec![args.to_string()])
+        };
+        for case in cases {
+            let state = SelectCommandState {
+                // `task build` and `task deploy` have the same fuzzy score for this query.
+                search_text_area: text_area("task"),
+                history: vec![history_command("deploy", 1.0), history_command("build", 5.0)],
+                history_ranking: case.history_ranking,
+                ..select_command_state_with_task()
+            };
+            let result: Vec<String> = state.narrow_down_commands().into_iter().map(|c| c.args).collect();
+            assert_eq!(case.expected, result, "\nFailed: 🚨{:?}🚨\n", case.title);
+        }
+    }
+
+    #[test]
+    fn narrow_down_commands_with_project_config_test() {
+        struct Case {
+            title: &'static str,
+            project_config: &'static str,
+            query: &'static str,
+            expected: Vec<&'static str>,
+        }
+        let cases = vec![
+            Case {
+                title: "pinned commands are listed first",
+                project_config: r#"pin = ["task deploy"]"#,
+                query: "",
+                expected: vec!["deploy", "build"],
+            },
+            Case {
+                title: "pinned commands are listed first if they match the query",
+                project_config: r#"pin = ["task deploy"]"#,
+                query: "task",
+                expected: vec!["deploy", "build"],
+            },
+            Case {
+                title: "hidden commands are not listed",
+                project_config: r#"hide = ["task b*"]"#,
+                query: "",
+                expected: vec!["deploy"],
+            },
+            Case {
+                title: "commands can be searched by the name in the project config",
+                project_config: "[commands.\"task build\"]\nname = \"compile\"",
+                query: "compile",
+                expected: vec!["build"],
+            },
+        ];
+
+        for case in cases {
+            let state = SelectCommandState {
+                search_text_area: text_area(case.query),
+                project_config: project_config::ProjectConfig::new_for_test(case.project_config),
+                ..select_command_state_with_task()
+            };
+            let result: Vec<String> = state.narrow_down_commands().into_iter().map(|c| c.args).collect();
+            assert_eq!(case.expected, result, "\nFailed: 🚨{:?}🚨\n", case.title);
+        }
+    }
+
     // The manual PartialEq implementation compares errors by their string representation.
     // If it were wrong (e.g. always true), assert_eq! in other tests could pass incorrectly,
     // so its behavior is tested directly here.
@@ -1271,17 +2483,116 @@ mod test {
             },
         ];
 
-        unsafe { env::set_var("FZF_MAKE_IS_TESTING", "true") };
-
         for case in cases {
             assert_eq!(case.expect, case.left == case.right, "\nFailed: 🚨{:?}🚨\n", case.title,);
         }
     }
 
     #[test]
-    fn should_quit_test() {
-        unsafe { env::set_var("FZF_MAKE_IS_TESTING", "true") };
+    fn handle_key_input_test() {
+        use crossterm::event::KeyModifiers;
+
+        struct Case {
+            title: &'static str,
+            keymap: &'static str,
+            current_pane: CurrentPane,
+            key: KeyEvent,
+            expected: Option<Message>,
+        }
+        let key = |c: char| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        let cases = vec![
+            Case {
+                title: "<c-n> selects the next command in the main pane",
+                keymap: "",
+                current_pane: CurrentPane::Main,
+                key: KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL),
+                expected: Some(Message::NextCommand),
+            },
+            Case {
+                title: "unbound keys are input to the search box",
+                keymap: "preset = \"vim\"",
+                current_pane: CurrentPane::Main,
+                key: key('j'),
+                expected: Some(Message::SearchTextAreaKeyInput(key('j'))),
+            },
+            Case {
+                title: "j selects the next history with the vim preset",
+                keymap: "preset = \"vim\"",
+                current_pane: CurrentPane::History,
+                key: key('j'),
+                expected: Some(Message::NextHistory),
+            },
+            Case {
+                title: "unbound keys are ignored in the history pane",
+                keymap: "",
+                current_pane: CurrentPane::History,
+                key: key('j'),
+                expected: None,
+            },
+            Case {
+                title: "configured key",
+                keymap: "[history]\nquit = [\"x\"]",
+                current_pane: CurrentPane::History,
+                key: key('x'),
+                expected: Some(Message::Quit),
+            },
+            Case {
+                title: "the key replaced by the configured one is unbound",
+                keymap: "[history]\nquit = [\"x\"]",
+                current_pane: CurrentPane::History,
+                key: key('q'),
+                expected: None,
+            },
+        ];
 
+        for case in cases {
+            let model = Model {
+                app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                    current_pane: case.current_pane,
+                    keymap: ::toml::from_str(case.keymap).unwrap(),
+                    ..SelectCommandState::new_for_test()
+                })),
+            };
+            assert_eq!(case.expected, model.handle_key_input(case.key), "\nFailed: 🚨{:?}🚨\n", case.title);
+        }
+    }
+
+    #[test]
+    fn confirm_popup_submit_test() {
+        use crossterm::event::KeyModifiers;
+
+        let confirm_popup_opened = || Model {
+            app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                confirm_popup_state: Some(ConfirmPopupState {
+                    message: "Deploy to production?".to_string(),
+                    command: just_command("deploy"),
+                }),
+                ..select_command_state_with_just()
+            })),
+        };
+
+        // Submit(Enter) cancels the execution since the default answer is No.
+        let mut model = confirm_popup_opened();
+        let message = model.handle_key_input(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(Some(Message::CancelExecution), message);
+        update(&mut model, message);
+        assert_eq!(
+            Model {
+                app_state: AppState::SelectCommand(Box::new(select_command_state_with_just())),
+            },
+            model
+        );
+
+        // Only `y` executes the command.
+        let model = confirm_popup_opened();
+        assert_eq!(
+            Some(Message::ConfirmExecution),
+            model.handle_key_input(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE))
+        );
+    }
+
+    #[test]
+    fn should_quit_test() {
         // None => should not quit yet
         let model = Model {
             app_state: AppState::SelectCommand(Box::new(SelectCommandState::new_for_test())),
@@ -1303,4 +2614,239 @@ mod test {
             other => panic!("expected Some(Err(_)), got {:?}", other),
         }
     }
+
+    #[test]
+    fn receive_detected_runners_test() {
+        let mut state = SelectCommandState {
+            runners: vec![],
+            detecting_runners: Some(discovery::Detecting::new_for_test(vec![
+                (
+                    "task",
+                    discovery::Detected {
+                        runner: Some(runner::Runner::Task(Task::new_for_test())),
+                        warnings: vec![],
+                    },
+                ),
+                (
+                    "just",
+                    discovery::Detected {
+                        runner: None,
+                        warnings: vec!["Failed to load just: failed to parse justfile".to_string()],
+                    },
+                ),
+            ])),
+            ..SelectCommandState::new_for_test()
+        };
+
+        assert!(state.receive_detected_runners());
+        assert_eq!(
+            vec![runner_type::RunnerType::Task],
+            state
+                .runners
+                .iter()
+                .map(|r| runner_type::RunnerType::from(r.clone()))
+                .collect::<Vec<_>>()
+        );
+        assert!(
+            state
+                .warnings
+                .contains(&"Failed to load just: failed to parse justfile".to_string())
+        );
+        assert!(state.detecting_runners.is_none());
+        // Nothing happens after all runners are detected.
+        assert!(!state.receive_detected_runners());
+    }
+
+    #[tokio::test]
+    async fn wait_for_runners_test() {
+        let mut state = SelectCommandState {
+            runners: vec![],
+            detecting_runners: Some(discovery::Detecting::new_for_test(vec![(
+                "make",
+                discovery::Detected {
+                    runner: Some(runner::Runner::MakeCommand(Make::new_for_test())),
+                    warnings: vec![],
+                },
+            )])),
+            ..SelectCommandState::new_for_test()
+        };
+
+        state.wait_for_runners().await;
+        assert!(state.get_runner(&runner_type::RunnerType::Make).is_some());
+        assert!(state.detecting_runners.is_none());
+    }
+
+    #[test]
+    fn cached_commands_test() {
+        let cached = |runner_type: runner_type::RunnerType, args: &str| {
+            (
+                runner_type.clone(),
+                vec![command::CommandWithPreview::new(
+                    runner_type,
+                    args.to_string(),
+                    PathBuf::from(""),
+                    1,
+                )],
+            )
+        };
+        let mut state = SelectCommandState {
+            runners: vec![],
+            cached_commands: vec![
+                cached(runner_type::RunnerType::Just, "cached-recipe"),
+                cached(runner_type::RunnerType::Make, "cached-target"),
+            ],
+            detecting_runners: Some(discovery::Detecting::new_for_test(vec![(
+                "make",
+                discovery::Detected {
+                    runner: Some(runner::Runner::MakeCommand(Make::new_for_test())),
+                    warnings: vec![],
+                },
+            )])),
+            ..SelectCommandState::new_for_test()
+        };
+        let args = |state: &SelectCommandState| -> Vec<String> {
+            state
+                .narrow_down_commands_with_indices()
+                .iter()
+                .map(|(c, _)| c.args.clone())
+                .collect()
+        };
+
+        // The cached commands are listed in the default order of the runners until the runners are detected.
+        assert_eq!(vec!["cached-target", "cached-recipe"], args(&state));
+
+        // The cached commands are replaced with the ones of the detected runners, and the ones of the runners
+        // not found anymore are removed.
+        assert!(state.receive_detected_runners());
+        assert_eq!(vec!["target0", "target1", "target2"], args(&state));
+        assert!(state.cached_commands.is_empty());
+    }
+
+    #[test]
+    fn waiting_command_test() {
+        let command = command::CommandForExec {
+            runner_type: runner_type::RunnerType::Make,
+            args: vec!["target0".to_string()],
+        };
+        let mut model = Model {
+            app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                runners: vec![],
+                detecting_runners: Some(discovery::Detecting::new_for_test(vec![(
+                    "make",
+                    discovery::Detected {
+                        runner: Some(runner::Runner::MakeCommand(Make::new_for_test())),
+                        warnings: vec![],
+                    },
+                )])),
+                ..SelectCommandState::new_for_test()
+            })),
+        };
+
+        // The command is executed once its runner is detected.
+        update(&mut model, Some(Message::ExecuteCommand(command.clone())));
+        assert!(matches!(&model.app_state, AppState::SelectCommand(s) if s.waiting_command == Some(command.clone())));
+        model.receive_detected_runners();
+        assert_eq!(
+            AppState::ExecuteCommand(ExecuteCommandState::new(
+                runner::Runner::MakeCommand(Make::new_for_test()),
+                command
+            )),
+            model.app_state
+        );
+    }
+
+    #[tokio::test]
+    async fn switch_project_test() {
+        let current_dir = env::current_dir().unwrap();
+        let dir = current_dir.join("test_data/make");
+        let command = command::CommandForExec {
+            runner_type: runner_type::RunnerType::Make,
+            args: vec!["run".to_string()],
+        };
+        let mut model = Model {
+            app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                current_pane: CurrentPane::History,
+                history_view: HistoryView::Global,
+                ..SelectCommandState::new_for_test()
+            })),
+        };
+
+        // The command waits for the runners in `dir` detected in the background.
+        update(&mut model, Some(Message::ExecuteCommandInDir(dir.clone(), command.clone())));
+        match &model.app_state {
+            AppState::SelectCommand(s) => {
+                assert_eq!(Some(command.clone()), s.waiting_command);
+                assert_eq!(Some(dir.clone()), s.switched_dir);
+                assert!(s.runners.is_empty());
+            }
+            other => panic!("expected AppState::SelectCommand, got {:?}", other),
+        }
+        // The working directory of fzf-make itself is not changed.
+        assert_eq!(current_dir, env::current_dir().unwrap());
+
+        while model.is_detecting_runners() {
+            tokio::task::yield_now().await;
+            model.receive_detected_runners();
+        }
+        match model.command_to_execute() {
+            Some(state) => {
+                assert_eq!(command, state.command);
+                assert_eq!(Some(dir.clone()), state.dir);
+                assert_eq!(dir.join("Makefile"), state.executor.path());
+            }
+            None => panic!("expected the command to be executed, got {:?}", model.app_state),
+        }
+    }
+
+    #[test]
+    fn history_command_dir_test() {
+        let root = env::current_dir().unwrap();
+        let sub = root.join("src");
+        let scope = histories::Scope::RunnerRoot;
+        // The command is executed in the root of the project, which has Makefile.
+        let histories = histories::Histories { histories: vec![] }.append(
+            scope.key(&root),
+            histories::HistoryCommand {
+                dir: Some(root.clone()),
+                ..histories::HistoryCommand::new(runner_type::RunnerType::Make, vec!["run".to_string()])
+            },
+            histories::DEFAULT_MAX_LENGTH,
+        );
+        // The command is repeated in the subdirectory.
+        let state = SelectCommandState {
+            current_pane: CurrentPane::History,
+            history: Model::get_histories(&histories, scope.key(&sub)),
+            history_scope: scope,
+            switched_dir: Some(sub),
+            ..SelectCommandState::new_for_test()
+        };
+
+        assert_eq!(Some(root.clone()), state.selected_history_dir());
+        assert_eq!(root, state.history_command_dir(state.get_latest_command().unwrap()));
+    }
+
+    #[test]
+    fn receive_detected_runners_no_runner_found_test() {
+        let mut model = Model {
+            app_state: AppState::SelectCommand(Box::new(SelectCommandState {
+                runners: vec![],
+                history_view: HistoryView::Project,
+                detecting_runners: Some(discovery::Detecting::new_for_test(vec![(
+                    "make",
+                    discovery::Detected {
+                        runner: None,
+                        warnings: vec![],
+                    },
+                )])),
+                ..SelectCommandState::new_for_test()
+            })),
+        };
+        assert!(model.is_detecting_runners());
+
+        model.receive_detected_runners();
+        match model.should_quit() {
+            Some(Err(e)) => assert_eq!(NO_RUNNER_FOUND, e.to_string()),
+            other => panic!("expected Some(Err(_)), got {:?}", other),
+        }
+    }
 }
@@ -1,17 +1,605 @@
+use super::{keymap, theme};
+use crate::{
+    file::{app_dir, path_to_content, toml},
+    model::{histories, runner, runner_type},
+    usecase::usecase_main,
+};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    env, io,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+const EXECUTION_MODE_ENV_NAME: &str = "FZF_MAKE_EXECUTION_MODE";
+const HISTORY_RANKING_ENV_NAME: &str = "FZF_MAKE_HISTORY_RANKING";
+const HISTORY_SCOPE_ENV_NAME: &str = "FZF_MAKE_HISTORY_SCOPE";
+// https://no-color.org/
+const NO_COLOR_ENV_NAME: &str = "NO_COLOR";
+const THEMES_DIR_NAME: &str = "themes";
+
 pub struct Config {
     focus_history: bool,
+    // Show the commands executed in all directories in the history pane.
+    global_history: bool,
+    check_update: bool,
+    execution_mode: ExecutionMode,
+    runners: RunnersConfig,
+    history: HistoryConfig,
+    preview: PreviewConfig,
+    keymap: keymap::Keymap,
+    theme: theme::ThemeConfig,
+    // The colors are disabled if NO_COLOR is set to a non-empty value.
+    no_color: bool,
+    // The directory where the `*.tmTheme` files are searched(`$XDG_CONFIG_HOME/fzf-make/themes`).
+    themes_dir: Option<PathBuf>,
+    // The history file specified by the command line option. See toml::history_file_path for the default.
+    history_file: Option<PathBuf>,
+    // The directory where the commands of the runners are cached(`$XDG_CACHE_HOME/fzf-make`).
+    // It is None if the cache is disabled by `--no-cache`.
+    cache_dir: Option<PathBuf>,
 }
 
 impl Config {
-    pub fn new(focus_history: bool) -> Self {
-        Self { focus_history }
+    // load reads the config file(`$XDG_CONFIG_HOME/fzf-make/config.toml`) and the environment variables.
+    // The environment variables take precedence over the config file.
+    pub fn load(options: usecase_main::Options) -> Result<Self> {
+        let app_dir = app_dir::AppDir::from_env();
+        let file = match &app_dir {
+            Some(app_dir) => read_config_file(&app_dir.config.join(CONFIG_FILE_NAME))?,
+            None => ConfigFile::default(),
+        };
+        Ok(Self {
+            cache_dir: app_dir
+                .as_ref()
+                .filter(|_| !options.no_cache)
+                .map(|app_dir| app_dir.cache.clone()),
+            themes_dir: app_dir.map(|app_dir| app_dir.config.join(THEMES_DIR_NAME)),
+            history_file: options.history_file,
+            ..Self::new(file, |key| env::var(key).ok())
+        })
+    }
+
+    fn new(file: ConfigFile, var: impl Fn(&str) -> Option<String>) -> Self {
+        Self {
+            focus_history: file.default_pane == Pane::History,
+            global_history: false,
+            check_update: file.check_update,
+            // `spawn` or `exec`
+            execution_mode: var(EXECUTION_MODE_ENV_NAME)
+                .and_then(|v| ExecutionMode::parse(&v))
+                .unwrap_or(file.execution_mode),
+            runners: file.runners,
+            history: HistoryConfig {
+                // `mru`, `frecency` or `off`
+                ranking: var(HISTORY_RANKING_ENV_NAME)
+                    .and_then(|v| histories::Ranking::parse(&v))
+                    .unwrap_or(file.history.ranking),
+                // `exact`, `git-root` or `runner-root`
+                scope: var(HISTORY_SCOPE_ENV_NAME)
+                    .and_then(|v| histories::Scope::parse(&v))
+                    .unwrap_or(file.history.scope),
+                ..file.history
+            },
+            preview: file.preview,
+            keymap: file.keymap,
+            theme: file.theme,
+            no_color: var(NO_COLOR_ENV_NAME).is_some_and(|v| !v.is_empty()),
+            themes_dir: None,
+            history_file: None,
+            cache_dir: None,
+        }
+    }
+
+    // focus_history returns the config to launch fzf-make with the history pane focused.
+    pub fn focus_history(self) -> Self {
+        Self {
+            focus_history: true,
+            ..self
+        }
     }
 
-    pub fn default() -> Self {
-        Self { focus_history: false }
+    // global_history returns the config to launch fzf-make with the global history focused.
+    pub fn global_history(self) -> Self {
+        Self {
+            global_history: true,
+            ..self.focus_history()
+        }
     }
 
     pub fn get_focus_history(&self) -> bool {
         self.focus_history
     }
+
+    pub fn get_global_history(&self) -> bool {
+        self.global_history
+    }
+
+    pub fn get_check_update(&self) -> bool {
+        self.check_update
+    }
+
+    pub fn get_execution_mode(&self) -> ExecutionMode {
+        self.execution_mode
+    }
+
+    pub fn get_runners(&self) -> RunnersConfig {
+        self.runners.clone()
+    }
+
+    pub fn get_history_size(&self) -> usize {
+        self.history.size.get()
+    }
+
+    pub fn get_history_ranking(&self) -> histories::Ranking {
+        self.history.ranking
+    }
+
+    pub fn get_history_scope(&self) -> histories::Scope {
+        self.history.scope
+    }
+
+    pub fn get_preview(&self) -> PreviewConfig {
+        self.preview
+    }
+
+    pub fn get_keymap(&self) -> keymap::Keymap {
+        self.keymap.clone()
+    }
+
+    pub fn get_theme(&self) -> Result<theme::Theme> {
+        theme::Theme::new(&self.theme, self.no_color, self.themes_dir.as_deref())
+            .context("failed to load the theme. Check `[theme]` of the config file")
+    }
+
+    pub fn get_cache_dir(&self) -> Option<PathBuf> {
+        self.cache_dir.clone()
+    }
+
+    pub fn get_history_file(&self) -> Result<PathBuf> {
+        toml::history_file_path(self.history_file.clone())
+    }
+}
+
+// read_config_file reads the config file. The default config is used if it does not exist.
+fn read_config_file(path: &Path) -> Result<ConfigFile> {
+    match path_to_content::path_to_content(path) {
+        // The error of toml contains the line and the column where the error occurred.
+        Ok(content) => ::toml::from_str(&content).with_context(|| format!("failed to parse {}", path.display())),
+        Err(e) if e.downcast_ref::<io::Error>().map(|e| e.kind()) == Some(io::ErrorKind::NotFound) => {
+            Ok(ConfigFile::default())
+        }
+        Err(e) => Err(e).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+/// ConfigFile is the schema of the config file. The default value is used for each missing key.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct ConfigFile {
+    /// The pane focused on launch.
+    default_pane: Pane,
+    /// Check if a new version of fzf-make is released.
+    check_update: bool,
+    execution_mode: ExecutionMode,
+    runners: RunnersConfig,
+    history: HistoryConfig,
+    preview: PreviewConfig,
+    keymap: keymap::Keymap,
+    theme: theme::ThemeConfig,
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        Self {
+            default_pane: Pane::default(),
+            check_update: true,
+            execution_mode: ExecutionMode::default(),
+            runners: RunnersConfig::default(),
+            history: HistoryConfig::default(),
+            preview: PreviewConfig::default(),
+            keymap: keymap::Keymap::default(),
+            theme: theme::ThemeConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Pane {
+    #[default]
+    Commands,
+    History,
+}
+
+/// RunnersConfig decides which runners are used and in which order their commands are listed.
+#[derive(Clone, Debug, PartialEq, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RunnersConfig {
+    /// The runners to be used. All runners are used if it is not specified.
+    enabled: Option<Vec<runner_type::RunnerType>>,
+    /// The runners not to be used.
+    disabled: Vec<runner_type::RunnerType>,
+    /// The runners listed first. The others follow them in the default order.
+    priority: Vec<runner_type::RunnerType>,
+}
+
+impl RunnersConfig {
+    // apply removes the runners not to be used and sorts the rest by the priority.
+    pub fn apply(&self, runners: Vec<runner::Runner>) -> Vec<runner::Runner> {
+        let mut runners: Vec<runner::Runner> = runners
+            .into_iter()
+            .filter(|r| self.is_enabled(&runner_type::RunnerType::from(r.clone())))
+            .collect();
+        runners.sort_by_key(|r| self.priority(&runner_type::RunnerType::from(r.clone())));
+        runners
+    }
+
+    pub fn is_enabled(&self, runner_type: &runner_type::RunnerType) -> bool {
+        self.enabled
+            .as_ref()
+            .is_none_or(|enabled| enabled.contains(runner_type))
+            && !self.disabled.contains(runner_type)
+    }
+
+    // priority returns the position of the runner in `priority`. The runners not in it follow the others.
+    pub fn priority(&self, runner_type: &runner_type::RunnerType) -> usize {
+        self.priority
+            .iter()
+            .position(|p| p == runner_type)
+            .unwrap_or(self.priority.len())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct HistoryConfig {
+    /// The max number of the commands stored for each directory.
+    size: NonZeroUsize,
+    ranking: histories::Ranking,
+    scope: histories::Scope,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            size: NonZeroUsize::new(histories::DEFAULT_MAX_LENGTH).unwrap(),
+            ranking: histories::Ranking::default(),
+            scope: histories::Scope::default(),
+        }
+    }
+}
+
+/// PreviewConfig decides when the preview window is shown.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct PreviewConfig {
+    pub enabled: bool,
+    /// The preview window is hidden when the height of the terminal is less than this.
+    pub min_height: u16,
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_height: 20,
+        }
+    }
+}
+
+/// ExecutionMode decides how the selected command is executed.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExecutionMode {
+    /// Spawn the command and wait for it. fzf-make can do something after the command exits.
+    #[default]
+    Spawn,
+    /// Replace the fzf-make process with the command. Signals and job control are handled by the command directly.
+    /// This is available only on Unix. On other platforms, the command is spawned.
+    Exec,
+}
+
+impl ExecutionMode {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "spawn" => Some(Self::Spawn),
+            "exec" => Some(Self::Exec),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn config_file_parse_test() {
+        struct Case {
+            title: &'static str,
+            content: &'static str,
+            expected: Result<ConfigFile, &'static str>,
+        }
+        let cases = vec![
+            Case {
+                title: "empty file",
+                content: "",
+                expected: Ok(ConfigFile::default()),
+            },
+            Case {
+                title: "all keys",
+                content: r#"
+default-pane = "history"
+check-update = false
+execution-mode = "exec"
+
+[runners]
+enabled = ["make", "just", "task"]
+disabled = ["task"]
+priority = ["just"]
+
+[history]
+size = 100
+ranking = "mru"
+scope = "git-root"
+
+[preview]
+enabled = false
+min-height = 30
+
+[keymap]
+preset = "vim"
+
+[theme]
+preset = "light"
+"#,
+                expected: Ok(ConfigFile {
+                    default_pane: Pane::History,
+                    check_update: false,
+                    execution_mode: ExecutionMode::Exec,
+                    runners: RunnersConfig {
+                        enabled: Some(vec![
+                            runner_type::RunnerType::Make,
+                            runner_type::RunnerType::Just,
+                            runner_type::RunnerType::Task,
+                        ]),
+                        disabled: vec![runner_type::RunnerType::Task],
+                        priority: vec![runner_type::RunnerType::Just],
+                    },
+                    history: HistoryConfig {
+                        size: NonZeroUsize::new(100).unwrap(),
+                        ranking: histories::Ranking::Mru,
+                        scope: histories::Scope::GitRoot,
+                    },
+                    preview: PreviewConfig {
+                        enabled: false,
+                        min_height: 30,
+                    },
+                    keymap: ::toml::from_str("preset = \"vim\"").unwrap(),
+                    theme: ::toml::from_str("preset = \"light\"").unwrap(),
+                }),
+            },
+            Case {
+                title: "unknown key",
+                content: "[history]\nsize = 10\nmax = 10\n",
+                expected: Err(
+                    "TOML parse error at line 3, column 1\n  |\n3 | max = 10\n  | ^^^\nunknown field `max`, expected one of `size`, `ranking`, `scope`\n",
+                ),
+            },
+            Case {
+                title: "unknown runner",
+                content: "[runners]\ndisabled = [\"make\", \"cargo\"]\n",
+                expected: Err(
+                    "TOML parse error at line 2, column 12\n  |\n2 | disabled = [\"make\", \"cargo\"]\n  |            ^^^^^^^^^^^^^^^^^\nUnknown runner type: cargo\n",
+                ),
+            },
+            Case {
+                title: "history size must not be zero",
+                content: "[history]\nsize = 0\n",
+                expected: Err(
+                    "TOML parse error at line 2, column 8\n  |\n2 | size = 0\n  |        ^\ninvalid value: integer `0`, expected a nonzero usize\n",
+                ),
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(
+                case.expected.map_err(|e| e.to_string()),
+                ::toml::from_str::<ConfigFile>(case.content).map_err(|e| e.to_string()),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn config_new_test() {
+        struct Case {
+            title: &'static str,
+            file: ConfigFile,
+            vars: Vec<(&'static str, &'static str)>,
+            expected_execution_mode: ExecutionMode,
+            expected_history_ranking: histories::Ranking,
+            expected_history_scope: histories::Scope,
+        }
+        let file = ConfigFile {
+            execution_mode: ExecutionMode::Exec,
+            history: HistoryConfig {
+                ranking: histories::Ranking::Off,
+                scope: histories::Scope::RunnerRoot,
+                ..HistoryConfig::default()
+            },
+            ..ConfigFile::default()
+        };
+        let cases = vec![
+            Case {
+                title: "the config file is used",
+                file: file.clone(),
+                vars: vec![],
+                expected_execution_mode: ExecutionMode::Exec,
+                expected_history_ranking: histories::Ranking::Off,
+                expected_history_scope: histories::Scope::RunnerRoot,
+            },
+            Case {
+                title: "the environment variables take precedence",
+                file: file.clone(),
+                vars: vec![
+                    (EXECUTION_MODE_ENV_NAME, "spawn"),
+                    (HISTORY_RANKING_ENV_NAME, "mru"),
+                    (HISTORY_SCOPE_ENV_NAME, "git-root"),
+                ],
+                expected_execution_mode: ExecutionMode::Spawn,
+                expected_history_ranking: histories::Ranking::Mru,
+                expected_history_scope: histories::Scope::GitRoot,
+            },
+            Case {
+                title: "invalid environment variables are ignored",
+                file: file.clone(),
+                vars: vec![(EXECUTION_MODE_ENV_NAME, "fork")],
+                expected_execution_mode: ExecutionMode::Exec,
+                expected_history_ranking: histories::Ranking::Off,
+                expected_history_scope: histories::Scope::RunnerRoot,
+            },
+        ];
+
+        for case in cases {
+            let config =
+                Config::new(case.file, |key| case.vars.iter().find(|(k, _)| *k == key).map(|(_, v)| v.to_string()));
+            assert_eq!(
+                (case.expected_execution_mode, case.expected_history_ranking, case.expected_history_scope),
+                (config.get_execution_mode(), config.get_history_ranking(), config.get_history_scope()),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn no_color_test() {
+        struct Case {
+            title: &'static str,
+            no_color: Option<&'static str>,
+            expected: bool,
+        }
+        let cases = vec![
+            Case {
+                title: "not set",
+                no_color: None,
+                expected: false,
+            },
+            Case {
+                title: "empty",
+                no_color: Some(""),
+                expected: false,
+            },
+            Case {
+                title: "set",
+                no_color: Some("1"),
+                expected: true,
+            },
+        ];
+
+        for case in cases {
+            let config = Config::new(ConfigFile::default(), |key| {
+                (key == NO_COLOR_ENV_NAME)
+                    .then_some(case.no_color)
+                    .flatten()
+                    .map(|v| v.to_string())
+            });
+            assert_eq!(case.expected, config.no_color, "\nFailed: 🚨{:?}🚨\n", case.title);
+        }
+    }
+
+    #[test]
+    fn runners_config_apply_test() {
+        use crate::model::{make::make_main::Make, task::task_main::Task};
+
+        struct Case {
+            title: &'static str,
+            runners_config: RunnersConfig,
+            expected: Vec<runner_type::RunnerType>,
+        }
+        let cases = vec![
+            Case {
+                title: "all runners are used by default",
+                runners_config: RunnersConfig::default(),
+                expected: vec![runner_type::RunnerType::Make, runner_type::RunnerType::Task],
+            },
+            Case {
+                title: "only enabled runners are used",
+                runners_config: RunnersConfig {
+                    enabled: Some(vec![runner_type::RunnerType::Task, runner_type::RunnerType::Just]),
+                    ..RunnersConfig::default()
+                },
+                expected: vec![runner_type::RunnerType::Task],
+            },
+            Case {
+                title: "disabled runners are not used",
+                runners_config: RunnersConfig {
+                    disabled: vec![runner_type::RunnerType::Make],
+                    ..RunnersConfig::default()
+                },
+                expected: vec![runner_type::RunnerType::Task],
+            },
+            Case {
+                title: "runners are sorted by priority",
+                runners_config: RunnersConfig {
+                    priority: vec![runner_type::RunnerType::Task],
+                    ..RunnersConfig::default()
+                },
+                expected: vec![runner_type::RunnerType::Task, runner_type::RunnerType::Make],
+            },
+        ];
+
+        for case in cases {
+            let runners = vec![
+                runner::Runner::MakeCommand(Make::new_for_test()),
+                runner::Runner::Task(Task::new_for_test()),
+            ];
+            assert_eq!(
+                case.expected,
+                case.runners_config
+                    .apply(runners)
+                    .into_iter()
+                    .map(runner_type::RunnerType::from)
+                    .collect::<Vec<_>>(),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn execution_mode_parse_test() {
+        struct Case {
+            title: &'static str,
+            value: &'static str,
+            expected: Option<ExecutionMode>,
+        }
+        let cases = vec![
+            Case {
+                title: "spawn",
+                value: "spawn",
+                expected: Some(ExecutionMode::Spawn),
+            },
+            Case {
+                title: "exec(case insensitive)",
+                value: "Exec",
+                expected: Some(ExecutionMode::Exec),
+            },
+            Case {
+                title: "unknown mode",
+                value: "fork",
+                expected: None,
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(case.expected, ExecutionMode::parse(case.value), "\nFailed: 🚨{:?}🚨\n", case.title,);
+        }
+    }
 }
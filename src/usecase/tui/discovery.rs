@@ -0,0 +1,283 @@
+use super::config;
+use crate::model::{
+    js_package_manager::js_package_manager_main as js,
+    just::just_main::Just,
+    make::make_main::Make,
+    runner::{self, Runner},
+    runner_type,
+    task::task_main::Task,
+};
+use anyhow::Result;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+
+/// Detected is the result of detecting a runner.
+#[derive(Debug)]
+pub struct Detected {
+    pub runner: Option<runner::Runner>,
+    // The errors(e.g. a broken justfile) and the warnings(e.g. conflicting lockfiles) shown in the notification area.
+    pub warnings: Vec<String>,
+}
+
+impl Detected {
+    // enabled drops the runner disabled by `[runners]` of the config with its warnings.
+    fn enabled(self, runners_config: &config::RunnersConfig) -> Self {
+        match &self.runner {
+            Some(r) if !runners_config.is_enabled(&runner_type::RunnerType::from(r.clone())) => Self {
+                runner: None,
+                warnings: vec![],
+            },
+            _ => self,
+        }
+    }
+}
+
+type Detector = fn(PathBuf) -> Detected;
+
+// (name, the types of the runners which can be detected, detector) in the default order of the runners.
+const DETECTORS: [(&str, &[runner_type::RunnerType], Detector); 4] = [
+    ("make", &[runner_type::RunnerType::Make], detect_make),
+    (
+        "package.json",
+        &[
+            runner_type::RunnerType::JsPackageManager(runner_type::JsPackageManager::Npm),
+            runner_type::RunnerType::JsPackageManager(runner_type::JsPackageManager::Pnpm),
+            runner_type::RunnerType::JsPackageManager(runner_type::JsPackageManager::Yarn),
+        ],
+        detect_js_package_manager,
+    ),
+    ("just", &[runner_type::RunnerType::Just], detect_just),
+    ("task", &[runner_type::RunnerType::Task], detect_task),
+];
+
+// enabled_detectors returns the detectors which can detect the runners enabled by `runners_config`.
+// The others are not run since some of them spawn external processes(e.g. `task --list-all --json`).
+fn enabled_detectors(runners_config: &config::RunnersConfig) -> impl Iterator<Item = (&'static str, Detector)> {
+    DETECTORS
+        .into_iter()
+        .filter(|(_, runner_types, _)| runner_types.iter().any(|r| runners_config.is_enabled(r)))
+        .map(|(name, _, detector)| (name, detector))
+}
+
+/// Detecting is the detection of the runners running concurrently in the background.
+/// Some runners take a while to be detected since they spawn external processes(e.g. `task --list-all --json`).
+#[derive(Debug)]
+pub struct Detecting {
+    receiver: mpsc::UnboundedReceiver<(&'static str, Detected)>,
+    // The names of the runners not detected yet.
+    pending: Vec<&'static str>,
+}
+
+impl Detecting {
+    // start detects the runners enabled by `runners_config` in `current_dir` concurrently on the tokio runtime.
+    pub fn start(current_dir: PathBuf, runners_config: &config::RunnersConfig) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut pending = vec![];
+        for (name, detector) in enabled_detectors(runners_config) {
+            let sender = sender.clone();
+            let current_dir = current_dir.clone();
+            let runners_config = runners_config.clone();
+            tokio::task::spawn_blocking(move || {
+                // The receiver is dropped if fzf-make quits before the detection finishes.
+                let _ = sender.send((name, detector(current_dir).enabled(&runners_config)));
+            });
+            pending.push(name);
+        }
+        Self { receiver, pending }
+    }
+
+    // receive returns the runners detected since the last call in the order of completion.
+    pub fn receive(&mut self) -> Vec<Detected> {
+        let mut result = vec![];
+        loop {
+            match self.receiver.try_recv() {
+                Ok((name, detected)) => {
+                    self.pending.retain(|pending| *pending != name);
+                    result.push(detected);
+                }
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                // All detections have finished(or panicked).
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    self.pending.clear();
+                    break;
+                }
+            }
+        }
+        result
+    }
+
+    // wait returns the runners not received yet after all of them are detected.
+    pub async fn wait(&mut self) -> Vec<Detected> {
+        let mut result = vec![];
+        // recv returns None once all detections have finished(or panicked) and the senders are dropped.
+        while let Some((_, detected)) = self.receiver.recv().await {
+            result.push(detected);
+        }
+        self.pending.clear();
+        result
+    }
+
+    pub fn pending(&self) -> &[&'static str] {
+        &self.pending
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    #[cfg(test)]
+    pub fn new_for_test(detected: Vec<(&'static str, Detected)>) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let pending = detected.iter().map(|(name, _)| *name).collect();
+        for d in detected {
+            sender.send(d).unwrap();
+        }
+        Self { receiver, pending }
+    }
+}
+
+// default_order returns the position of the runner in the default order. The runners are sorted by it since they
+// are detected in the order of completion.
+pub fn default_order(runner_type: &runner_type::RunnerType) -> usize {
+    match runner_type {
+        runner_type::RunnerType::Make => 0,
+        runner_type::RunnerType::JsPackageManager(_) => 1,
+        runner_type::RunnerType::Just => 2,
+        runner_type::RunnerType::Task => 3,
+        runner_type::RunnerType::Custom => 4,
+    }
+}
+
+// sort sorts the runners in the default order.
+pub fn sort(runners: &mut [runner::Runner]) {
+    runners.sort_by_key(|r| default_order(&runner_type::RunnerType::from(r.clone())));
+}
+
+fn detect_make(current_dir: PathBuf) -> Detected {
+    detected("make", Make::find(current_dir).map(|m| m.map(Runner::MakeCommand)))
+}
+
+fn detect_js_package_manager(current_dir: PathBuf) -> Detected {
+    let (js_package_manager, warnings) = js::get_js_package_manager_runner(current_dir);
+    Detected {
+        runner: js_package_manager.map(Runner::JsPackageManager),
+        warnings,
+    }
+}
+
+fn detect_just(current_dir: PathBuf) -> Detected {
+    detected("just", Just::find(current_dir).map(|j| j.map(Runner::Just)))
+}
+
+fn detect_task(current_dir: PathBuf) -> Detected {
+    detected("task", Task::find(current_dir).map(|t| t.map(Runner::Task)))
+}
+
+// detected converts the result of finding the runner. The error is shown as a warning not to prevent
+// the other runners from being used.
+fn detected(name: &str, result: Result<Option<runner::Runner>>) -> Detected {
+    match result {
+        Ok(runner) => Detected {
+            runner,
+            warnings: vec![],
+        },
+        Err(e) => Detected {
+            runner: None,
+            warnings: vec![format!("Failed to load {}: {:#}", name, e)],
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::env;
+
+    fn runner_types(detected: Vec<Detected>) -> Vec<runner_type::RunnerType> {
+        let mut runners: Vec<runner::Runner> = detected.into_iter().filter_map(|d| d.runner).collect();
+        sort(&mut runners);
+        runners.into_iter().map(runner_type::RunnerType::from).collect()
+    }
+
+    #[tokio::test]
+    async fn detecting_test() {
+        let current_dir = env::current_dir().unwrap();
+        let runners_config = config::RunnersConfig::default();
+        let mut detecting = Detecting::start(current_dir.clone(), &runners_config);
+        let detected = detecting.wait().await;
+
+        assert!(detecting.is_done(), "pending: {:?}", detecting.pending());
+        // The same runners as the ones detected one by one are detected.
+        let expected = enabled_detectors(&runners_config)
+            .map(|(_, detector)| detector(current_dir.clone()).enabled(&runners_config))
+            .collect();
+        assert_eq!(runner_types(expected), runner_types(detected));
+    }
+
+    #[test]
+    fn enabled_detectors_test() {
+        struct Case {
+            title: &'static str,
+            runners_config: &'static str,
+            expected: Vec<&'static str>,
+        }
+        let cases = vec![
+            Case {
+                title: "all detectors are run by default",
+                runners_config: "",
+                expected: vec!["make", "package.json", "just", "task"],
+            },
+            Case {
+                title: "disabled runners are not detected",
+                runners_config: r#"disabled = ["make", "task"]"#,
+                expected: vec!["package.json", "just"],
+            },
+            Case {
+                title: "package.json is detected while any of the package managers is enabled",
+                runners_config: r#"enabled = ["just", "yarn"]"#,
+                expected: vec!["package.json", "just"],
+            },
+            Case {
+                title: "package.json is not detected if all of the package managers are disabled",
+                runners_config: r#"disabled = ["npm", "pnpm", "yarn"]"#,
+                expected: vec!["make", "just", "task"],
+            },
+        ];
+
+        for case in cases {
+            let runners_config: config::RunnersConfig = ::toml::from_str(case.runners_config).unwrap();
+            assert_eq!(
+                case.expected,
+                enabled_detectors(&runners_config)
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn detected_enabled_test() {
+        let runners_config: config::RunnersConfig = ::toml::from_str(r#"disabled = ["make"]"#).unwrap();
+        let detected = |runner: Runner| Detected {
+            runner: Some(runner),
+            warnings: vec!["warning".to_string()],
+        };
+
+        // The disabled runner is dropped with its warnings.
+        let disabled = detected(Runner::MakeCommand(Make::new_for_test())).enabled(&runners_config);
+        assert_eq!((None, vec![]), (disabled.runner, disabled.warnings));
+        let enabled = detected(Runner::Task(Task::new_for_test())).enabled(&runners_config);
+        assert_eq!(vec!["warning".to_string()], enabled.warnings);
+        assert!(enabled.runner.is_some());
+    }
+
+    #[test]
+    fn detected_test() {
+        let detected = detected("just", Err(anyhow::anyhow!("failed to parse justfile")));
+        assert_eq!(None, detected.runner);
+        assert_eq!(vec!["Failed to load just: failed to parse justfile".to_string()], detected.warnings);
+    }
+}
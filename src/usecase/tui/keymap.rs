@@ -0,0 +1,654 @@
+use anyhow::{Result, anyhow, bail};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt};
+
+/// Keymap maps the key chords to the actions in each context.
+/// It is configured in `[keymap]` of the config file. The bindings of the preset are used for unconfigured actions.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "KeymapToml")]
+pub struct Keymap {
+    pub main: Bindings,
+    pub history: Bindings,
+    // The additional arguments popup, the variables popup and the confirm popup.
+    pub popup: Bindings,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Preset::default().keymap()
+    }
+}
+
+/// toml representation of Keymap.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct KeymapToml {
+    preset: Preset,
+    main: BTreeMap<Action, Vec<KeyChord>>,
+    history: BTreeMap<Action, Vec<KeyChord>>,
+    popup: BTreeMap<Action, Vec<KeyChord>>,
+}
+
+impl TryFrom<KeymapToml> for Keymap {
+    type Error = anyhow::Error;
+
+    fn try_from(toml: KeymapToml) -> Result<Self> {
+        let preset = toml.preset.keymap();
+        Ok(Self {
+            main: preset.main.configure(Context::Main, toml.main)?,
+            history: preset.history.configure(Context::History, toml.history)?,
+            popup: preset.popup.configure(Context::Popup, toml.popup)?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Preset {
+    #[default]
+    Default,
+    // j/k and <c-j>/<c-k> to select commands.
+    Vim,
+    // <c-g> to quit or close the popup.
+    Emacs,
+}
+
+impl Preset {
+    fn keymap(&self) -> Keymap {
+        use Action::*;
+
+        let main = vec![
+            (Execute, vec!["<enter>"]),
+            (Previous, vec!["<up>", "<c-p>"]),
+            (Next, vec!["<down>", "<c-n>"]),
+            (Quit, vec!["<c-c>", "<esc>"]),
+            (NextPane, vec!["<tab>"]),
+            (CopyCommand, vec!["<c-y>"]),
+            (AdditionalArguments, vec!["<c-o>"]),
+        ];
+        let history = vec![
+            (Execute, vec!["<enter>", "<space>"]),
+            (Previous, vec!["<up>", "<c-p>"]),
+            (Next, vec!["<down>", "<c-n>"]),
+            (Quit, vec!["<c-c>", "q", "<esc>"]),
+            (NextPane, vec!["<tab>"]),
+            (CopyCommand, vec!["<c-y>"]),
+            (AdditionalArguments, vec!["<c-o>"]),
+            (ToggleHistoryView, vec!["<c-g>"]),
+        ];
+        let popup = vec![
+            (Submit, vec!["<enter>"]),
+            (Next, vec!["<tab>", "<down>"]),
+            (Previous, vec!["<s-tab>", "<up>"]),
+            (Close, vec!["<esc>"]),
+        ];
+
+        let (main, history, popup) = match self {
+            Preset::Default => (main, history, popup),
+            Preset::Vim => (
+                with_keys(main, &[(Previous, &["<c-k>"]), (Next, &["<c-j>"])]),
+                with_keys(history, &[(Previous, &["k", "<c-k>"]), (Next, &["j", "<c-j>"])]),
+                with_keys(popup, &[(Next, &["<c-j>"]), (Previous, &["<c-k>"])]),
+            ),
+            Preset::Emacs => {
+                (with_keys(main, &[(Quit, &["<c-g>"])]), history, with_keys(popup, &[(Close, &["<c-g>"])]))
+            }
+        };
+        Keymap {
+            main: Bindings::from_preset(main),
+            history: Bindings::from_preset(history),
+            popup: Bindings::from_preset(popup),
+        }
+    }
+}
+
+// with_keys adds the keys to the actions of the preset.
+fn with_keys(
+    bindings: Vec<(Action, Vec<&'static str>)>,
+    keys: &[(Action, &[&'static str])],
+) -> Vec<(Action, Vec<&'static str>)> {
+    bindings
+        .into_iter()
+        .map(|(action, mut action_keys)| {
+            for (_, k) in keys.iter().filter(|(a, _)| *a == action) {
+                action_keys.extend_from_slice(k);
+            }
+            (action, action_keys)
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Context {
+    Main,
+    History,
+    Popup,
+}
+
+impl Context {
+    fn actions(&self) -> &'static [Action] {
+        use Action::*;
+
+        match self {
+            Context::Main => &[
+                Execute,
+                Previous,
+                Next,
+                Quit,
+                NextPane,
+                CopyCommand,
+                AdditionalArguments,
+            ],
+            Context::History => &[
+                Execute,
+                Previous,
+                Next,
+                Quit,
+                NextPane,
+                CopyCommand,
+                AdditionalArguments,
+                ToggleHistoryView,
+            ],
+            Context::Popup => &[Submit, Next, Previous, Close],
+        }
+    }
+
+    // accepts_text returns true if the characters typed in this context are input to the text area.
+    fn accepts_text(&self) -> bool {
+        match self {
+            Context::Main | Context::Popup => true,
+            Context::History => false,
+        }
+    }
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Context::Main => write!(f, "keymap.main"),
+            Context::History => write!(f, "keymap.history"),
+            Context::Popup => write!(f, "keymap.popup"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Execute,
+    Previous,
+    Next,
+    Quit,
+    NextPane,
+    CopyCommand,
+    AdditionalArguments,
+    ToggleHistoryView,
+    // Popup only
+    Submit,
+    Close,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Action::Execute => "execute",
+            Action::Previous => "previous",
+            Action::Next => "next",
+            Action::Quit => "quit",
+            Action::NextPane => "next-pane",
+            Action::CopyCommand => "copy-command",
+            Action::AdditionalArguments => "additional-arguments",
+            Action::ToggleHistoryView => "toggle-history-view",
+            Action::Submit => "submit",
+            Action::Close => "close",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Bindings is the list of the actions and their keys in a context.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bindings(Vec<(Action, Vec<KeyChord>)>);
+
+impl Bindings {
+    fn from_preset(bindings: Vec<(Action, Vec<&'static str>)>) -> Self {
+        Self(
+            bindings
+                .into_iter()
+                .map(|(action, keys)| {
+                    let keys = keys
+                        .into_iter()
+                        .map(|k| KeyChord::parse(k).expect("the keys of the presets must be valid"))
+                        .collect();
+                    (action, keys)
+                })
+                .collect(),
+        )
+    }
+
+    // configure replaces the keys of the actions with the configured ones and validates them.
+    fn configure(self, context: Context, configured: BTreeMap<Action, Vec<KeyChord>>) -> Result<Self> {
+        if let Some(action) = configured.keys().find(|a| !context.actions().contains(a)) {
+            bail!("`{}` is not available in {}", action, context);
+        }
+
+        let bindings = Self(
+            self.0
+                .into_iter()
+                .map(|(action, keys)| (action, configured.get(&action).cloned().unwrap_or(keys)))
+                .collect(),
+        );
+        bindings.validate(context)?;
+        Ok(bindings)
+    }
+
+    // validate returns an error if a key is bound to multiple actions or used to type characters.
+    fn validate(&self, context: Context) -> Result<()> {
+        let mut bound: Vec<(KeyChord, Action)> = vec![];
+        for (action, keys) in &self.0 {
+            for key in keys {
+                if context.accepts_text() && key.is_text() {
+                    bail!("`{}` of `{}` conflicts with typing characters in {}", key, action, context);
+                }
+                match bound.iter().find(|(k, a)| k == key && a != action) {
+                    Some((_, other)) => bail!("`{}` is bound to both `{}` and `{}` in {}", key, other, action, context),
+                    None => bound.push((*key, *action)),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // action returns the action bound to the key.
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        let chord = KeyChord::from(key);
+        self.0
+            .iter()
+            .find(|(_, keys)| keys.contains(&chord))
+            .map(|(action, _)| *action)
+    }
+
+    // hint returns the keys of the action joined with `/`(e.g. `↑/<c-p>`). None if no key is bound.
+    pub fn hint(&self, action: Action) -> Option<String> {
+        let keys = self.0.iter().find(|(a, _)| *a == action).map(|(_, keys)| keys)?;
+        (!keys.is_empty()).then(|| keys.iter().map(|k| k.to_string()).collect::<Vec<_>>().join("/"))
+    }
+}
+
+/// KeyChord is a key with modifiers. It is written as `q`, `<enter>`, `<c-n>`, `<s-tab>`, `<a-x>` and so on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        match code {
+            // Shift is expressed in the character itself(e.g. `Y`) or BackTab.
+            KeyCode::Char(_) | KeyCode::BackTab => Self {
+                code,
+                modifiers: modifiers.difference(KeyModifiers::SHIFT),
+            },
+            _ => Self { code, modifiers },
+        }
+    }
+
+    fn parse(key: &str) -> Result<Self> {
+        let invalid = || anyhow!("invalid key: `{}` (e.g. `q`, `<enter>`, `<c-n>`)", key);
+
+        let mut chars = key.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(Self::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+
+        let mut name = key
+            .strip_prefix('<')
+            .and_then(|k| k.strip_suffix('>'))
+            .ok_or_else(invalid)?;
+        let mut modifiers = KeyModifiers::NONE;
+        while let Some((modifier, rest)) = name.split_once('-').filter(|(_, rest)| !rest.is_empty()) {
+            modifiers |= match modifier {
+                "c" => KeyModifiers::CONTROL,
+                "a" => KeyModifiers::ALT,
+                "s" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+            name = rest;
+        }
+
+        let mut chars = name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::Char(c.to_ascii_uppercase()),
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match name {
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "space" => KeyCode::Char(' '),
+                "bs" => KeyCode::Backspace,
+                "del" => KeyCode::Delete,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                _ => return Err(invalid()),
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+
+    // is_text returns true if the key inputs a character to the text area.
+    fn is_text(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_)) && !self.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+}
+
+impl From<&KeyEvent> for KeyChord {
+    fn from(key: &KeyEvent) -> Self {
+        Self::new(
+            key.code,
+            key.modifiers
+                .intersection(KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT),
+        )
+    }
+}
+
+impl TryFrom<String> for KeyChord {
+    type Error = anyhow::Error;
+
+    fn try_from(key: String) -> Result<Self> {
+        Self::parse(&key)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.code {
+            // The arrows are shown as they are since they are easier to read.
+            KeyCode::Up if self.modifiers.is_empty() => return write!(f, "↑"),
+            KeyCode::Down if self.modifiers.is_empty() => return write!(f, "↓"),
+            KeyCode::Char(' ') => "space".to_string(),
+            KeyCode::Char(c) if self.modifiers.is_empty() => return write!(f, "{}", c),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "enter".to_string(),
+            KeyCode::Esc => "esc".to_string(),
+            KeyCode::Tab => "tab".to_string(),
+            KeyCode::BackTab => "s-tab".to_string(),
+            KeyCode::Backspace => "bs".to_string(),
+            KeyCode::Delete => "del".to_string(),
+            KeyCode::Up => "up".to_string(),
+            KeyCode::Down => "down".to_string(),
+            KeyCode::Left => "left".to_string(),
+            KeyCode::Right => "right".to_string(),
+            KeyCode::Home => "home".to_string(),
+            KeyCode::End => "end".to_string(),
+            KeyCode::PageUp => "pageup".to_string(),
+            KeyCode::PageDown => "pagedown".to_string(),
+            code => format!("{:?}", code).to_lowercase(),
+        };
+        let mut modifiers = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            modifiers.push_str("c-");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            modifiers.push_str("a-");
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            modifiers.push_str("s-");
+        }
+        write!(f, "<{}{}>", modifiers, name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn key_chord_parse_test() {
+        struct Case {
+            title: &'static str,
+            key: &'static str,
+            expected: Result<(KeyCode, KeyModifiers, &'static str), &'static str>,
+        }
+        let cases = vec![
+            Case {
+                title: "character",
+                key: "q",
+                expected: Ok((KeyCode::Char('q'), KeyModifiers::NONE, "q")),
+            },
+            Case {
+                title: "named key",
+                key: "<enter>",
+                expected: Ok((KeyCode::Enter, KeyModifiers::NONE, "<enter>")),
+            },
+            Case {
+                title: "arrow",
+                key: "<up>",
+                expected: Ok((KeyCode::Up, KeyModifiers::NONE, "↑")),
+            },
+            Case {
+                title: "ctrl",
+                key: "<c-n>",
+                expected: Ok((KeyCode::Char('n'), KeyModifiers::CONTROL, "<c-n>")),
+            },
+            Case {
+                title: "multiple modifiers",
+                key: "<c-a-x>",
+                expected: Ok((KeyCode::Char('x'), KeyModifiers::CONTROL | KeyModifiers::ALT, "<c-a-x>")),
+            },
+            Case {
+                title: "shift-tab is backtab",
+                key: "<s-tab>",
+                expected: Ok((KeyCode::BackTab, KeyModifiers::NONE, "<s-tab>")),
+            },
+            Case {
+                title: "shift with a character is the uppercase",
+                key: "<s-g>",
+                expected: Ok((KeyCode::Char('G'), KeyModifiers::NONE, "G")),
+            },
+            Case {
+                title: "minus",
+                key: "<c-->",
+                expected: Ok((KeyCode::Char('-'), KeyModifiers::CONTROL, "<c-->")),
+            },
+            Case {
+                title: "unknown key",
+                key: "<c-foo>",
+                expected: Err("invalid key: `<c-foo>` (e.g. `q`, `<enter>`, `<c-n>`)"),
+            },
+            Case {
+                title: "unknown modifier",
+                key: "<x-n>",
+                expected: Err("invalid key: `<x-n>` (e.g. `q`, `<enter>`, `<c-n>`)"),
+            },
+            Case {
+                title: "without brackets",
+                key: "ctrl-n",
+                expected: Err("invalid key: `ctrl-n` (e.g. `q`, `<enter>`, `<c-n>`)"),
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(
+                case.expected
+                    .map(|(code, modifiers, display)| (KeyChord { code, modifiers }, display.to_string()))
+                    .map_err(|e| e.to_string()),
+                KeyChord::parse(case.key)
+                    .map(|k| (k, k.to_string()))
+                    .map_err(|e| e.to_string()),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn keymap_parse_test() {
+        struct Case {
+            title: &'static str,
+            content: &'static str,
+            actions: Vec<(Context, Action)>,
+            expected: Result<Vec<Option<&'static str>>, &'static str>,
+        }
+        let cases = vec![
+            Case {
+                title: "default preset",
+                content: "",
+                actions: vec![
+                    (Context::Main, Action::Next),
+                    (Context::History, Action::Quit),
+                    (Context::Popup, Action::Previous),
+                ],
+                expected: Ok(vec![Some("↓/<c-n>"), Some("<c-c>/q/<esc>"), Some("<s-tab>/↑")]),
+            },
+            Case {
+                title: "vim preset",
+                content: "preset = \"vim\"\n",
+                actions: vec![
+                    (Context::Main, Action::Next),
+                    (Context::History, Action::Next),
+                    (Context::Popup, Action::Previous),
+                ],
+                expected: Ok(vec![Some("↓/<c-n>/<c-j>"), Some("↓/<c-n>/j/<c-j>"), Some("<s-tab>/↑/<c-k>")]),
+            },
+            Case {
+                title: "emacs preset",
+                content: "preset = \"emacs\"\n",
+                actions: vec![
+                    (Context::Main, Action::Quit),
+                    (Context::History, Action::ToggleHistoryView),
+                    (Context::Popup, Action::Close),
+                ],
+                expected: Ok(vec![Some("<c-c>/<esc>/<c-g>"), Some("<c-g>"), Some("<esc>/<c-g>")]),
+            },
+            Case {
+                title: "configured keys replace the keys of the preset",
+                content: "preset = \"vim\"\n[main]\nnext = [\"<c-d>\"]\ncopy-command = []\n",
+                actions: vec![
+                    (Context::Main, Action::Next),
+                    (Context::Main, Action::CopyCommand),
+                    (Context::Main, Action::Previous),
+                ],
+                expected: Ok(vec![Some("<c-d>"), None, Some("↑/<c-p>/<c-k>")]),
+            },
+            Case {
+                title: "a key bound to multiple actions",
+                content: "[history]\nquit = [\"<c-g>\"]\n",
+                actions: vec![],
+                expected: Err("`<c-g>` is bound to both `quit` and `toggle-history-view` in keymap.history\n"),
+            },
+            Case {
+                title: "a key used to type characters",
+                content: "[main]\nnext = [\"j\"]\n",
+                actions: vec![],
+                expected: Err("`j` of `next` conflicts with typing characters in keymap.main\n"),
+            },
+            Case {
+                title: "an action not available in the context",
+                content: "[popup]\nquit = [\"<c-q>\"]\n",
+                actions: vec![],
+                expected: Err("`quit` is not available in keymap.popup\n"),
+            },
+            Case {
+                title: "invalid key",
+                content: "[main]\nquit = [\"<ctrl-q>\"]\n",
+                actions: vec![],
+                expected: Err(
+                    "TOML parse error at line 2, column 8\n  |\n2 | quit = [\"<ctrl-q>\"]\n  |        ^^^^^^^^^^^^\ninvalid key: `<ctrl-q>` (e.g. `q`, `<enter>`, `<c-n>`)\n",
+                ),
+            },
+        ];
+
+        for case in cases {
+            let hints = ::toml::from_str::<Keymap>(case.content)
+                .map(|keymap| {
+                    case.actions
+                        .iter()
+                        .map(|(context, action)| {
+                            let bindings = match context {
+                                Context::Main => &keymap.main,
+                                Context::History => &keymap.history,
+                                Context::Popup => &keymap.popup,
+                            };
+                            bindings.hint(*action)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .map_err(|e| e.to_string());
+            assert_eq!(
+                case.expected
+                    .map(|hints| hints.into_iter().map(|h| h.map(|h| h.to_string())).collect::<Vec<_>>())
+                    .map_err(|e| e.to_string()),
+                hints,
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn presets_are_valid_test() {
+        for preset in [Preset::Default, Preset::Vim, Preset::Emacs] {
+            let keymap = preset.keymap();
+            for (context, bindings) in [
+                (Context::Main, &keymap.main),
+                (Context::History, &keymap.history),
+                (Context::Popup, &keymap.popup),
+            ] {
+                assert_eq!(
+                    Ok(()),
+                    bindings.validate(context).map_err(|e| e.to_string()),
+                    "\nFailed: 🚨{:?} {}🚨\n",
+                    preset,
+                    context,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bindings_action_test() {
+        struct Case {
+            title: &'static str,
+            key: KeyEvent,
+            expected: Option<Action>,
+        }
+        let cases = vec![
+            Case {
+                title: "ctrl",
+                key: KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL),
+                expected: Some(Action::Next),
+            },
+            Case {
+                title: "character",
+                key: KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE),
+                expected: Some(Action::Next),
+            },
+            Case {
+                title: "shift of the character is ignored",
+                key: KeyEvent::new(KeyCode::Char('Q'), KeyModifiers::SHIFT),
+                expected: Some(Action::Quit),
+            },
+            Case {
+                title: "unbound key",
+                key: KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE),
+                expected: None,
+            },
+        ];
+
+        let keymap = ::toml::from_str::<Keymap>("preset = \"vim\"\n[history]\nquit = [\"Q\"]\n").unwrap();
+        for case in cases {
+            assert_eq!(case.expected, keymap.history.action(&case.key), "\nFailed: 🚨{:?}🚨\n", case.title);
+        }
+    }
+}
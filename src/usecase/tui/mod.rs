@@ -1,3 +1,9 @@
 pub(super) mod app;
 pub(super) mod config;
+pub(super) mod discovery;
+pub(super) mod keymap;
+pub(super) mod narrow_down;
+pub(super) mod preview;
+pub(super) mod query;
+pub(super) mod theme;
 pub(super) mod ui;
@@ -0,0 +1,281 @@
+use super::query;
+use crate::model::command;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use std::{collections::HashMap, sync::Arc};
+
+/// Commands is the commands matching the query with the indices of the matched characters in `command.to_string()`.
+pub type Commands = Arc<Vec<(command::CommandWithPreview, Vec<usize>)>>;
+
+/// Cache caches the commands of the runners and the result of the last query since the commands are narrowed down
+/// several times per frame(the list, the preview and the selection).
+/// When the query is extended(e.g. `bui` → `buil`), only the commands matching the last query are filtered.
+#[derive(Debug, Default)]
+pub struct Cache {
+    // The commands of all runners. The index is used as the id of the command.
+    candidates: Option<Vec<command::CommandWithPreview>>,
+    last: Option<Filtered>,
+}
+
+#[derive(Debug)]
+struct Filtered {
+    query: String,
+    // The ids of the commands matching the query in the order of the candidates.
+    ids: Vec<usize>,
+    commands: Commands,
+}
+
+impl Cache {
+    // invalidate clears the cache. It must be called when the runners, the project config or the history are replaced.
+    pub fn invalidate(&mut self) {
+        *self = Self::default();
+    }
+
+    // narrow_down returns the commands matching `query`(fzf's extended search syntax).
+    // The commands are sorted by (pinned, score, history score) if the query is not empty.
+    // `load_candidates` and `history_scores` are called only when they are needed.
+    pub fn narrow_down(
+        &mut self,
+        query: &str,
+        load_candidates: impl FnOnce() -> Vec<command::CommandWithPreview>,
+        history_scores: impl FnOnce() -> HashMap<String, f64>,
+    ) -> Commands {
+        if let Some(last) = &self.last
+            && last.query == query
+        {
+            return last.commands.clone();
+        }
+
+        let candidates = self.candidates.get_or_insert_with(load_candidates);
+        let parsed_query = query::Query::parse(query);
+        let (ids, commands) = if parsed_query.is_empty() {
+            ((0..candidates.len()).collect(), candidates.iter().map(|c| (c.clone(), vec![])).collect())
+        } else {
+            let ids_to_filter: Vec<usize> = match &self.last {
+                Some(last) if narrows(&last.query, query) => last.ids.clone(),
+                _ => (0..candidates.len()).collect(),
+            };
+
+            // The commands used frequently(or recently) are ranked higher if their fuzzy scores are the same.
+            let history_scores = history_scores();
+            let matcher = SkimMatcherV2::default();
+            let mut list: Vec<(bool, i64, f64, usize, Vec<usize>)> = ids_to_filter
+                .into_iter()
+                .filter_map(|id| {
+                    let command = &candidates[id];
+                    // Aliases are also searched and the best score is used.
+                    parsed_query.matches(&matcher, command).map(|m| {
+                        let history_score = history_scores.get(&command.to_string()).copied().unwrap_or_default();
+                        (command.metadata.pinned, m.score, history_score, id, m.indices)
+                    })
+                })
+                .collect();
+            let ids = list.iter().map(|(_, _, _, id, _)| *id).collect();
+
+            // Pinned commands are listed first if they match the query.
+            list.sort_by(|(pinned1, score1, history_score1, _, _), (pinned2, score2, history_score2, _, _)| {
+                pinned1
+                    .cmp(pinned2)
+                    .then(score1.cmp(score2))
+                    .then(history_score1.total_cmp(history_score2))
+            });
+            list.reverse();
+
+            let commands = list
+                .into_iter()
+                .map(|(_, _, _, id, indices)| (candidates[id].clone(), indices))
+                .collect();
+            (ids, commands)
+        };
+
+        let commands: Commands = Arc::new(commands);
+        self.last = Some(Filtered {
+            query: query.to_string(),
+            ids,
+            commands: commands.clone(),
+        });
+        commands
+    }
+}
+
+// narrows returns true if the commands matching `query` are always included in the ones matching `last`.
+// Appending characters to the query narrows down the commands except for adding `|`, a term after a dangling `|`
+// and extending a negation(`!bui` → `!buil`), a runner filter(`@ma` → `@make`) or a suffix(`bui$` → `bui$l`).
+fn narrows(last: &str, query: &str) -> bool {
+    let Some(appended) = query.strip_prefix(last) else {
+        return false;
+    };
+    if appended.contains('|') {
+        return false;
+    }
+
+    let last_term = last.split_whitespace().next_back().unwrap_or_default();
+    if last_term == "|" {
+        return false;
+    }
+    let extends_last_term = !last.ends_with(char::is_whitespace) && !appended.starts_with(char::is_whitespace);
+    !(extends_last_term && (last_term.starts_with('!') || last_term.starts_with('@') || last_term.ends_with('$')))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::runner_type;
+    use pretty_assertions::assert_eq;
+    use std::{path::PathBuf, time::Instant};
+
+    fn command(args: &str) -> command::CommandWithPreview {
+        command::CommandWithPreview::new(runner_type::RunnerType::Make, args.to_string(), PathBuf::from(""), 1)
+    }
+
+    #[test]
+    fn narrows_test() {
+        struct Case {
+            title: &'static str,
+            last: &'static str,
+            query: &'static str,
+            expected: bool,
+        }
+        let cases = vec![
+            Case {
+                title: "extending a fuzzy term",
+                last: "bui",
+                query: "buil",
+                expected: true,
+            },
+            Case {
+                title: "adding a term",
+                last: "bui",
+                query: "bui rel",
+                expected: true,
+            },
+            Case {
+                title: "extending an exact term to a suffix",
+                last: "'bui",
+                query: "'build$",
+                expected: true,
+            },
+            Case {
+                title: "deleting characters",
+                last: "buil",
+                query: "bui",
+                expected: false,
+            },
+            Case {
+                title: "adding |",
+                last: "bui",
+                query: "bui | rel",
+                expected: false,
+            },
+            Case {
+                title: "adding a term after a dangling |",
+                last: "bui | ",
+                query: "bui | rel",
+                expected: false,
+            },
+            Case {
+                title: "extending a negation",
+                last: "!bui",
+                query: "!buil",
+                expected: false,
+            },
+            Case {
+                title: "extending a runner filter",
+                last: "@ma",
+                query: "@make",
+                expected: false,
+            },
+            Case {
+                title: "extending a suffix",
+                last: "bui$",
+                query: "bui$l",
+                expected: false,
+            },
+            Case {
+                title: "adding a term after a negation",
+                last: "!bui ",
+                query: "!bui rel",
+                expected: true,
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(case.expected, narrows(case.last, case.query), "\nFailed: 🚨{:?}🚨\n", case.title);
+        }
+    }
+
+    #[test]
+    fn narrow_down_test() {
+        let mut cache = Cache::default();
+        let candidates = || {
+            vec![
+                command("build"),
+                command("build-release"),
+                command("test"),
+                command("build"),
+            ]
+        };
+        let narrow_down = |cache: &mut Cache, query: &str| -> Vec<String> {
+            cache
+                .narrow_down(query, candidates, HashMap::new)
+                .iter()
+                .map(|(c, _)| c.args.clone())
+                .collect()
+        };
+
+        // The commands with the same name are not merged.
+        assert_eq!(vec!["build", "build-release", "test", "build"], narrow_down(&mut cache, ""));
+        assert_eq!(vec!["build", "build-release", "build"], narrow_down(&mut cache, "bui"));
+        // Filtered incrementally.
+        assert_eq!(vec!["build-release"], narrow_down(&mut cache, "bui rel"));
+        // Not filtered incrementally since the query is not extended.
+        assert_eq!(vec!["test"], narrow_down(&mut cache, "tes"));
+        // The candidates are cached until the cache is invalidated.
+        let mut cache = Cache {
+            candidates: Some(vec![command("lint")]),
+            last: None,
+        };
+        assert_eq!(vec!["lint"], narrow_down(&mut cache, ""));
+        cache.invalidate();
+        assert_eq!(vec!["build", "build-release", "test", "build"], narrow_down(&mut cache, ""));
+    }
+
+    // narrow_down_benchmark measures narrowing down tens of thousands of commands while typing a query.
+    // Run it with `cargo test --release narrow_down_benchmark -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn narrow_down_benchmark() {
+        const COMMAND_COUNT: usize = 50_000;
+        let candidates: Vec<command::CommandWithPreview> = (0..COMMAND_COUNT)
+            .map(|i| command(&format!("target-{}-{}", ["build", "test", "lint", "deploy"][i % 4], i)))
+            .collect();
+        let query = "build 12";
+
+        let started_at = Instant::now();
+        for i in 1..=query.len() {
+            let mut cache = Cache::default();
+            cache.narrow_down(&query[..i], || candidates.clone(), HashMap::new);
+        }
+        let without_cache = started_at.elapsed();
+
+        let mut cache = Cache::default();
+        let started_at = Instant::now();
+        for i in 1..=query.len() {
+            // The list, the preview and the selection narrow down the commands in each frame.
+            for _ in 0..3 {
+                cache.narrow_down(&query[..i], || candidates.clone(), HashMap::new);
+            }
+        }
+        let with_cache = started_at.elapsed();
+
+        println!(
+            "{} commands, typing `{}`: {:?} without the cache(once per keystroke), {:?} with the cache(3 times per keystroke)",
+            COMMAND_COUNT, query, without_cache, with_cache
+        );
+        assert_eq!(
+            Cache::default()
+                .narrow_down(query, || candidates.clone(), HashMap::new)
+                .len(),
+            cache.narrow_down(query, || candidates.clone(), HashMap::new).len()
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use ratatui::text::Span;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use syntect::{easy::HighlightLines, highlighting, parsing::SyntaxSet};
+use syntect_tui::into_span;
+
+// The number of the highlighted ranges kept in the cache. The cache is cleared when it is exceeded.
+const HIGHLIGHTED_CAPACITY: usize = 64;
+
+/// Cache keeps the syntax definitions, the contents of the files and the highlighted lines of the preview window
+/// across frames since the preview window is rendered every frame(at least every 500ms).
+#[derive(Debug, Default)]
+pub struct Cache {
+    // It is loaded on the first render since loading it takes a while.
+    syntax_set: Option<SyntaxSet>,
+    // map[path](mtime, lines)
+    files: HashMap<PathBuf, (Option<SystemTime>, Vec<String>)>,
+    highlighted: HashMap<HighlightedKey, Vec<Vec<Span<'static>>>>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct HighlightedKey {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    // The range of the lines(0-based, inclusive).
+    start: usize,
+    end: usize,
+}
+
+impl Cache {
+    // lines returns the lines of `path` in `start..=end` highlighted as the syntax of `extension`.
+    // The lines are not highlighted if `theme` is None(the colors are disabled). It returns an empty vec if the file
+    // cannot be read.
+    pub fn lines(
+        &mut self,
+        path: &Path,
+        extension: &str,
+        (start, end): (usize, usize),
+        theme: Option<&highlighting::Theme>,
+    ) -> Vec<Vec<Span<'static>>> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        let key = HighlightedKey {
+            path: path.to_path_buf(),
+            modified,
+            start,
+            end,
+        };
+        if let Some(lines) = self.highlighted.get(&key) {
+            return lines.clone();
+        }
+
+        let Some(source_lines) = source_lines(&mut self.files, path, modified) else {
+            return vec![];
+        };
+        let source_lines = source_lines.iter().skip(start).take(end.saturating_sub(start) + 1);
+        let lines: Vec<Vec<Span<'static>>> = match theme {
+            Some(theme) => {
+                let syntax_set = self.syntax_set.get_or_insert_with(SyntaxSet::load_defaults_newlines);
+                let syntax = syntax_set
+                    .find_syntax_by_extension(extension)
+                    .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+                source_lines
+                    .map(|line| {
+                        // Skip syntax highlighting for lines that cause catastrophic
+                        // backtracking in syntect's Makefile grammar (e.g. nested
+                        // $(eval ... $(shell ...)) constructs).
+                        // For more details, see https://github.com/kyu08/fzf-make/issues/595.
+                        if line.contains("$(eval") && line.contains("$(shell") {
+                            return vec![Span::raw(line.clone())];
+                        }
+                        let mut h = HighlightLines::new(syntax, theme);
+                        match h.highlight_line(line, syntax_set) {
+                            Ok(segments) => segments
+                                .into_iter()
+                                .filter_map(|segment| into_span(segment).ok())
+                                .map(|span| Span::styled(span.content.into_owned(), span.style))
+                                .collect(),
+                            Err(_) => vec![Span::raw(line.clone())],
+                        }
+                    })
+                    .collect()
+            }
+            None => source_lines.map(|line| vec![Span::raw(line.clone())]).collect(),
+        };
+
+        if HIGHLIGHTED_CAPACITY <= self.highlighted.len() {
+            self.highlighted.clear();
+        }
+        self.highlighted.insert(key, lines.clone());
+        lines
+    }
+}
+
+// source_lines returns the lines of the file. The file is read again only when it is modified.
+fn source_lines<'a>(
+    files: &'a mut HashMap<PathBuf, (Option<SystemTime>, Vec<String>)>,
+    path: &Path,
+    modified: Option<SystemTime>,
+) -> Option<&'a Vec<String>> {
+    let is_fresh = matches!(files.get(path), Some((m, _)) if modified.is_some() && *m == modified);
+    if !is_fresh {
+        let content = fs::read(path).ok()?;
+        let lines = String::from_utf8_lossy(&content)
+            .lines()
+            // HACK: workaround for https://github.com/ratatui/ratatui/issues/876
+            .map(|line| line.replace('\t', "    "))
+            .collect();
+        files.insert(path.to_path_buf(), (modified, lines));
+    }
+    files.get(path).map(|(_, lines)| lines)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+    use syntect::highlighting::ThemeSet;
+
+    fn contents(lines: Vec<Vec<Span<'static>>>) -> Vec<String> {
+        lines
+            .into_iter()
+            .map(|spans| spans.into_iter().map(|s| s.content.into_owned()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn lines_test() {
+        let dir = std::env::temp_dir().join(format!("fzf-make-preview-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Makefile");
+        fs::write(&path, "build:\n\tcargo build\n\ntest:\n\tcargo test\n").unwrap();
+        let theme = ThemeSet::load_defaults().themes["base16-ocean.dark"].clone();
+
+        let mut cache = Cache::default();
+        let highlighted = cache.lines(&path, "mk", (1, 3), Some(&theme));
+        assert_eq!(vec!["    cargo build", "", "test:"], contents(highlighted.clone()));
+        assert!(highlighted[2].len() > 1, "the line should be highlighted: {:?}", highlighted[2]);
+        // The highlighted lines are cached.
+        assert_eq!(1, cache.highlighted.len());
+        assert_eq!(highlighted, cache.lines(&path, "mk", (1, 3), Some(&theme)));
+        // The lines out of the file are ignored.
+        assert_eq!(vec!["    cargo test"], contents(cache.lines(&path, "mk", (4, 10), None)));
+
+        // The modified file is read again.
+        fs::write(&path, "lint:\n\tcargo clippy\n").unwrap();
+        let modified = std::time::SystemTime::now() + std::time::Duration::from_secs(10);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert_eq!(vec!["    cargo clippy"], contents(cache.lines(&path, "mk", (1, 3), Some(&theme))));
+
+        // The file which cannot be read.
+        assert_eq!(0, cache.lines(&dir.join("Justfile"), "just", (0, 3), None).len());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    const HIGHLIGHT_THRESHOLD: Duration = Duration::from_millis(500);
+    const NORMAL_MAKEFILE_LINES: &[&str] = &[
+        ".PHONY: build",
+        "build:",
+        "\t@cargo build --verbose --release",
+        "test: tool-test",
+        "\trm -rf $(TEST_HISTORY_DIR)",
+        "\tRUST_BACKTRACE=full cargo nextest run",
+    ];
+    const PATHOLOGICAL_LINE: &str = "\t$(eval RESOLVED_TARGETS := $(shell bash resolve.sh $(DEPENDENCY_SERVICES)))";
+    fn load_makefile_syntax() -> (SyntaxSet, syntect::highlighting::Theme) {
+        let ss = SyntaxSet::load_defaults_newlines();
+        let ts = ThemeSet::load_defaults();
+        let theme = ts.themes["base16-ocean.dark"].clone();
+        (ss, theme)
+    }
+
+    #[test]
+    fn highlight_normal_lines_within_threshold() {
+        let (ss, theme) = load_makefile_syntax();
+        let syntax = ss
+            .find_syntax_by_extension("mk")
+            .unwrap_or_else(|| ss.find_syntax_plain_text());
+
+        let start = std::time::Instant::now();
+        for line in NORMAL_MAKEFILE_LINES {
+            let mut h = HighlightLines::new(syntax, &theme);
+            let _ = h.highlight_line(line, &ss);
+        }
+        let elapsed = start.elapsed();
+
+        assert!(
+            elapsed < HIGHLIGHT_THRESHOLD,
+            "Highlighting normal Makefile lines took {elapsed:?}, which exceeds the threshold of {HIGHLIGHT_THRESHOLD:?}",
+        );
+    }
+
+    #[test]
+    fn highlight_pathological_line_with_skip_guard() {
+        let (_, theme) = load_makefile_syntax();
+        let dir = std::env::temp_dir().join(format!("fzf-make-preview-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Makefile");
+        fs::write(&path, format!("deps:\n{}\n", PATHOLOGICAL_LINE)).unwrap();
+
+        let start = std::time::Instant::now();
+        // The skip guard in Cache::lines returns the line without highlighting.
+        let spans = Cache::default().lines(&path, "mk", (1, 1), Some(&theme));
+        let elapsed = start.elapsed();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(vec![vec![Span::raw(PATHOLOGICAL_LINE.replace('\t', "    "))]], spans);
+
+        assert!(
+            elapsed < HIGHLIGHT_THRESHOLD,
+            "Highlighting pathological line with skip guard took {elapsed:?}, which exceeds the threshold of {HIGHLIGHT_THRESHOLD:?}",
+        );
+    }
+}
@@ -0,0 +1,489 @@
+use crate::model::command;
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+
+/// Query is the search query in fzf's extended search syntax.
+/// The terms separated by spaces are ANDed and the terms joined with `|` are ORed.
+///
+/// | Term     | Match                                                  |
+/// | -------- | ------------------------------------------------------ |
+/// | `build`  | fuzzy match                                            |
+/// | `'build` | exact match                                            |
+/// | `^build` | prefix exact match                                     |
+/// | `build$` | suffix exact match                                     |
+/// | `!build` | inverse exact match(also `!^build` and `!build$`)      |
+/// | `@just`  | the commands of the runner(also `!@just`)              |
+#[derive(Debug, PartialEq)]
+pub struct Query {
+    // AND of the OR groups.
+    groups: Vec<Vec<Term>>,
+}
+
+/// Match is the result of matching a command with the query.
+#[derive(Debug, PartialEq)]
+pub struct Match {
+    pub score: i64,
+    // The indices of the matched characters in `command.to_string()`. They are highlighted in the commands list.
+    pub indices: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Term {
+    kind: TermKind,
+    text: String,
+    negated: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum TermKind {
+    Fuzzy,
+    Exact,
+    Prefix,
+    Suffix,
+    // `^build$`
+    Equal,
+    Runner,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Self {
+        let mut groups: Vec<Vec<Term>> = vec![];
+        let mut or = false;
+        for token in input.split_whitespace() {
+            if token == "|" {
+                // `|` at the beginning is ignored.
+                or = !groups.is_empty();
+                continue;
+            }
+            // Incomplete terms(e.g. `!` or `'` while typing) are ignored not to filter out everything.
+            let Some(term) = Term::parse(token) else {
+                continue;
+            };
+            match groups.last_mut() {
+                Some(group) if or => group.push(term),
+                _ => groups.push(vec![term]),
+            }
+            or = false;
+        }
+        Self { groups }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    // matches returns the score and the matched characters if the command matches the query.
+    // The score is the sum of the best scores of the OR groups. Higher is better.
+    pub fn matches(&self, matcher: &SkimMatcherV2, command: &command::CommandWithPreview) -> Option<Match> {
+        let targets = command.search_targets();
+        let mut result = Match {
+            score: 0,
+            indices: vec![],
+        };
+        for group in &self.groups {
+            let (score, indices) = group
+                .iter()
+                .filter_map(|term| term.matches(matcher, command, &targets))
+                .max_by_key(|(score, _)| *score)?;
+            result.score += score;
+            result.indices.extend(indices);
+        }
+        result.indices.sort_unstable();
+        result.indices.dedup();
+        Some(result)
+    }
+}
+
+impl Term {
+    fn parse(token: &str) -> Option<Self> {
+        let (negated, token) = match token.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, token),
+        };
+
+        let (kind, text) = if let Some(runner) = token.strip_prefix('@') {
+            (TermKind::Runner, runner)
+        } else if let Some(text) = token.strip_prefix('\'') {
+            (TermKind::Exact, text)
+        } else if let Some(text) = token.strip_prefix('^') {
+            match text.strip_suffix('$') {
+                Some(text) => (TermKind::Equal, text),
+                None => (TermKind::Prefix, text),
+            }
+        } else if let Some(text) = token.strip_suffix('$') {
+            (TermKind::Suffix, text)
+        } else if negated {
+            // `!build` is an inverse exact match as fzf does.
+            (TermKind::Exact, token)
+        } else {
+            (TermKind::Fuzzy, token)
+        };
+
+        (!text.is_empty()).then(|| Self {
+            kind,
+            text: text.to_string(),
+            negated,
+        })
+    }
+
+    // matches returns the score and the matched characters in `targets[0]`(`command.to_string()`) if the command
+    // matches the term. Negated terms and runner filters score 0 without matched characters.
+    fn matches(
+        &self,
+        matcher: &SkimMatcherV2,
+        command: &command::CommandWithPreview,
+        targets: &[String],
+    ) -> Option<(i64, Vec<usize>)> {
+        let matched = match self.kind {
+            TermKind::Runner => command
+                .runner_type
+                .to_string()
+                .eq_ignore_ascii_case(&self.text)
+                .then(|| (0, vec![])),
+            TermKind::Fuzzy => targets
+                .iter()
+                .filter_map(|t| matcher.fuzzy_match(t, &self.text))
+                .max()
+                .map(|score| {
+                    let indices = targets
+                        .first()
+                        .and_then(|t| matcher.fuzzy_indices(t, &self.text))
+                        .map(|(_, indices)| indices)
+                        .unwrap_or_default();
+                    (score, indices)
+                }),
+            // The exact matches are also scored by the fuzzy matcher to rank the better matches higher.
+            TermKind::Exact | TermKind::Prefix | TermKind::Suffix | TermKind::Equal => targets
+                .iter()
+                .filter(|t| self.exact_indices(t).is_some())
+                .filter_map(|t| matcher.fuzzy_match(t, &self.text))
+                .max()
+                .map(|score| {
+                    let indices = targets.first().and_then(|t| self.exact_indices(t)).unwrap_or_default();
+                    (score, indices)
+                }),
+        };
+        match (self.negated, matched) {
+            (false, matched) => matched,
+            (true, Some(_)) => None,
+            (true, None) => Some((0, vec![])),
+        }
+    }
+
+    // exact_indices returns the indices of the characters matched exactly. The target is matched with and without
+    // the runner name(e.g. `make build` and `build`) so that `^build` matches `make build`.
+    fn exact_indices(&self, target: &str) -> Option<Vec<usize>> {
+        // Smart case: case sensitive only if the term contains uppercase characters.
+        let (target, text) = if self.text.chars().any(|c| c.is_uppercase()) {
+            (target.to_string(), self.text.clone())
+        } else {
+            (target.to_lowercase(), self.text.to_lowercase())
+        };
+        // (the offset in characters, the target)
+        let without_runner = target
+            .split_once(' ')
+            .map(|(runner, rest)| (runner.chars().count() + 1, rest));
+
+        [Some((0, target.as_str())), without_runner]
+            .into_iter()
+            .flatten()
+            .find_map(|(offset, target)| {
+                let start = match self.kind {
+                    TermKind::Exact => target.find(&text),
+                    TermKind::Prefix => target.starts_with(&text).then_some(0),
+                    TermKind::Suffix => target.ends_with(&text).then(|| target.len() - text.len()),
+                    TermKind::Equal => (target == text).then_some(0),
+                    TermKind::Fuzzy | TermKind::Runner => None,
+                }?;
+                let start = offset + target[..start].chars().count();
+                Some((start..start + text.chars().count()).collect())
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::runner_type;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn term(kind: TermKind, text: &str, negated: bool) -> Term {
+        Term {
+            kind,
+            text: text.to_string(),
+            negated,
+        }
+    }
+
+    #[test]
+    fn parse_test() {
+        struct Case {
+            title: &'static str,
+            input: &'static str,
+            expected: Vec<Vec<Term>>,
+        }
+        let cases = vec![
+            Case {
+                title: "empty",
+                input: "  ",
+                expected: vec![],
+            },
+            Case {
+                title: "terms are ANDed",
+                input: "bui  rel",
+                expected: vec![
+                    vec![term(TermKind::Fuzzy, "bui", false)],
+                    vec![term(TermKind::Fuzzy, "rel", false)],
+                ],
+            },
+            Case {
+                title: "exact, prefix, suffix and equal",
+                input: "'build ^test lint$ ^fmt$",
+                expected: vec![
+                    vec![term(TermKind::Exact, "build", false)],
+                    vec![term(TermKind::Prefix, "test", false)],
+                    vec![term(TermKind::Suffix, "lint", false)],
+                    vec![term(TermKind::Equal, "fmt", false)],
+                ],
+            },
+            Case {
+                title: "negations",
+                input: "!build !^test !lint$ !@npm",
+                expected: vec![
+                    vec![term(TermKind::Exact, "build", true)],
+                    vec![term(TermKind::Prefix, "test", true)],
+                    vec![term(TermKind::Suffix, "lint", true)],
+                    vec![term(TermKind::Runner, "npm", true)],
+                ],
+            },
+            Case {
+                title: "terms joined with | are ORed",
+                input: "^build | ^test lint | fmt | @just",
+                expected: vec![
+                    vec![
+                        term(TermKind::Prefix, "build", false),
+                        term(TermKind::Prefix, "test", false),
+                    ],
+                    vec![
+                        term(TermKind::Fuzzy, "lint", false),
+                        term(TermKind::Fuzzy, "fmt", false),
+                        term(TermKind::Runner, "just", false),
+                    ],
+                ],
+            },
+            Case {
+                title: "incomplete terms and dangling | are ignored",
+                input: "| ! ' build ^ |",
+                expected: vec![vec![term(TermKind::Fuzzy, "build", false)]],
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(Query { groups: case.expected }, Query::parse(case.input), "\nFailed: 🚨{:?}🚨\n", case.title,);
+        }
+    }
+
+    #[test]
+    fn score_test() {
+        struct Case {
+            title: &'static str,
+            query: &'static str,
+            expected: Vec<&'static str>,
+        }
+        let cases = vec![
+            Case {
+                title: "fuzzy",
+                query: "bld",
+                expected: vec!["make build", "just build-release", "pnpm run build"],
+            },
+            Case {
+                title: "AND",
+                query: "bld rel",
+                expected: vec!["just build-release"],
+            },
+            Case {
+                title: "exact",
+                query: "'uild",
+                expected: vec!["make build", "just build-release", "pnpm run build"],
+            },
+            Case {
+                title: "exact does not match fuzzily",
+                query: "'bld",
+                expected: vec![],
+            },
+            Case {
+                title: "prefix matches with and without the runner name",
+                query: "^build",
+                expected: vec!["make build", "just build-release"],
+            },
+            Case {
+                title: "prefix with the runner name",
+                query: "^pnpm",
+                expected: vec!["pnpm run build"],
+            },
+            Case {
+                title: "suffix",
+                query: "build$",
+                expected: vec!["make build", "pnpm run build"],
+            },
+            Case {
+                title: "equal",
+                query: "^build$",
+                expected: vec!["make build"],
+            },
+            Case {
+                title: "negation",
+                query: "!release",
+                expected: vec!["make build", "make Test", "pnpm run build"],
+            },
+            Case {
+                title: "OR",
+                query: "^test | release$",
+                expected: vec!["make Test", "just build-release"],
+            },
+            Case {
+                title: "smart case",
+                query: "'Test",
+                expected: vec!["make Test"],
+            },
+            Case {
+                title: "case sensitive if the term contains uppercase",
+                query: "'TEST",
+                expected: vec![],
+            },
+            Case {
+                title: "runner filter",
+                query: "@just",
+                expected: vec!["just build-release"],
+            },
+            Case {
+                title: "runner filter with other terms",
+                query: "@make | @pnpm bld",
+                expected: vec!["make build", "pnpm run build"],
+            },
+            Case {
+                title: "negated runner filter",
+                query: "!@make",
+                expected: vec!["just build-release", "pnpm run build"],
+            },
+        ];
+
+        let command = |runner_type: runner_type::RunnerType, args: &str| {
+            command::CommandWithPreview::new(runner_type, args.to_string(), PathBuf::from(""), 1)
+        };
+        let commands = vec![
+            command(runner_type::RunnerType::Make, "build"),
+            command(runner_type::RunnerType::Make, "Test"),
+            command(runner_type::RunnerType::Just, "build-release"),
+            command(runner_type::RunnerType::JsPackageManager(runner_type::JsPackageManager::Pnpm), "run build"),
+        ];
+        let matcher = SkimMatcherV2::default();
+        for case in cases {
+            let query = Query::parse(case.query);
+            assert_eq!(
+                case.expected,
+                commands
+                    .iter()
+                    .filter(|c| query.matches(&matcher, c).is_some())
+                    .map(|c| c.to_string())
+                    .collect::<Vec<_>>(),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn score_ranks_better_matches_higher_test() {
+        let command = |args: &str| {
+            command::CommandWithPreview::new(runner_type::RunnerType::Make, args.to_string(), PathBuf::from(""), 1)
+        };
+        let matcher = SkimMatcherV2::default();
+        let query = Query::parse("bui | tst");
+        let score = |query: &Query, args: &str| query.matches(&matcher, &command(args)).unwrap().score;
+        // The consecutive match scores higher than the scattered one.
+        assert!(score(&query, "build") > score(&query, "b-u-i"));
+        // A runner filter does not change the score.
+        assert_eq!(score(&query, "build"), score(&Query::parse("bui | tst @make"), "build"));
+    }
+
+    #[test]
+    fn matched_indices_test() {
+        struct Case {
+            title: &'static str,
+            query: &'static str,
+            command: &'static str,
+            expected: Vec<usize>,
+        }
+        let cases = vec![
+            Case {
+                title: "fuzzy",
+                query: "bld",
+                command: "build",
+                expected: vec![5, 8, 9],
+            },
+            Case {
+                title: "exact",
+                query: "'uil",
+                command: "build",
+                expected: vec![6, 7, 8],
+            },
+            Case {
+                title: "prefix without the runner name",
+                query: "^bu",
+                command: "build",
+                expected: vec![5, 6],
+            },
+            Case {
+                title: "suffix",
+                query: "ld$",
+                command: "build",
+                expected: vec![8, 9],
+            },
+            Case {
+                title: "the indices of the terms are merged",
+                query: "^bu ild$",
+                command: "build",
+                expected: vec![5, 6, 7, 8, 9],
+            },
+            Case {
+                title: "the best term of OR",
+                query: "'xyz | 'ui",
+                command: "build",
+                expected: vec![6, 7],
+            },
+            Case {
+                title: "negations and runner filters have no indices",
+                query: "@make !test",
+                command: "build",
+                expected: vec![],
+            },
+            Case {
+                title: "the term matched with the alias has no indices",
+                query: "'ship",
+                command: "deploy",
+                expected: vec![],
+            },
+        ];
+
+        let matcher = SkimMatcherV2::default();
+        for case in cases {
+            let command = command::CommandWithPreview::new(
+                runner_type::RunnerType::Make,
+                case.command.to_string(),
+                PathBuf::from(""),
+                1,
+            )
+            .with_metadata(command::CommandMetadata {
+                aliases: vec!["ship".to_string()],
+                ..command::CommandMetadata::default()
+            });
+            assert_eq!(
+                Some(case.expected),
+                Query::parse(case.query).matches(&matcher, &command).map(|m| m.indices),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+}
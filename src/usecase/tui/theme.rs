@@ -0,0 +1,395 @@
+use anyhow::{Context, Result, anyhow};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    widgets::BorderType,
+};
+use rust_embed::RustEmbed;
+use serde::Deserialize;
+use std::{io::Cursor, path::Path, str::FromStr};
+use syntect::highlighting::{self, ThemeSet};
+
+// The syntax theme embedded in the binary. It is not included in the default themes of syntect.
+const EMBEDDED_SYNTAX_THEME: &str = "OneHalfDark";
+
+#[derive(RustEmbed)]
+#[folder = "assets"]
+struct Asset;
+
+/// Theme is the colors and the border styles of the UI and the syntax theme of the preview window.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub colors: Colors,
+    /// The border of the focused block.
+    pub border_selected: BorderType,
+    pub border_not_selected: BorderType,
+    /// None if the colors are disabled by `NO_COLOR`.
+    pub syntax: Option<highlighting::Theme>,
+    no_color: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Colors {
+    /// The focused block, the selected item and the hint bar.
+    pub selected: Color,
+    /// The blocks not focused and the supplementary texts(e.g. descriptions).
+    pub not_selected: Color,
+    pub warning: Color,
+    pub error: Color,
+    pub directory: Color,
+    /// The background of the line where the command is defined in the preview window.
+    pub current_line: Color,
+    /// The characters matching the query in the commands list.
+    pub matched: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(&ThemeConfig::default(), false, None).expect("the default theme must be valid")
+    }
+}
+
+impl Theme {
+    // new builds the theme from the preset and the config. The syntax theme is searched from the default themes
+    // of syntect, the embedded theme and `*.tmTheme` in `themes_dir`.
+    pub fn new(config: &ThemeConfig, no_color: bool, themes_dir: Option<&Path>) -> Result<Self> {
+        let preset = config.preset.theme();
+        let border_selected = config
+            .border_selected
+            .map(BorderType::from)
+            .unwrap_or(preset.border_selected);
+        let border_not_selected = config
+            .border_not_selected
+            .map(BorderType::from)
+            .unwrap_or(preset.border_not_selected);
+
+        // https://no-color.org/
+        if no_color {
+            return Ok(Self {
+                colors: Colors {
+                    selected: Color::Reset,
+                    not_selected: Color::Reset,
+                    warning: Color::Reset,
+                    error: Color::Reset,
+                    directory: Color::Reset,
+                    current_line: Color::Reset,
+                    matched: Color::Reset,
+                },
+                border_selected,
+                border_not_selected,
+                syntax: None,
+                no_color,
+            });
+        }
+
+        let syntax_theme = config.syntax_theme.as_deref().unwrap_or(preset.syntax_theme);
+        let mut syntax = load_syntax_themes(themes_dir)?
+            .themes
+            .remove(syntax_theme)
+            .ok_or_else(|| anyhow!("unknown syntax theme: `{}`", syntax_theme))?;
+        // The background of the terminal is used instead of the one of the syntax theme.
+        syntax.settings.background = Some(highlighting::Color { r: 0, g: 0, b: 0, a: 0 });
+
+        let colors = &config.colors;
+        Ok(Self {
+            colors: Colors {
+                selected: colors.selected.map_or(preset.colors.selected, |c| c.0),
+                not_selected: colors.not_selected.map_or(preset.colors.not_selected, |c| c.0),
+                warning: colors.warning.map_or(preset.colors.warning, |c| c.0),
+                error: colors.error.map_or(preset.colors.error, |c| c.0),
+                directory: colors.directory.map_or(preset.colors.directory, |c| c.0),
+                current_line: colors.current_line.map_or(preset.colors.current_line, |c| c.0),
+                matched: colors.matched.map_or(preset.colors.matched, |c| c.0),
+            },
+            border_selected,
+            border_not_selected,
+            syntax: Some(syntax),
+            no_color,
+        })
+    }
+
+    // border returns the style and the type of the border of the block.
+    pub fn border(&self, is_selected: bool) -> (Style, BorderType) {
+        if is_selected {
+            (Style::default().fg(self.colors.selected), self.border_selected)
+        } else {
+            (Style::default().fg(self.colors.not_selected), self.border_not_selected)
+        }
+    }
+
+    pub fn selected(&self) -> Style {
+        Style::default().fg(self.colors.selected)
+    }
+
+    pub fn not_selected(&self) -> Style {
+        Style::default().fg(self.colors.not_selected)
+    }
+
+    pub fn warning(&self) -> Style {
+        Style::default().fg(self.colors.warning)
+    }
+
+    pub fn error(&self) -> Style {
+        Style::default().fg(self.colors.error)
+    }
+
+    pub fn directory(&self) -> Style {
+        Style::default().fg(self.colors.directory)
+    }
+
+    // highlight returns the style of the selected item of the lists.
+    pub fn highlight(&self) -> Style {
+        if self.no_color {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            self.selected()
+        }
+    }
+
+    // current_line returns the style of the line where the command is defined in the preview window.
+    pub fn current_line(&self) -> Style {
+        if self.no_color {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default().bg(self.colors.current_line)
+        }
+    }
+
+    // matched returns the style of the characters matching the query. They are also bold to be distinguished
+    // in the selected item whose color is overridden by the highlight.
+    pub fn matched(&self) -> Style {
+        if self.no_color {
+            Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+        } else {
+            Style::default().fg(self.colors.matched).add_modifier(Modifier::BOLD)
+        }
+    }
+}
+
+// load_syntax_themes loads the default themes of syntect, the embedded theme and the themes in `themes_dir`.
+fn load_syntax_themes(themes_dir: Option<&Path>) -> Result<ThemeSet> {
+    let mut theme_set = ThemeSet::load_defaults();
+
+    let file_name = format!("{}.tmTheme", EMBEDDED_SYNTAX_THEME);
+    let content = Asset::get(&file_name).context("Failed to get embedded asset")?;
+    let theme = ThemeSet::load_from_reader(&mut Cursor::new(content.data)).context("Failed to load embedded theme")?;
+    theme_set.themes.insert(EMBEDDED_SYNTAX_THEME.to_string(), theme);
+
+    // The themes are named after their file names(e.g. `themes/Nord.tmTheme` is `Nord`).
+    if let Some(themes_dir) = themes_dir.filter(|d| d.is_dir()) {
+        theme_set
+            .add_from_folder(themes_dir)
+            .with_context(|| format!("failed to load the themes in {}", themes_dir.display()))?;
+    }
+    Ok(theme_set)
+}
+
+/// ThemeConfig is `[theme]` of the config file. The values of the preset are used for unconfigured keys.
+#[derive(Clone, Debug, PartialEq, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ThemeConfig {
+    preset: Preset,
+    /// The name of the syntax theme used in the preview window.
+    syntax_theme: Option<String>,
+    border_selected: Option<BorderStyle>,
+    border_not_selected: Option<BorderStyle>,
+    colors: ColorsConfig,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct ColorsConfig {
+    selected: Option<ThemeColor>,
+    not_selected: Option<ThemeColor>,
+    warning: Option<ThemeColor>,
+    error: Option<ThemeColor>,
+    directory: Option<ThemeColor>,
+    current_line: Option<ThemeColor>,
+    matched: Option<ThemeColor>,
+}
+
+/// ThemeColor is a color written as a name(e.g. `red`, `dark-gray`), an ANSI index(e.g. `8`) or `#rrggbb`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+struct ThemeColor(Color);
+
+impl TryFrom<String> for ThemeColor {
+    type Error = anyhow::Error;
+
+    fn try_from(color: String) -> Result<Self> {
+        Color::from_str(&color)
+            .map(ThemeColor)
+            .map_err(|_| anyhow!("invalid color: `{}` (e.g. `red`, `8`, `#a1dc9c`)", color))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum BorderStyle {
+    Plain,
+    Rounded,
+    Double,
+    Thick,
+}
+
+impl From<BorderStyle> for BorderType {
+    fn from(style: BorderStyle) -> Self {
+        match style {
+            BorderStyle::Plain => BorderType::Plain,
+            BorderStyle::Rounded => BorderType::Rounded,
+            BorderStyle::Double => BorderType::Double,
+            BorderStyle::Thick => BorderType::Thick,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Preset {
+    /// For the terminals with dark background.
+    #[default]
+    Dark,
+    /// For the terminals with light background.
+    Light,
+}
+
+struct PresetTheme {
+    colors: Colors,
+    border_selected: BorderType,
+    border_not_selected: BorderType,
+    syntax_theme: &'static str,
+}
+
+impl Preset {
+    fn theme(&self) -> PresetTheme {
+        match self {
+            Preset::Dark => PresetTheme {
+                colors: Colors {
+                    selected: Color::Rgb(161, 220, 156),
+                    not_selected: Color::DarkGray,
+                    warning: Color::Yellow,
+                    error: Color::Red,
+                    directory: Color::Blue,
+                    current_line: Color::Rgb(94, 120, 200),
+                    matched: Color::Rgb(229, 192, 123),
+                },
+                border_selected: BorderType::Thick,
+                border_not_selected: BorderType::Plain,
+                syntax_theme: EMBEDDED_SYNTAX_THEME,
+            },
+            Preset::Light => PresetTheme {
+                colors: Colors {
+                    selected: Color::Rgb(34, 134, 58),
+                    not_selected: Color::Rgb(110, 110, 110),
+                    warning: Color::Rgb(176, 106, 0),
+                    error: Color::Red,
+                    directory: Color::Blue,
+                    current_line: Color::Rgb(200, 215, 250),
+                    matched: Color::Rgb(207, 34, 46),
+                },
+                border_selected: BorderType::Thick,
+                border_not_selected: BorderType::Plain,
+                syntax_theme: "InspiredGitHub",
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+
+    #[test]
+    fn theme_new_test() {
+        struct Case {
+            title: &'static str,
+            config: &'static str,
+            no_color: bool,
+            expected: Result<(Color, Color, BorderType, Option<&'static str>), &'static str>,
+        }
+        let cases = vec![
+            Case {
+                title: "dark preset by default",
+                config: "",
+                no_color: false,
+                expected: Ok((Color::Rgb(161, 220, 156), Color::DarkGray, BorderType::Thick, Some("OneHalfDark"))),
+            },
+            Case {
+                title: "light preset",
+                config: "preset = \"light\"\n",
+                no_color: false,
+                expected: Ok((
+                    Color::Rgb(34, 134, 58),
+                    Color::Rgb(110, 110, 110),
+                    BorderType::Thick,
+                    Some("InspiredGitHub"),
+                )),
+            },
+            Case {
+                title: "configured values take precedence over the preset",
+                config: "preset = \"light\"\nsyntax-theme = \"Solarized (light)\"\nborder-selected = \"rounded\"\n[colors]\nselected = \"#0000ff\"\nnot-selected = \"8\"\n",
+                no_color: false,
+                expected: Ok((
+                    Color::Rgb(0, 0, 255),
+                    Color::Indexed(8),
+                    BorderType::Rounded,
+                    Some("Solarized (light)"),
+                )),
+            },
+            Case {
+                title: "the theme in the themes directory",
+                config: "syntax-theme = \"Custom\"\n",
+                no_color: false,
+                expected: Ok((Color::Rgb(161, 220, 156), Color::DarkGray, BorderType::Thick, Some("Custom"))),
+            },
+            Case {
+                title: "NO_COLOR disables the colors but not the borders",
+                config: "border-selected = \"double\"\n[colors]\nselected = \"red\"\n",
+                no_color: true,
+                expected: Ok((Color::Reset, Color::Reset, BorderType::Double, None)),
+            },
+            Case {
+                title: "unknown syntax theme",
+                config: "syntax-theme = \"Unknown\"\n",
+                no_color: false,
+                expected: Err("unknown syntax theme: `Unknown`"),
+            },
+        ];
+
+        // The embedded theme is copied to the themes directory as `Custom.tmTheme`.
+        let themes_dir = std::env::temp_dir().join(format!("fzf-make-themes-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&themes_dir).unwrap();
+        fs::write(themes_dir.join("Custom.tmTheme"), Asset::get("OneHalfDark.tmTheme").unwrap().data).unwrap();
+        let theme_set = load_syntax_themes(Some(&themes_dir)).unwrap();
+
+        for case in cases {
+            let config: ThemeConfig = ::toml::from_str(case.config).unwrap();
+            assert_eq!(
+                case.expected
+                    .map(|(selected, not_selected, border_selected, syntax_theme)| {
+                        (
+                            selected,
+                            not_selected,
+                            border_selected,
+                            syntax_theme.map(|name| theme_set.themes[name].scopes.clone()),
+                        )
+                    })
+                    .map_err(|e| e.to_string()),
+                Theme::new(&config, case.no_color, Some(&themes_dir))
+                    .map(|theme| {
+                        (
+                            theme.colors.selected,
+                            theme.colors.not_selected,
+                            theme.border_selected,
+                            theme.syntax.map(|s| s.scopes),
+                        )
+                    })
+                    .map_err(|e| e.to_string()),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+        fs::remove_dir_all(&themes_dir).unwrap();
+    }
+}
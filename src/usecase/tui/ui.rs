@@ -1,26 +1,16 @@
-use super::app::{AppState, CurrentPane, Model, SelectCommandState};
-use crate::model::command;
-use anyhow::{Context, Result};
+use super::{
+    app::{AppState, CurrentPane, HistoryView, Model, SelectCommandState},
+    keymap, theme,
+};
+use crate::model::{command, histories};
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Flex, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
 };
-use rust_embed::RustEmbed;
-use std::{
-    fs,
-    fs::File,
-    io::{BufRead, BufReader},
-    path::PathBuf,
-};
-use syntect::{
-    easy::HighlightLines,
-    highlighting::{Color as SColor, ThemeSet},
-    parsing::SyntaxSet,
-};
-use syntect_tui::into_span;
+use std::path::Path;
 
 pub fn ui(f: &mut Frame, model: &mut Model) {
     if let AppState::SelectCommand(model) = &mut model.app_state {
@@ -48,12 +38,12 @@ pub fn ui(f: &mut Frame, model: &mut Model) {
         render_notification_block(model, f, notification_and_current_version[0]);
         render_current_version_block(f, notification_and_current_version[1]);
 
-        let commands = if f.area().height < HEIGHT_THRESHOLD_TO_HIDE_PREVIEW_WINDOW {
+        let commands = if !model.preview.enabled || f.area().height < model.preview.min_height {
             let preview_and_commands = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([Constraint::Percentage(100)])
                 .split(main[0]);
-            // When the window height is too small to show the preview window.
+            // When the preview window is disabled or the window height is too small to show it.
 
             preview_and_commands[0]
         } else {
@@ -63,7 +53,7 @@ pub fn ui(f: &mut Frame, model: &mut Model) {
                 .split(main[0]);
 
             // Render the preview window only when the window height is enough.
-            render_preview_block(model, f, preview_and_commands[0]);
+            render_preview_and_expanded_body_block(model, f, preview_and_commands[0]);
 
             preview_and_commands[1]
         };
@@ -77,102 +67,88 @@ pub fn ui(f: &mut Frame, model: &mut Model) {
         render_history_block(model, f, commands_and_history[1]);
         // Render additional arguments popup if needed.
         render_additional_arguments_popup(model, f);
+        // Render confirm popup if needed.
+        render_confirm_popup(model, f);
+        // Render variables popup if needed.
+        render_vars_popup(model, f);
     }
 }
 
-const HEIGHT_THRESHOLD_TO_HIDE_PREVIEW_WINDOW: u16 = 20;
-const FG_COLOR_SELECTED: ratatui::style::Color = Color::Rgb(161, 220, 156);
-const FG_COLOR_NOT_SELECTED: ratatui::style::Color = Color::DarkGray;
-const BORDER_STYLE_SELECTED: ratatui::widgets::block::BorderType = ratatui::widgets::BorderType::Thick;
-const BORDER_STYLE_NOT_SELECTED: ratatui::widgets::block::BorderType = ratatui::widgets::BorderType::Plain;
 const TITLE_STYLE: ratatui::style::Style = Style::new().add_modifier(Modifier::BOLD);
 
-fn color_and_border_style_for_selectable(
+fn border_for_selectable(
+    theme: &theme::Theme,
     is_selected: bool,
-    is_additional_arguments_popup_opened: bool,
-) -> (Color, ratatui::widgets::block::BorderType) {
-    if is_selected && !is_additional_arguments_popup_opened {
-        (FG_COLOR_SELECTED, BORDER_STYLE_SELECTED)
-    } else {
-        (FG_COLOR_NOT_SELECTED, BORDER_STYLE_NOT_SELECTED)
+    is_popup_opened: bool,
+) -> (Style, ratatui::widgets::block::BorderType) {
+    theme.border(is_selected && !is_popup_opened)
+}
+
+// render_preview_and_expanded_body_block renders everything which actually runs above the preview window
+// when the command runs other commands(e.g. chained scripts and lifecycle hooks in package.json).
+fn render_preview_and_expanded_body_block(model: &mut SelectCommandState, f: &mut Frame, chunk: ratatui::layout::Rect) {
+    let expanded_body = model
+        .narrow_down_commands_with_indices()
+        .get(model.commands_list_state.selected().unwrap_or(0))
+        .map(|(c, _)| c.metadata.expanded_body.clone())
+        .unwrap_or_default();
+    if expanded_body.len() <= 1 {
+        render_preview_block(model, f, chunk);
+        return;
     }
+
+    // +2 is for border lines. The expanded body takes up to half of the area.
+    let height = (expanded_body.len() as u16 + 2).min(chunk.height / 2);
+    let expanded_body_and_preview = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(height), Constraint::Min(3)])
+        .split(chunk);
+
+    let (border_style, border_type) =
+        border_for_selectable(&model.theme, model.current_pane.is_main(), model.is_popup_opened());
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(border_type)
+        .border_style(border_style)
+        .title(" 🔗 Scripts to run ")
+        .title_style(TITLE_STYLE)
+        .padding(ratatui::widgets::Padding::new(1, 1, 0, 0));
+    let lines: Vec<Line> = expanded_body.into_iter().map(Line::from).collect();
+    f.render_widget(Paragraph::new(lines).block(block), expanded_body_and_preview[0]);
+
+    render_preview_block(model, f, expanded_body_and_preview[1]);
 }
 
-fn render_preview_block(model: &SelectCommandState, f: &mut Frame, chunk: ratatui::layout::Rect) {
-    let narrow_down_commands = model.narrow_down_commands();
-    let selecting_command = narrow_down_commands.get(model.commands_list_state.selected().unwrap_or(0));
+fn render_preview_block(model: &mut SelectCommandState, f: &mut Frame, chunk: ratatui::layout::Rect) {
+    let narrow_down_commands = model.narrow_down_commands_with_indices();
+    let selecting_command = narrow_down_commands
+        .get(model.commands_list_state.selected().unwrap_or(0))
+        .map(|(c, _)| c);
 
-    let reader = match selecting_command.map(|c| File::open(c.file_path.clone())) {
-        Some(Ok(file)) => Some(BufReader::new(file)),
-        _ => None,
-    };
     let command_row_index = selecting_command.map(|c| c.line_number as usize - 1);
     let row_count = chunk.rows().count() - 2; // NOTE: chunk.rows().count() includes border lines
     let start_index_and_end_index = command_row_index.map(|c| determine_rendering_position(row_count, c));
-    // NOTE: due to lifetime, source_lines need to be declared outside of `let lines = {/* ... */}`
-    let source_lines: Vec<_> = match (selecting_command, start_index_and_end_index, reader) {
-        (Some(_), Some((start_index, end_index)), Some(reader)) => {
-            reader
-                .lines()
-                .skip(start_index)
-                .take(end_index - start_index + 1)
-                // HACK: workaround for https://github.com/ratatui/ratatui/issues/876
-                .map(|line| line.unwrap().replace('\t', "    "))
-                .collect()
-        }
-        _ => vec![],
-    };
 
     let lines = {
         match (selecting_command, start_index_and_end_index, command_row_index) {
-            (Some(cmd), Some((start_index, _)), Some(command_row_index)) => {
-                let ss = SyntaxSet::load_defaults_newlines();
-
-                let mut ts = ThemeSet::load_defaults();
-                if let Ok(path) = load_syntax_highlighting_theme() {
-                    let _ = ts.add_from_folder(path);
-                }
+            (Some(cmd), Some((start_index, end_index)), Some(command_row_index)) => {
+                // NOTE: It is against TEA's way to update the model value on the UI side, but the highlighted lines
+                // are cached since highlighting them every frame is slow on large files.
+                let highlighted_lines = model.preview_cache.lines(
+                    &cmd.file_path,
+                    cmd.runner_type.get_extension_for_highlighting(),
+                    (start_index, end_index),
+                    model.theme.syntax.as_ref(),
+                );
 
-                let command_file_extension = cmd.runner_type.get_extension_for_highlighting();
-                let syntax = ss
-                    .find_syntax_by_extension(command_file_extension)
-                    .unwrap_or_else(|| ss.find_syntax_plain_text());
-
-                let theme = &mut ts.themes["OneHalfDark"].clone();
                 let mut lines = vec![];
-                for (index, line) in source_lines.iter().enumerate() {
-                    theme.settings.background = Some(SColor {
-                        r: 94,
-                        g: 120,
-                        b: 200,
-                        // To get bg same as ratatui's background, make the line other than includes command transparent.
-                        a: if (start_index + index) == command_row_index {
-                            50
-                        } else {
-                            0
-                        },
-                    });
-                    // Skip syntax highlighting for lines that cause catastrophic
-                    // backtracking in syntect's Makefile grammar (e.g. nested
-                    // $(eval ... $(shell ...)) constructs).
-                    // For more details, see https://github.com/kyu08/fzf-make/issues/595.
-                    let mut spans: Vec<Span> = if line.contains("$(eval") && line.contains("$(shell") {
-                        if (start_index + index) == command_row_index {
-                            vec![Span::styled(
-                                line.to_string(),
-                                Style::default().bg(Color::Rgb(94, 120, 200)),
-                            )]
-                        } else {
-                            vec![Span::raw(line.to_string())]
-                        }
-                    } else {
-                        let mut h = HighlightLines::new(syntax, theme);
-                        h.highlight_line(line, &ss)
-                            .unwrap()
+                for (index, mut spans) in highlighted_lines.into_iter().enumerate() {
+                    if (start_index + index) == command_row_index {
+                        spans = spans
                             .into_iter()
-                            .filter_map(|segment| into_span(segment).ok())
-                            .collect()
-                    };
+                            .map(|span| span.patch_style(model.theme.current_line()))
+                            .collect();
+                    }
 
                     // add row number
                     spans.insert(0, Span::styled(format!("{:5} ", start_index + index + 1), Style::default()));
@@ -185,53 +161,37 @@ fn render_preview_block(model: &SelectCommandState, f: &mut Frame, chunk: ratatu
         }
     };
 
-    let (fg_color_, border_style) = color_and_border_style_for_selectable(
-        model.current_pane.is_main(),
-        model.is_additional_arguments_popup_opened(),
-    );
+    let (border_style, border_type) =
+        border_for_selectable(&model.theme, model.current_pane.is_main(), model.is_popup_opened());
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_type(border_style)
-        .border_style(Style::default().fg(fg_color_))
+        .border_type(border_type)
+        .border_style(border_style)
         .title(" ✨ Preview ")
         .title_style(TITLE_STYLE);
-    let preview_widget = Paragraph::new(lines).wrap(Wrap { trim: false }).block(block);
-    f.render_widget(preview_widget, chunk);
-}
-
-#[derive(RustEmbed)]
-#[folder = "assets"]
-struct Asset;
-fn load_syntax_highlighting_theme() -> Result<PathBuf> {
-    let temp_dir = std::env::temp_dir().join("fzf-make-syntax-highlighting-assets");
-    let version_file = temp_dir.join(".version");
-    let current_version = env!("CARGO_PKG_VERSION");
-
-    let should_extract = if temp_dir.exists() {
-        match fs::read_to_string(&version_file) {
-            // extract is done only once per version
-            Ok(v) => v.trim() != current_version,
-            Err(_) => true,
-        }
+    // Show how the command will be executed(working directory, shell, etc.) if the runner provides it.
+    let execution_details = selecting_command
+        .and_then(|c| {
+            model
+                .get_runner(&c.runner_type)
+                .map(|r| r.execution_details(&c.clone().into(), &model.current_dir()))
+        })
+        .unwrap_or_default();
+    let block = if execution_details.is_empty() {
+        block
     } else {
-        true
+        block.title_bottom(format!(" {} ", execution_details.join(" | ")))
     };
-
-    if should_extract {
-        if temp_dir.exists() {
-            fs::remove_dir_all(&temp_dir).context("Failed to remove existing temp directory")?;
-        }
-        fs::create_dir_all(&temp_dir).context("Failed to create temp directory")?;
-
-        let theme_file_name = "OneHalfDark.tmTheme";
-        let path = temp_dir.join(theme_file_name);
-        let content = Asset::get(theme_file_name).context("Failed to get embedded asset")?;
-
-        fs::write(path, content.data).context("Failed to write asset file")?;
-        fs::write(version_file, current_version).context("Failed to write version file")?;
-    }
-
-    Ok(temp_dir)
+    // Show the summary(or description) of the command if the runner provides it.
+    let block = match selecting_command
+        .and_then(|c| c.metadata.summary.as_ref().or(c.metadata.description.as_ref()))
+        .and_then(|s| s.lines().next())
+    {
+        Some(summary) => block.title(Line::from(format!(" {} ", summary)).right_aligned()),
+        None => block,
+    };
+    let preview_widget = Paragraph::new(lines).wrap(Wrap { trim: false }).block(block);
+    f.render_widget(preview_widget, chunk);
 }
 
 fn determine_rendering_position(row_count: usize, command_row_index: usize) -> (usize, usize) {
@@ -251,12 +211,22 @@ fn determine_rendering_position(row_count: usize, command_row_index: usize) -> (
 }
 
 fn render_commands_block(model: &mut SelectCommandState, f: &mut Frame, chunk: ratatui::layout::Rect) {
+    // The runners being detected in the background are shown as the loading indicator.
+    let title = match &model.detecting_runners {
+        Some(detecting) => format!(" 📢 Commands ⏳ detecting {} ", detecting.pending().join(", ")),
+        None => " 📢 Commands ".to_string(),
+    };
     f.render_stateful_widget(
         commands_block(
-            " 📢 Commands ",
-            model.narrow_down_commands().into_iter().map(|c| c.into()).collect(),
+            &title,
+            model
+                .narrow_down_commands_with_indices()
+                .iter()
+                .map(|(c, indices)| command_line(c, indices, &model.theme))
+                .collect(),
             model.current_pane.is_main(),
-            model.is_additional_arguments_popup_opened(),
+            model.is_popup_opened(),
+            &model.theme,
         ),
         chunk,
         // NOTE: It is against TEA's way to update the model value on the UI side, but it is unavoidable so it is allowed.
@@ -265,17 +235,15 @@ fn render_commands_block(model: &mut SelectCommandState, f: &mut Frame, chunk: r
 }
 
 fn render_input_block(model: &mut SelectCommandState, f: &mut Frame, chunk: ratatui::layout::Rect) {
-    let (fg_color, border_style) = color_and_border_style_for_selectable(
-        model.current_pane.is_main(),
-        model.is_additional_arguments_popup_opened(),
-    );
+    let (border_style, border_type) =
+        border_for_selectable(&model.theme, model.current_pane.is_main(), model.is_popup_opened());
 
     let block = Block::default()
         .title(" 🔍 Search ")
         .title_style(TITLE_STYLE)
         .borders(Borders::ALL)
-        .border_type(border_style)
-        .border_style(Style::default().fg(fg_color))
+        .border_type(border_type)
+        .border_style(border_style)
         .style(Style::default())
         .padding(ratatui::widgets::Padding::new(2, 2, 0, 0));
 
@@ -295,6 +263,13 @@ fn render_notification_block(model: &mut SelectCommandState, f: &mut Frame, chun
                 Ok(c) => format!("📋 Command copied to clipboard: {}", c),
                 Err(e) => format!("⚠️ Failed to copy command to clipboard: {}", e),
             }
+        } else if !model.warnings.is_empty() {
+            model
+                .warnings
+                .iter()
+                .map(|w| format!("⚠️ {}", w))
+                .collect::<Vec<_>>()
+                .join(" ")
         } else {
             match &model.latest_version {
                 Some(has_update) => {
@@ -316,7 +291,7 @@ fn render_notification_block(model: &mut SelectCommandState, f: &mut Frame, chun
     let notification = Span::styled(text, Style::default());
     let block = Block::default()
         .padding(ratatui::widgets::Padding::new(1, 0, 1, 1))
-        .style(Style::new().add_modifier(Modifier::BOLD).fg(Color::Yellow));
+        .style(model.theme.warning().add_modifier(Modifier::BOLD));
     let key_notes_footer = Paragraph::new(notification).wrap(Wrap { trim: true }).block(block);
     f.render_widget(key_notes_footer, chunk);
 }
@@ -335,13 +310,34 @@ fn render_current_version_block(f: &mut Frame, chunk: ratatui::layout::Rect) {
 }
 
 fn render_history_block(model: &mut SelectCommandState, f: &mut Frame, chunk: ratatui::layout::Rect) {
-    f.render_stateful_widget(
-        commands_block(
+    let now = histories::now();
+    let home_dir = std::env::home_dir();
+    let (title, lines) = match model.history_view {
+        HistoryView::Project => (
             " 📚 History ",
-            model.get_history().into_iter().map(|c| c.into()).collect(),
-            model.current_pane.is_history(),
-            model.is_additional_arguments_popup_opened(),
+            model
+                .get_history()
+                .iter()
+                .map(|c| match &c.dir {
+                    // Show the directory only if the command was executed in another directory(e.g. a subdirectory).
+                    Some(dir) => with_dir(history_line(c, now, &model.theme), dir, home_dir.as_deref(), &model.theme),
+                    None => history_line(c, now, &model.theme),
+                })
+                .collect(),
         ),
+        HistoryView::Global => (
+            " 🌐 Global history ",
+            model
+                .get_global_history()
+                .iter()
+                .map(|c| {
+                    with_dir(history_line(&c.command, now, &model.theme), &c.dir(), home_dir.as_deref(), &model.theme)
+                })
+                .collect(),
+        ),
+    };
+    f.render_stateful_widget(
+        commands_block(title, lines, model.current_pane.is_history(), model.is_popup_opened(), &model.theme),
         chunk,
         // NOTE: It is against TEA's way to update the model value on the UI side, but it is unavoidable so it is allowed.
         &mut model.history_list_state,
@@ -364,11 +360,16 @@ fn render_additional_arguments_popup(model: &mut SelectCommandState, f: &mut Fra
     // If this popup is going to be opened, model.get_selected_command() returns Some(command).
     // So we can call unwrap() safely.
     let command = model.get_selected_command().unwrap();
+    let (border_style, border_type) = model.theme.border(true);
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_type(BORDER_STYLE_SELECTED)
-        .border_style(Style::default().fg(FG_COLOR_SELECTED))
+        .border_type(border_type)
+        .border_style(border_style)
         .title(format!(" 👋 Pass additional arguments to `{}`", command));
+    let block = match &model.additional_arguments_popup_state.as_ref().unwrap().error {
+        Some(e) => block.title_bottom(Line::from(format!(" ⚠️ {} ", e)).style(model.theme.error())),
+        None => block,
+    };
 
     let area = popup_area(f.area(), 60, 3);
     // This clears out the background which is needed to allow
@@ -379,39 +380,272 @@ fn render_additional_arguments_popup(model: &mut SelectCommandState, f: &mut Fra
     f.render_widget(&additional_arguments_popup_state.arguments_text_area.0, area);
 }
 
+fn render_confirm_popup(model: &mut SelectCommandState, f: &mut Frame) {
+    let Some(confirm_popup_state) = &model.confirm_popup_state else {
+        return;
+    };
+    let (border_style, border_type) = model.theme.border(true);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(border_type)
+        .border_style(border_style)
+        .title(format!(" ⚠️ Confirm to execute `{}` ", confirm_popup_state.command))
+        .padding(ratatui::widgets::Padding::new(1, 1, 0, 0));
+
+    let area = popup_area(f.area(), 60, 3);
+    // This clears out the background which is needed to allow
+    // overdrawing
+    f.render_widget(Clear, area);
+    let message = Paragraph::new(format!("{} [y/N]", confirm_popup_state.message)).block(block);
+    f.render_widget(message, area);
+}
+
+fn render_vars_popup(model: &mut SelectCommandState, f: &mut Frame) {
+    let Some(vars_popup_state) = &model.vars_popup_state else {
+        return;
+    };
+
+    // Each variable has its own input box whose height is 3(border + input line + border).
+    let area = popup_area(f.area(), 60, vars_popup_state.vars.len() as u16 * 3);
+    // This clears out the background which is needed to allow
+    // overdrawing
+    f.render_widget(Clear, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vars_popup_state.vars.iter().map(|_| Constraint::Length(3)))
+        .split(area);
+    for (index, ((var, text_area), chunk)) in vars_popup_state.vars.iter().zip(chunks.iter()).enumerate() {
+        let (border_style, border_type) = model.theme.border(index == vars_popup_state.selected);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(border_type)
+            .border_style(border_style)
+            .title(format!(" 📝 {} ", var));
+        let block = match &vars_popup_state.error {
+            Some(e) if index == vars_popup_state.selected => {
+                block.title_bottom(Line::from(format!(" ⚠️ {} ", e)).style(model.theme.error()))
+            }
+            _ => block,
+        };
+        let mut text_area = text_area.clone();
+        text_area.0.set_block(block);
+        f.render_widget(&text_area.0, *chunk);
+    }
+}
+
 fn render_hint_block(model: &mut SelectCommandState, f: &mut Frame, chunk: ratatui::layout::Rect) {
-    let hint_text = if model.is_additional_arguments_popup_opened() {
-        "Execute the selected command: <enter> | Passing additional arguments: (type any character) | Close the popup window: <esc>"
+    let hint = Span::styled(hint_text(model), model.theme.selected());
+
+    let block = Block::default().padding(ratatui::widgets::Padding::new(2, 2, 0, 0));
+    let key_notes_footer = Paragraph::new(hint).wrap(Wrap { trim: true }).block(block);
+
+    f.render_widget(key_notes_footer, chunk);
+}
+
+// hint_text builds the hint of the keys from the keymap. The actions without keys are omitted.
+fn hint_text(model: &SelectCommandState) -> String {
+    use keymap::Action::*;
+
+    let keymap = &model.keymap;
+    let select = |bindings: &keymap::Bindings| match (bindings.hint(Previous), bindings.hint(Next)) {
+        (Some(previous), Some(next)) => Some(format!("{}, {}", previous, next)),
+        (previous, next) => previous.or(next),
+    };
+    let hints: Vec<(&str, Option<String>)> = if model.is_confirm_popup_opened() {
+        vec![
+            ("Execute the command", Some("y".to_string())),
+            ("Cancel", Some(with_key(&with_key("n", keymap.popup.hint(Close)), keymap.popup.hint(Submit)))),
+        ]
+    } else if model.is_vars_popup_opened() {
+        vec![
+            ("Execute the command", keymap.popup.hint(Submit)),
+            ("Move to next variable", keymap.popup.hint(Next)),
+            ("Move to previous variable", keymap.popup.hint(Previous)),
+            ("Close the popup window", keymap.popup.hint(Close)),
+        ]
+    } else if model.is_additional_arguments_popup_opened() {
+        vec![
+            ("Execute the selected command", keymap.popup.hint(Submit)),
+            ("Passing additional arguments", Some("(type any character)".to_string())),
+            ("Close the popup window", keymap.popup.hint(Close)),
+        ]
     } else {
         match model.current_pane {
-            CurrentPane::Main => {
-                "Execute the selected command: <enter> | Select command: ↑(<c-p>)/↓(<c-n>) | Narrow down command: (type any character) | Quit: <c-c>/<esc> | Move to next tab: <tab> | Copy command to clipboard: <c-y> | Pass additional arguments: <c-o>"
-            }
-            CurrentPane::History => {
-                "Execute the selected command: <enter> | Select command: ↑(<c-p>)/↓(<c-n>) | Quit: <c-c>/q/<esc> | Move to next tab: <tab> | Copy command to clipboard: <c-y> | Pass additional arguments: <c-o>"
-            }
+            CurrentPane::Main => vec![
+                ("Execute the selected command", keymap.main.hint(Execute)),
+                ("Select command", select(&keymap.main)),
+                ("Narrow down command", Some("(type any character)".to_string())),
+                ("Quit", keymap.main.hint(Quit)),
+                ("Move to next tab", keymap.main.hint(NextPane)),
+                ("Copy command to clipboard", keymap.main.hint(CopyCommand)),
+                ("Pass additional arguments", keymap.main.hint(AdditionalArguments)),
+            ],
+            CurrentPane::History => match model.history_view {
+                HistoryView::Project => vec![
+                    ("Execute the selected command", keymap.history.hint(Execute)),
+                    ("Select command", select(&keymap.history)),
+                    ("Quit", keymap.history.hint(Quit)),
+                    ("Move to next tab", keymap.history.hint(NextPane)),
+                    ("Copy command to clipboard", keymap.history.hint(CopyCommand)),
+                    ("Pass additional arguments", keymap.history.hint(AdditionalArguments)),
+                    ("Show global history", keymap.history.hint(ToggleHistoryView)),
+                ],
+                HistoryView::Global => vec![
+                    ("Execute the selected command in its directory", keymap.history.hint(Execute)),
+                    ("Select command", select(&keymap.history)),
+                    ("Quit", keymap.history.hint(Quit)),
+                    ("Move to next tab", keymap.history.hint(NextPane)),
+                    ("Copy command to clipboard", keymap.history.hint(CopyCommand)),
+                    ("Show history of this directory", keymap.history.hint(ToggleHistoryView)),
+                ],
+            },
         }
     };
-    let hint = Span::styled(hint_text, Style::default().fg(FG_COLOR_SELECTED));
 
-    let block = Block::default().padding(ratatui::widgets::Padding::new(2, 2, 0, 0));
-    let key_notes_footer = Paragraph::new(hint).wrap(Wrap { trim: true }).block(block);
+    hints
+        .into_iter()
+        .filter_map(|(description, keys)| keys.map(|keys| format!("{}: {}", description, keys)))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
 
-    f.render_widget(key_notes_footer, chunk);
+// with_key prepends the key which is always available(e.g. `y` of the confirm popup) to the hint of the keymap.
+fn with_key(key: &str, hint: Option<String>) -> String {
+    match hint {
+        Some(hint) => format!("{}/{}", key, hint),
+        None => key.to_string(),
+    }
+}
+
+// command_line builds a line of the commands list with the metadata(up to date or not, description).
+fn command_line(
+    command: &command::CommandWithPreview,
+    matched_indices: &[usize],
+    theme: &theme::Theme,
+) -> Line<'static> {
+    let mut spans = vec![];
+    if command.metadata.pinned {
+        spans.push(Span::raw("📌 "));
+    }
+    match &command.metadata.display_name {
+        // The original command is also shown since it is what is actually executed.
+        Some(display_name) => {
+            spans.push(Span::raw(display_name.clone()));
+            spans.push(Span::styled(" (", theme.not_selected()));
+            spans.extend(highlight_matches(
+                &command.to_string(),
+                matched_indices,
+                theme.not_selected(),
+                theme.matched(),
+            ));
+            spans.push(Span::styled(")", theme.not_selected()));
+        }
+        None => {
+            spans.extend(highlight_matches(&command.to_string(), matched_indices, Style::default(), theme.matched()))
+        }
+    }
+    if let Some(body) = &command.metadata.body {
+        spans.push(Span::styled(format!(" → {}", body), theme.not_selected()));
+    }
+    if command.metadata.is_up_to_date {
+        spans.push(Span::styled(" ✓ up to date", theme.selected()));
+    }
+    if let Some(description) = &command.metadata.description {
+        spans.push(Span::styled(format!("  {}", description), theme.not_selected()));
+    }
+    Line::from(spans)
+}
+
+// highlight_matches splits the text into the spans of the matched characters and the others.
+// `matched_indices` are the indices of the characters, not bytes.
+fn highlight_matches(text: &str, matched_indices: &[usize], style: Style, matched_style: Style) -> Vec<Span<'static>> {
+    let mut spans: Vec<Span<'static>> = vec![];
+    let mut current = String::new();
+    let mut is_current_matched = false;
+    for (i, c) in text.chars().enumerate() {
+        let is_matched = matched_indices.contains(&i);
+        if is_matched != is_current_matched && !current.is_empty() {
+            let style = if is_current_matched {
+                style.patch(matched_style)
+            } else {
+                style
+            };
+            spans.push(Span::styled(std::mem::take(&mut current), style));
+        }
+        is_current_matched = is_matched;
+        current.push(c);
+    }
+    if !current.is_empty() {
+        let style = if is_current_matched {
+            style.patch(matched_style)
+        } else {
+            style
+        };
+        spans.push(Span::styled(current, style));
+    }
+    spans
+}
+
+// history_line shows the command with the result of the last execution.
+// e.g. `make build ✗ exit 2  5m ago · 1.2s`
+fn history_line(command: &histories::HistoryCommand, now: u64, theme: &theme::Theme) -> Line<'static> {
+    let mut spans = vec![Span::raw(command::CommandForExec::from(command.clone()).to_string())];
+    let Some(execution) = &command.execution else {
+        return Line::from(spans);
+    };
+
+    if command.is_failed() {
+        spans.push(Span::styled(format!(" ✗ exit {}", execution.exit_code.unwrap_or_default()), theme.error()));
+    }
+    let mut details = vec![format_elapsed_time(now.saturating_sub(execution.executed_at))];
+    if let Some(duration_ms) = execution.duration_ms {
+        details.push(format_duration(duration_ms));
+    }
+    spans.push(Span::styled(format!("  {}", details.join(" · ")), theme.not_selected()));
+    Line::from(spans)
 }
 
-fn commands_block(
-    title: &str,
-    narrowed_down_commands: Vec<command::CommandForExec>,
+// with_dir appends the directory where the command was executed to the line of the history.
+// e.g. `make build  5m ago · 1.2s  ~/code/app`
+fn with_dir(mut line: Line<'static>, dir: &Path, home_dir: Option<&Path>, theme: &theme::Theme) -> Line<'static> {
+    // The home directory is abbreviated as `~` to save the width.
+    let dir = match home_dir.and_then(|home_dir| dir.strip_prefix(home_dir).ok()) {
+        Some(relative_path) => Path::new("~").join(relative_path),
+        None => dir.to_path_buf(),
+    };
+    line.push_span(Span::styled(format!("  {}", dir.display()), theme.directory()));
+    line
+}
+
+fn format_elapsed_time(secs: u64) -> String {
+    match secs {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+fn format_duration(ms: u64) -> String {
+    match ms {
+        0..1000 => format!("{}ms", ms),
+        1000..60000 => format!("{:.1}s", ms as f64 / 1000.0),
+        _ => format!("{}m {}s", ms / 60000, ms % 60000 / 1000),
+    }
+}
+
+fn commands_block<'a>(
+    title: &'a str,
+    narrowed_down_commands: Vec<Line<'a>>,
     is_current: bool,
-    is_additional_arguments_popup_opened: bool,
-) -> List<'_> {
-    let (fg_color, border_style) =
-        color_and_border_style_for_selectable(is_current, is_additional_arguments_popup_opened);
+    is_popup_opened: bool,
+    theme: &theme::Theme,
+) -> List<'a> {
+    let (border_style, border_type) = border_for_selectable(theme, is_current, is_popup_opened);
 
     let list: Vec<ListItem> = narrowed_down_commands
         .into_iter()
-        .map(|command| ListItem::new(command.to_string()).style(Style::default()))
+        .map(|command| ListItem::new(command).style(Style::default()))
         .collect();
 
     List::new(list)
@@ -421,19 +655,158 @@ fn commands_block(
                 .title(title)
                 .title_style(TITLE_STYLE)
                 .borders(Borders::ALL)
-                .border_type(border_style)
-                .border_style(Style::default().fg(fg_color))
+                .border_type(border_type)
+                .border_style(border_style)
                 .style(Style::default())
                 .padding(ratatui::widgets::Padding::new(2, 0, 0, 0)),
         )
-        .highlight_style(Style::default().fg(FG_COLOR_SELECTED))
+        .highlight_style(theme.highlight())
         .highlight_symbol("> ")
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use std::time::Duration;
+
+    #[test]
+    fn test_history_line() {
+        const NOW: u64 = 100_000;
+        struct Case {
+            title: &'static str,
+            execution: Option<histories::Execution>,
+            expected: &'static str,
+        }
+        let cases = vec![
+            Case {
+                title: "history written by older versions",
+                execution: None,
+                expected: "make build",
+            },
+            Case {
+                title: "succeeded",
+                execution: Some(histories::Execution {
+                    executed_at: NOW - 300,
+                    duration_ms: Some(1234),
+                    exit_code: Some(0),
+                    run_count: 1,
+                    frecency: 1.0,
+                }),
+                expected: "make build  5m ago · 1.2s",
+            },
+            Case {
+                title: "failed",
+                execution: Some(histories::Execution {
+                    executed_at: NOW - 10,
+                    duration_ms: Some(61500),
+                    exit_code: Some(2),
+                    run_count: 1,
+                    frecency: 1.0,
+                }),
+                expected: "make build ✗ exit 2  just now · 1m 1s",
+            },
+            Case {
+                title: "executed in exec mode",
+                execution: Some(histories::Execution {
+                    executed_at: NOW - 7200,
+                    duration_ms: None,
+                    exit_code: None,
+                    run_count: 1,
+                    frecency: 1.0,
+                }),
+                expected: "make build  2h ago",
+            },
+        ];
+
+        for case in cases {
+            let command = histories::HistoryCommand {
+                execution: case.execution,
+                ..histories::HistoryCommand::new(crate::model::runner_type::RunnerType::Make, vec!["build".to_string()])
+            };
+            assert_eq!(
+                case.expected,
+                history_line(&command, NOW, &theme::Theme::default()).to_string(),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
+
+    #[test]
+    fn test_with_dir() {
+        struct Case {
+            title: &'static str,
+            dir: &'static str,
+            expected: &'static str,
+        }
+        let cases = vec![
+            Case {
+                title: "under the home directory",
+                dir: "/home/user/code/app",
+                expected: "make build  ~/code/app",
+            },
+            Case {
+                title: "outside of the home directory",
+                dir: "/tmp/app",
+                expected: "make build  /tmp/app",
+            },
+        ];
+
+        for case in cases {
+            let line = with_dir(
+                Line::from("make build"),
+                Path::new(case.dir),
+                Some(Path::new("/home/user")),
+                &theme::Theme::default(),
+            );
+            assert_eq!(case.expected, line.to_string(), "\nFailed: 🚨{:?}🚨\n", case.title,);
+        }
+    }
+
+    #[test]
+    fn test_highlight_matches() {
+        struct Case {
+            title: &'static str,
+            text: &'static str,
+            matched_indices: Vec<usize>,
+            // (content, is_matched)
+            expected: Vec<(&'static str, bool)>,
+        }
+        let cases = vec![
+            Case {
+                title: "no matches",
+                text: "make build",
+                matched_indices: vec![],
+                expected: vec![("make build", false)],
+            },
+            Case {
+                title: "consecutive matches are in one span",
+                text: "make build",
+                matched_indices: vec![5, 6, 9],
+                expected: vec![("make ", false), ("bu", true), ("il", false), ("d", true)],
+            },
+            Case {
+                title: "indices are of characters",
+                text: "make ビルド",
+                matched_indices: vec![0, 6],
+                expected: vec![("m", true), ("ake ビ", false), ("ル", true), ("ド", false)],
+            },
+        ];
+
+        let matched_style = Style::default().add_modifier(Modifier::BOLD);
+        for case in cases {
+            assert_eq!(
+                case.expected
+                    .into_iter()
+                    .map(|(content, is_matched)| {
+                        Span::styled(content, if is_matched { matched_style } else { Style::default() })
+                    })
+                    .collect::<Vec<_>>(),
+                highlight_matches(case.text, &case.matched_indices, Style::default(), matched_style),
+                "\nFailed: 🚨{:?}🚨\n",
+                case.title,
+            );
+        }
+    }
 
     #[test]
     fn test_determine_rendering_position() {
@@ -452,54 +825,4 @@ mod test {
         assert_eq!(start, 0);
         assert_eq!(end, 9);
     }
-
-    const HIGHLIGHT_THRESHOLD: Duration = Duration::from_millis(500);
-    const NORMAL_MAKEFILE_LINES: &[&str] = &[
-        ".PHONY: build",
-        "build:",
-        "\t@cargo build --verbose --release",
-        "test: tool-test",
-        "\trm -rf $(TEST_HISTORY_DIR)",
-        "\tRUST_BACKTRACE=full cargo nextest run",
-    ];
-    const PATHOLOGICAL_LINE: &str = "\t$(eval RESOLVED_TARGETS := $(shell bash resolve.sh $(DEPENDENCY_SERVICES)))";
-    fn load_makefile_syntax() -> (SyntaxSet, syntect::highlighting::Theme) {
-        let ss = SyntaxSet::load_defaults_newlines();
-        let ts = ThemeSet::load_defaults();
-        let theme = ts.themes["base16-ocean.dark"].clone();
-        (ss, theme)
-    }
-
-    #[test]
-    fn highlight_normal_lines_within_threshold() {
-        let (ss, theme) = load_makefile_syntax();
-        let syntax = ss
-            .find_syntax_by_extension("mk")
-            .unwrap_or_else(|| ss.find_syntax_plain_text());
-
-        let start = std::time::Instant::now();
-        for line in NORMAL_MAKEFILE_LINES {
-            let mut h = HighlightLines::new(syntax, &theme);
-            let _ = h.highlight_line(line, &ss);
-        }
-        let elapsed = start.elapsed();
-
-        assert!(
-            elapsed < HIGHLIGHT_THRESHOLD,
-            "Highlighting normal Makefile lines took {elapsed:?}, which exceeds the threshold of {HIGHLIGHT_THRESHOLD:?}",
-        );
-    }
-
-    #[test]
-    fn highlight_pathological_line_with_skip_guard() {
-        let start = std::time::Instant::now();
-        // Reproduce the skip guard logic from render_preview_block.
-        let _spans: Vec<Span> = vec![Span::raw(PATHOLOGICAL_LINE.to_string())];
-        let elapsed = start.elapsed();
-
-        assert!(
-            elapsed < HIGHLIGHT_THRESHOLD,
-            "Highlighting pathological line with skip guard took {elapsed:?}, which exceeds the threshold of {HIGHLIGHT_THRESHOLD:?}",
-        );
-    }
 }
@@ -1,7 +1,18 @@
 use anyhow::Result;
 use futures::future::BoxFuture;
+use std::{path::PathBuf, process};
 
 pub trait Usecase: Send + Sync {
     fn command_str(&self) -> Vec<&'static str>;
-    fn run(&self) -> BoxFuture<'_, Result<()>>;
+    // run returns the exit status of the executed command. It returns None if no command was executed.
+    fn run(&self, options: Options) -> BoxFuture<'_, Result<Option<process::ExitStatus>>>;
+}
+
+/// Options are the options which can be specified with any subcommand.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Options {
+    /// `--history-file <path>`
+    pub history_file: Option<PathBuf>,
+    /// `--no-cache`
+    pub no_cache: bool,
 }
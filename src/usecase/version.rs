@@ -1,7 +1,7 @@
-use crate::usecase::usecase_main::Usecase;
+use crate::usecase::usecase_main::{Options, Usecase};
 use anyhow::Result;
 use futures::{FutureExt, future::BoxFuture};
-use std::env;
+use std::{env, process};
 
 pub struct Version;
 
@@ -16,10 +16,10 @@ impl Usecase for Version {
         vec!["--version", "-v", "version"]
     }
 
-    fn run(&self) -> BoxFuture<'_, Result<()>> {
+    fn run(&self, _: Options) -> BoxFuture<'_, Result<Option<process::ExitStatus>>> {
         async {
             println!("v{}", get_version());
-            Ok(())
+            Ok(None)
         }
         .boxed()
     }